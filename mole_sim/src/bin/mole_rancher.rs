@@ -0,0 +1,150 @@
+// Command line tools for checking levels without opening the game
+//
+// mole_rancher verify <level> <replay> [--assets <dir>] [--time-limit <seconds>]
+//     Plays a replay headlessly and reports whether it completes the
+//     level, exiting with 0 on a pass, 1 on a fail and 2 on an error.
+//     Replays with inputs the game could not have made fail unplayed
+//
+// mole_rancher check-locales [--assets <dir>]
+//     Reports the keys each language's string table is missing compared
+//     to the fallback language, exiting with 0 if every table is complete,
+//     1 if any are missing keys and 2 on an error
+
+// Import file reading and the exit code for the command line
+use std::{env, fs, path::{Path, PathBuf}, process::ExitCode};
+// Import the headless simulation
+use mole_sim::{Simulation, components::*, locale::*};
+
+const USAGE: &str = "usage: mole_rancher verify <level> <replay> [--assets <dir>] [--time-limit <seconds>]
+       mole_rancher check-locales [--assets <dir>]";
+
+// Simulated seconds a replay is given to complete its level
+const DEFAULT_TIME_LIMIT: f32 = 600.0;
+
+struct VerifyArgs {
+	level: usize,
+	replay: PathBuf,
+	assets: PathBuf,
+	time_limit: f32,
+}
+
+fn main() -> ExitCode {
+	let args: Vec<String> = env::args().skip(1).collect();
+	let result = match args.first().map(String::as_str) {
+		Some("verify") => parse_verify_args(&args[1..]).and_then(|args| verify(&args)),
+		Some("check-locales") => parse_assets_arg(&args[1..]).and_then(|assets| check_locales(&assets)),
+		_ => Err(USAGE.to_string()),
+	};
+	match result {
+		Ok(true) => ExitCode::SUCCESS,
+		Ok(false) => ExitCode::from(1),
+		Err(error) => {
+			eprintln!("{}", error);
+			ExitCode::from(2)
+		},
+	}
+}
+
+fn parse_verify_args(
+	args: &[String],
+) -> Result<VerifyArgs, String> {
+	let mut positional = Vec::new();
+	let mut assets = PathBuf::from("assets");
+	let mut time_limit = DEFAULT_TIME_LIMIT;
+	let mut iter = args.iter();
+	while let Some(arg) = iter.next() {
+		match arg.as_str() {
+			"--assets" => assets = iter.next().ok_or(USAGE)?.into(),
+			"--time-limit" => time_limit = iter.next().ok_or(USAGE)?.parse().map_err(|_| "time limit must be a number of seconds".to_string())?,
+			_ => positional.push(arg),
+		}
+	}
+	let [level, replay] = positional[..] else {
+		return Err(USAGE.to_string());
+	};
+	let level = level.parse().map_err(|_| format!("{} is not a level number", level))?;
+	if level >= NUMBER_OF_LEVELS {
+		return Err(format!("level {} does not exist, there are {} levels", level, NUMBER_OF_LEVELS));
+	}
+	Ok(VerifyArgs{
+		level,
+		replay: replay.into(),
+		assets,
+		time_limit,
+	})
+}
+
+fn parse_assets_arg(
+	args: &[String],
+) -> Result<PathBuf, String> {
+	match args {
+		[] => Ok(PathBuf::from("assets")),
+		[flag, assets] if flag == "--assets" => Ok(assets.into()),
+		_ => Err(USAGE.to_string()),
+	}
+}
+
+// Runs the replay until the level is complete, the level's own limits
+// fail it or the time limit is reached, using the same win check as
+// the game's product chambers
+fn verify(
+	args: &VerifyArgs,
+) -> Result<bool, String> {
+	let bytes = fs::read(&args.replay).map_err(|error| format!("{}: {}", args.replay.display(), error))?;
+	let replay = Replay::from_ron(&bytes).map_err(|error| format!("{}: {}", args.replay.display(), error))?;
+	if replay.level != args.level {
+		return Err(format!("{} is a replay of level {}, not level {}", args.replay.display(), replay.level, args.level));
+	}
+	let mut simulation = Simulation::from_assets(&args.assets, args.level, replay.seed)?;
+	if let Err(error) = simulation.play(replay) {
+		println!("{}: level {} FAIL", args.replay.display(), args.level);
+		println!("Illegal input: {}", error);
+		return Ok(false);
+	}
+	let max_steps = (args.time_limit / PHYSICS_TIMESTEP).ceil() as u64;
+	let passed = simulation.run(max_steps);
+	println!("{}: level {} {}", args.replay.display(), args.level, if passed {"PASS"} else {"FAIL"});
+	println!("Simulated time: {:.2} s", simulation.elapsed_secs());
+	println!("Total cost: {} c", simulation.cost());
+	if passed {
+		println!("Stars: {} / {}", simulation.level.stars(simulation.elapsed_secs(), simulation.cost()), MAX_STARS);
+	}
+	match simulation.failed() {
+		Some(FailReason::OverBudget) => println!("Failed: over the cost limit"),
+		Some(FailReason::OutOfTime) => println!("Failed: out of time"),
+		None => (),
+	}
+	Ok(passed)
+}
+
+// Checks every language against the fallback, listing what each is
+// missing along with any keys the game does not use
+fn check_locales(
+	assets: &Path,
+) -> Result<bool, String> {
+	let read = |language: &str| {
+		let path = get_string_table_path(language);
+		let bytes = fs::read(assets.join(&path)).map_err(|error| format!("{}: {}", path, error))?;
+		StringTable::from_ron(&bytes).map_err(|error| format!("{}: {}", path, error))
+	};
+	let fallback = read(FALLBACK_LANGUAGE)?;
+	let mut complete = true;
+	for (language, name) in LANGUAGES.iter().filter(|(language, _)| *language != FALLBACK_LANGUAGE) {
+		let report = check_string_table(&read(language)?, &fallback);
+		println!("{} ({}): {}", language, name, if report.is_complete() {"COMPLETE"} else {"INCOMPLETE"});
+		for (heading, keys) in [
+			("Missing", &report.missing),
+			("Different placeholders", &report.mismatched),
+			("Unused", &report.unknown),
+		] {
+			if !keys.is_empty() {
+				println!("{} ({}):", heading, keys.len());
+				for key in keys.iter() {
+					println!("    {}", key);
+				}
+			}
+		}
+		complete &= report.is_complete();
+	}
+	Ok(complete)
+}
@@ -0,0 +1,574 @@
+// Import Bevy game engine essentials
+use bevy::{prelude::*, reflect::{TypePath, TypeUuid}, utils::HashMap};
+// Import an ordered map so string tables are listed by key
+use std::collections::BTreeMap;
+// Import serde for reading the level, molecule
+// and reaction data files
+use serde::{Serialize, Deserialize};
+// Import ChaCha for a seedable random number stream
+use rand_chacha::ChaCha8Rng;
+
+// CONTENTS
+// - Level Definitions
+// - Molecule Registry
+// - Reaction Table
+// - String Tables
+// - Replays
+// - Constants
+// - Enums
+// - Structs
+// - System Sets
+// - Components
+// - Bundles
+// - Resources
+// - Events
+// - Path Helper Functions
+
+// LEVEL DEFINITIONS
+// Loaded from assets/levels, reactors are given an ID
+// based on their position in the list
+#[derive(Clone, Serialize, Deserialize, TypeUuid, TypePath)]
+#[uuid = "4d3b5a0c-2f6e-4b8a-9c51-7e0d8a6f1b23"]
+pub struct LevelDefinition {
+	pub reactors: Vec<ReactorDefinition>,
+	pub goal: WinCondition,
+	pub goal_text: String,
+	// Levels are failed once launches cost more than the cost
+	// limit, or the time limit in seconds runs out
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub cost_limit: Option<usize>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub time_limit: Option<f32>,
+	// Completing the level earns a star, with one more each for
+	// finishing within the par time in seconds and the par cost
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub par_time: Option<f32>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub par_cost: Option<usize>,
+	pub available_molecules: Vec<usize>,
+	pub initial_zoom: f32,
+	pub intro_text: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReactorDefinition {
+	pub reactor_type: ReactorType,
+	pub input_chamber: bool,
+	pub product_chamber: bool,
+	// For rectangles, limits between 0.0 and 1.0 represent centre to edge
+	// For circles, limits between 0.0 and 1.0 represent top, going anticlockwise, back to the top
+	#[serde(default = "default_launch_tube_limits")]
+	pub launch_tube_limits: Limits,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub connections: Vec<ConnectionDefinition>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub molecules: Vec<InitialMolecule>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ConnectionDefinition {
+	pub direction: Vec2,
+	pub connection_id: usize,
+	pub intake: bool,
+	// Molecule types accepted by an intake, outlets ignore this
+	#[serde(default)]
+	pub filter: Vec<usize>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InitialMolecule {
+	pub index: usize,
+	pub location: Vec2,
+	#[serde(default)]
+	pub velocity: Vec2,
+}
+
+fn default_launch_tube_limits() -> Limits {
+	Limits(1.0, 1.0)
+}
+
+
+// MOLECULE REGISTRY
+// Loaded from assets/data, molecules are indexed
+// by their position in the list
+#[derive(Resource, Default, Clone, Serialize, Deserialize, TypeUuid, TypePath)]
+#[uuid = "b6f1c2d8-93a4-4e27-8d5b-0a7c4e19f362"]
+pub struct MoleculeRegistry {
+	pub molecules: Vec<MoleculeDefinition>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MoleculeDefinition {
+	pub name: String,
+	// Letter drawn over the molecule for players who
+	// cannot tell its colour apart
+	pub glyph: String,
+	pub sprite: String,
+	pub radius: f32,
+	pub mass: f32,
+	pub cost: usize,
+	pub launch_speed: f32,
+	// Range of seconds before an unstable molecule decays,
+	// stable molecules leave this out
+	#[serde(default)]
+	pub decay: Option<Limits>,
+	pub tooltip: String,
+	pub locked_tooltip: String,
+	pub note: usize,
+	// One colour for each palette
+	pub colors: Vec<CatalogueColor>,
+}
+
+// Written the same way as a Bevy colour, but kept as plain
+// numbers so the catalogue can be read without the renderer
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum CatalogueColor {
+	Rgba{
+		red: f32,
+		green: f32,
+		blue: f32,
+		alpha: f32,
+	},
+}
+
+
+// REACTION TABLE
+// Loaded from assets/data, the lookups are built once
+// the file has been validated
+#[derive(Resource, Default, Clone, Serialize, Deserialize, TypeUuid, TypePath)]
+#[uuid = "e2a9d4f7-5c18-4b63-a0e6-3f8b71c95d04"]
+pub struct ReactionTable {
+	#[serde(default)]
+	pub reactions: Vec<ReactionDefinition>,
+	#[serde(default)]
+	pub decays: Vec<DecayDefinition>,
+	// Positions in the lists above, pairs are keyed
+	// with the smaller molecule index first
+	#[serde(skip)]
+	pub reaction_lookup: HashMap<(usize, usize), Vec<usize>>,
+	#[serde(skip)]
+	pub decay_lookup: HashMap<usize, Vec<usize>>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReactionDefinition {
+	pub reactants: (usize, usize),
+	pub products: Vec<usize>,
+	#[serde(default = "default_reaction_limits")]
+	pub temperature: Limits,
+	#[serde(default = "default_reaction_limits")]
+	pub pressure: Limits,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DecayDefinition {
+	pub reactant: usize,
+	pub products: Vec<usize>,
+	#[serde(default = "default_reaction_limits")]
+	pub temperature: Limits,
+	#[serde(default = "default_reaction_limits")]
+	pub pressure: Limits,
+}
+
+fn default_reaction_limits() -> Limits {
+	Limits(0.0, 1.0)
+}
+
+
+// STRING TABLES
+// Loaded from assets/locale, one per language. Text shown
+// by the game is looked up here by its key, and values may
+// hold numbered placeholders such as {0} filled in by the game
+#[derive(Resource, Default, Clone, Serialize, Deserialize, TypeUuid, TypePath)]
+#[uuid = "7c0e4f92-1b6d-4a3e-8f25-d93a6b0c17e8"]
+pub struct StringTable {
+	// Name of the language in that language, shown in the settings
+	pub language: String,
+	pub strings: BTreeMap<String, String>,
+}
+
+// Text for the selected language, filled in from the fallback
+// language where the selected one is missing a key
+#[derive(Resource, Default)]
+pub struct Locale {
+	pub language: String,
+	pub strings: HashMap<String, String>,
+}
+
+// REPLAYS
+// Every input made while playing a level, stamped with the
+// simulation step it was applied on. Together with the seed
+// this is enough to play the attempt back exactly
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Replay {
+	pub level: usize,
+	pub seed: u64,
+	pub inputs: Vec<RecordedInput>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct RecordedInput {
+	pub tick: u64,
+	pub input: ReactorInput,
+}
+
+// Launches, spawners and condition changes affect the simulation,
+// the rest are only needed to show the attempt being played back
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum ReactorInput {
+	MoveLaunchTube{
+		reactor_id: usize,
+		location: Vec2,
+		// Angle of the tube in radians, and its tilt in degrees
+		// away from pointing straight into the reactor
+		angle: f32,
+		tilt: f32,
+	},
+	Launch{
+		reactor_id: usize,
+		index: usize,
+		location: Vec2,
+		velocity: Vec2,
+	},
+	SetConditions{
+		reactor_id: usize,
+		temperature: f32,
+		pressure: f32,
+	},
+	PlaceSpawner{
+		reactor_id: usize,
+		index: usize,
+		location: Vec2,
+		// Angle of the spawner in radians, facing into the reactor
+		angle: f32,
+	},
+	SelectReactor(usize),
+	SelectMolecule(usize),
+}
+
+
+// CONSTANTS
+// Physics
+pub const PHYSICS_TIMESTEP: f32 = 1.0 / 60.0;
+pub const PHYSICS_SUBSTEPS: usize = 4;
+
+// Connections
+pub const CONNECTION_IN_WIDTH: f32 = 189.0;
+pub const CONNECTION_Z: f32 = 910.0;
+
+// Launch tubes, tilted up to the limit in degrees either
+// side of pointing straight into the reactor
+pub const LAUNCH_TUBE_WIDTH: f32 = 146.0;
+pub const LAUNCH_TUBE_MAX_TILT: f32 = 45.0;
+
+// General Parameters
+pub const TOTAL_MOLECULE_TYPES: usize = 18;
+pub const NUMBER_OF_PALETTES: usize = 7;
+pub const MOLECULE_CAP: usize = 800;
+pub const MOLECULE_Z: f32 = 500.0;
+pub const SPAWNER_Z: f32 = 400.0;
+pub const SPAWNER_INTERVAL: f32 = 3.0;
+
+pub const FRICTION: f32 = 0.05;
+
+pub const WIN_COUNTDOWN_LENGTH: f32 = 3.0;
+
+pub const NUMBER_OF_LEVELS: usize = 31;
+
+pub const MAX_STARS: usize = 3;
+
+// Localization
+// Code and name of every language with a string table, any
+// text missing from a table is taken from the fallback
+pub const LANGUAGES: [(&str, &str); 2] = [
+	("en", "English"),
+	("es", "Español"),
+];
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+
+// ENUMS
+// Goals are trees, with quantities counted over every product
+// chamber unless a part of the goal is limited to one of them
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum WinCondition {
+	// Quantity, molecule
+	GreaterThan(usize, usize),
+	LessThan(usize, usize),
+	Exactly(usize, usize),
+	// At least this many of the first molecule for every one
+	// of the second, with at least one of the first held
+	Ratio(usize, usize, f32),
+	// Reactor ID of a product chamber, goal counted in it alone
+	InChamber(usize, Box<WinCondition>),
+	All(Vec<WinCondition>),
+	Any(Vec<WinCondition>),
+	// Quantity, molecule, connection ID, seconds. Molecules
+	// leaving the outlets with that ID in the last few seconds
+	Delivered(usize, usize, usize, f32),
+	// Quantity, molecule, seconds. Molecules arriving in the
+	// product chambers through outlets in the last few seconds
+	Received(usize, usize, f32),
+	// Seconds the goal must be held without a break, a goal
+	// not wrapped in one is held for WIN_COUNTDOWN_LENGTH
+	Sustain(f32, Box<WinCondition>),
+}
+
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum FailReason {
+	OverBudget,
+	OutOfTime,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum ReactorType {
+	Rectangle{
+		origin: Vec2,
+		dimensions: Dimensions,
+	},
+	Circle{
+		origin: Vec2,
+		radius: f32,
+	},
+}
+
+// Unstable molecules decay using the rules in the
+// reaction table once their timer runs out
+pub enum Lifetime {
+	Unstable(Timer),
+	Stable,
+}
+
+
+// STRUCTS
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Dimensions {
+	pub width: f32,
+	pub height: f32,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Limits(pub f32, pub f32);
+
+// A molecule leaving an outlet, on the step it left
+#[derive(Clone, Copy, Debug)]
+pub struct FlowRecord {
+	pub tick: u64,
+	pub connection_id: usize,
+	pub reactor_id: usize,
+	pub index: usize,
+}
+
+// Molecules counted for one of the goal's sub-goals, the second
+// count is only used by ratios
+#[derive(Clone, Copy, Default, Debug)]
+pub struct SubGoalProgress {
+	pub count: usize,
+	pub other_count: usize,
+	pub met: bool,
+}
+
+
+// SYSTEM SETS
+// Every simulation system runs in this set, so the game
+// can decide when the simulation is allowed to run
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationSet;
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MoleculeMovementSet;
+
+
+// COMPONENTS
+#[derive(Component)]
+pub struct Connection {
+	pub reactor_id: usize,
+	pub connection_id: usize,
+	pub intake: bool,
+	pub filter: [bool; TOTAL_MOLECULE_TYPES],
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct ReactorInfo {
+	pub input_chamber: bool,
+	pub product_chamber: bool,
+	pub reactor_type: ReactorType,
+	pub reactor_id: usize,
+}
+
+#[derive(Component)]
+pub struct ReactorCondition{
+	pub temperature: f32,
+	pub pressure: f32,
+}
+
+#[derive(Component)]
+pub struct Molecule(pub Lifetime);
+
+#[derive(Component, Clone, Copy)]
+pub struct MoleculeInfo {
+	pub index: usize,
+	pub reacted: bool,
+	pub radius: f32,
+	pub mass: f32,
+}
+
+#[derive(Component, Clone, Copy)]
+pub struct Velocity(pub Vec2);
+
+// Drawn from the simulation's random stream when a molecule is
+// spawned. Molecules are handled in this order rather than the
+// order the ECS stores them in, which changes as the game adds
+// sprites, so a replay plays out the same with or without a window
+#[derive(Component, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SimOrder(pub u64);
+
+#[derive(Component)]
+pub struct SelectedMolecule;
+
+#[derive(Component)]
+pub struct MoleculeSpawnerInfo{
+	pub spawner_index: usize,
+	pub spawner_timer: Timer,
+}
+
+
+// BUNDLES
+// Everything the simulation needs for a molecule, the
+// game adds sprites and particle trails on top of this
+#[derive(Bundle)]
+pub struct MoleculeBundle {
+	pub molecule: Molecule,
+	pub info: MoleculeInfo,
+	pub reactor: ReactorInfo,
+	pub velocity: Velocity,
+	pub order: SimOrder,
+	pub transform: TransformBundle,
+	pub name: Name,
+}
+
+
+// RESOURCES
+// All randomness in the simulation is drawn from here, and it is
+// reseeded at the start of every level attempt so a run can be
+// reproduced exactly from its seed
+#[derive(Resource)]
+pub struct SimRng {
+	pub seed: u64,
+	// Used for every attempt instead of a random seed when set
+	pub pinned_seed: Option<u64>,
+	pub rng: ChaCha8Rng,
+}
+
+// Broad phase for molecule collisions, molecules are sorted
+// into square cells per reactor so only molecules in the same
+// or neighbouring cells are checked against each other
+#[derive(Resource)]
+pub struct SpatialHash {
+	pub enabled: bool,
+	pub cell_size: f32,
+	pub cells: HashMap<(usize, IVec2), Vec<Entity>>,
+}
+
+#[derive(Resource)]
+pub struct MoleculeCount {
+	pub total: usize,
+	pub cap: usize,
+}
+
+// Molecules which have come out of an outlet, replaced at the
+// start of every attempt and trimmed to what the goal looks at
+#[derive(Resource, Default)]
+pub struct MoleculeFlow(pub Vec<FlowRecord>);
+
+// Progress towards the goal of the level being played,
+// replaced at the start of every attempt
+#[derive(Resource)]
+pub struct LevelAttempt {
+	// Number of simulation steps since the attempt started
+	pub tick: u64,
+	pub goal: WinCondition,
+	pub goal_met: bool,
+	// One entry for each of the goal's sub-goals
+	pub sub_goals: Vec<SubGoalProgress>,
+	// Seconds each sustained part of the goal has been held for
+	pub sustained: Vec<f32>,
+	// Total cost of the molecules launched so far
+	pub cost: usize,
+	pub cost_limit: Option<usize>,
+	pub time_limit: Option<f32>,
+	pub failed: Option<FailReason>,
+	pub win_countdown: Timer,
+	pub complete: bool,
+}
+
+// Inputs waiting to be applied on the next simulation step,
+// the game pushes the player's inputs here
+#[derive(Resource, Default)]
+pub struct InputQueue(pub Vec<ReactorInput>);
+
+// Inputs applied so far in the current attempt
+#[derive(Resource, Default)]
+pub struct ReplayRecording(pub Replay);
+
+// While this exists the inputs come from a replay rather than
+// the player, and are queued once their step is reached
+#[derive(Resource)]
+pub struct ReplayPlayback {
+	pub replay: Replay,
+	pub next_input: usize,
+}
+
+
+// EVENTS
+#[derive(Event)]
+pub struct ConnectionEvent{
+	pub connection_id: usize,
+	pub m_info: MoleculeInfo,
+	pub r_info: ReactorInfo,
+	pub velocity: Vec2,
+	pub selected: bool,
+}
+
+// Sent whenever a molecule hits another molecule or a wall
+#[derive(Event)]
+pub struct CollisionEvent{
+	pub index: usize,
+	pub location: Vec2,
+}
+
+// Sent for every input once the simulation has applied it,
+// so playback can move the launch tubes and levers to match
+#[derive(Event)]
+pub struct ReactorInputEvent(pub ReactorInput);
+
+// Sent once the goal has been held for the whole win countdown
+#[derive(Event)]
+pub struct LevelCompleteEvent;
+
+// Sent once the level's cost or time limit has been passed
+#[derive(Event)]
+pub struct LevelFailedEvent(pub FailReason);
+
+
+// PATH HELPER FUNCTIONS
+// Relative to the assets folder
+pub fn get_molecule_registry_path() -> String {
+	"data/molecules.catalogue.ron".to_string()
+}
+
+pub fn get_reaction_table_path() -> String {
+	"data/reactions.table.ron".to_string()
+}
+
+pub fn get_level_path(
+	level: usize,
+) -> String {
+	format!("levels/level_{:02}.level.ron", level)
+}
+
+pub fn get_string_table_path(
+	language: &str,
+) -> String {
+	format!("locale/{}.strings.ron", language)
+}
@@ -0,0 +1,402 @@
+// Import Bevy game engine essentials
+use bevy::prelude::*;
+// Import components, resources, and events
+use crate::components::*;
+
+// Rejects level files which would otherwise fail later
+// on, such as molecule indices which do not exist
+pub fn validate_level(
+	level: &LevelDefinition,
+) -> Result<(), String> {
+	if level.reactors.is_empty() {
+		return Err("level has no reactors".to_string());
+	}
+	if !level.reactors.iter().any(|reactor| reactor.product_chamber) {
+		return Err("level has no product chamber".to_string());
+	}
+	for index in level.available_molecules.iter() {
+		if *index >= TOTAL_MOLECULE_TYPES {
+			return Err(format!("available molecule {} does not exist", index));
+		}
+	}
+	validate_goal(level, &level.goal)?;
+	if level.cost_limit == Some(0) {
+		return Err("level has a cost limit of nothing".to_string());
+	}
+	if let Some(time_limit) = level.time_limit {
+		if !(time_limit.is_finite() && time_limit > 0.0) {
+			return Err(format!("level has a time limit of {} seconds", time_limit));
+		}
+	}
+	if let Some(par_time) = level.par_time {
+		if !(par_time.is_finite() && par_time > 0.0) {
+			return Err(format!("level has a par time of {} seconds", par_time));
+		}
+	}
+	for (reactor_id, reactor) in level.reactors.iter().enumerate() {
+		for molecule in reactor.molecules.iter() {
+			if molecule.index >= TOTAL_MOLECULE_TYPES {
+				return Err(format!("reactor {} starts with molecule {} which does not exist", reactor_id, molecule.index));
+			}
+		}
+		for connection in reactor.connections.iter() {
+			if connection.direction == Vec2::ZERO {
+				return Err(format!("reactor {} has a connection with no direction", reactor_id));
+			}
+			for index in connection.filter.iter() {
+				if *index >= TOTAL_MOLECULE_TYPES {
+					return Err(format!("reactor {} filters molecule {} which does not exist", reactor_id, index));
+				}
+			}
+		}
+	}
+	Ok(())
+}
+
+fn validate_goal(
+	level: &LevelDefinition,
+	goal: &WinCondition,
+) -> Result<(), String> {
+	let check_molecule = |index: usize| if index >= TOTAL_MOLECULE_TYPES {
+		Err(format!("goal molecule {} does not exist", index))
+	} else {
+		Ok(())
+	};
+	match goal {
+		WinCondition::GreaterThan(_, index) | WinCondition::LessThan(_, index) | WinCondition::Exactly(_, index) => check_molecule(*index),
+		WinCondition::Ratio(index, other_index, ratio) => {
+			check_molecule(*index)?;
+			check_molecule(*other_index)?;
+			if !(ratio.is_finite() && *ratio > 0.0) {
+				return Err(format!("goal ratio {} must be above zero", ratio));
+			}
+			Ok(())
+		},
+		WinCondition::InChamber(reactor_id, goal) => {
+			if !level.reactors.get(*reactor_id).is_some_and(|reactor| reactor.product_chamber) {
+				return Err(format!("goal counts reactor {} which is not a product chamber", reactor_id));
+			}
+			validate_goal(level, goal)
+		},
+		WinCondition::All(goals) | WinCondition::Any(goals) => {
+			if goals.is_empty() {
+				return Err("goal combines no other goals".to_string());
+			}
+			goals.iter().try_for_each(|goal| validate_goal(level, goal))
+		},
+		WinCondition::Delivered(_, index, connection_id, seconds) => {
+			check_molecule(*index)?;
+			let has_outlet = level.reactors.iter()
+				.flat_map(|reactor| reactor.connections.iter())
+				.any(|connection| !connection.intake && connection.connection_id == *connection_id);
+			if !has_outlet {
+				return Err(format!("goal counts outlet {} which does not exist", connection_id));
+			}
+			check_duration(*seconds)
+		},
+		WinCondition::Received(_, index, seconds) => {
+			check_molecule(*index)?;
+			check_duration(*seconds)
+		},
+		WinCondition::Sustain(seconds, goal) => {
+			if !(seconds.is_finite() && *seconds >= 0.0) {
+				return Err(format!("goal cannot be sustained for {} seconds", seconds));
+			}
+			validate_goal(level, goal)
+		},
+	}
+}
+
+fn check_duration(
+	seconds: f32,
+) -> Result<(), String> {
+	if !(seconds.is_finite() && seconds > 0.0) {
+		return Err(format!("goal counts molecules over {} seconds", seconds));
+	}
+	Ok(())
+}
+
+fn seconds_to_ticks(
+	seconds: f32,
+) -> u64 {
+	(seconds / PHYSICS_TIMESTEP).round() as u64
+}
+
+impl LevelDefinition {
+	// Reads and validates a level written in RON, used by the
+	// game's asset loader and by tools reading the file directly
+	pub fn from_ron(
+		bytes: &[u8],
+	) -> Result<Self, String> {
+		let level = ron::de::from_bytes::<LevelDefinition>(bytes).map_err(|error| error.to_string())?;
+		validate_level(&level)?;
+		Ok(level)
+	}
+
+	// Stars earned by completing the level in the given seconds and
+	// cost, a level without a par only gives the star for completing it
+	pub fn stars(
+		&self,
+		time: f32,
+		cost: usize,
+	) -> usize {
+		let time_star = self.par_time.is_some_and(|par_time| time <= par_time);
+		let cost_star = self.par_cost.is_some_and(|par_cost| cost <= par_cost);
+		1 + time_star as usize + cost_star as usize
+	}
+
+	// Writes the level laid out like the hand written level files,
+	// with each starting molecule and connection on its own line
+	pub fn to_ron(&self) -> Result<String, String> {
+		let config = ron::ser::PrettyConfig::new()
+			.depth_limit(4)
+			.indentor("\t".to_string());
+		ron::ser::to_string_pretty(self, config)
+			.map(|text| text + "\n")
+			.map_err(|error| error.to_string())
+	}
+}
+
+impl ReactorDefinition {
+	pub fn reactor_info(
+		&self,
+		reactor_id: usize,
+	) -> ReactorInfo {
+		ReactorInfo{
+			input_chamber: self.input_chamber,
+			product_chamber: self.product_chamber,
+			reactor_type: self.reactor_type,
+			reactor_id,
+		}
+	}
+
+	pub fn connections(
+		&self,
+		reactor_id: usize,
+	) -> Vec<(Vec2, Connection)> {
+		let mut connections = Vec::new();
+		for definition in self.connections.iter() {
+			let mut filter = [false; TOTAL_MOLECULE_TYPES];
+			for index in definition.filter.iter() {
+				filter[*index] = true;
+			}
+			connections.push((definition.direction, Connection{
+				reactor_id,
+				connection_id: definition.connection_id,
+				intake: definition.intake,
+				filter,
+			}));
+		}
+		connections
+	}
+}
+
+impl ReactorType {
+	pub fn origin(&self) -> Vec2 {
+		match *self {
+			ReactorType::Rectangle{origin, ..} => origin,
+			ReactorType::Circle{origin, ..} => origin,
+		}
+	}
+
+	// The point on the reactor wall in the given direction, for
+	// circles the direction is treated as an angle
+	pub fn wall_point(
+		&self,
+		direction: Vec2,
+	) -> Vec2 {
+		match *self {
+			ReactorType::Rectangle{origin, dimensions} => Vec2::new(
+				origin.x + direction.x * dimensions.width / 2.0,
+				origin.y + direction.y * dimensions.height / 2.0,
+			),
+			ReactorType::Circle{origin, radius} => origin + direction.normalize() * radius,
+		}
+	}
+
+	// Intakes are turned to face the side they sit on, while
+	// outlets point away from the centre of the reactor which
+	// is the direction molecules leave them in
+	pub fn connection_transform(
+		&self,
+		direction: Vec2,
+		connection: &Connection,
+	) -> Transform {
+		let direction = match *self {
+			ReactorType::Rectangle{..} => direction,
+			ReactorType::Circle{..} => direction.normalize(),
+		};
+		let translation = self.wall_point(direction).extend(CONNECTION_Z + connection.connection_id as f32);
+		let rotation = if connection.intake {
+			Quat::from_rotation_z(if direction.y == 1.0 {0.0} else if direction.y == -1.0 {180.0_f32.to_radians()} else {if direction.x == 1.0 {-90.0_f32.to_radians()} else {90.0_f32.to_radians()}})
+		} else {
+			Quat::from_rotation_arc(Vec3::Y, (translation.truncate() - self.origin()).normalize().extend(0.0))
+		};
+		Transform::from_translation(translation).with_rotation(rotation)
+	}
+}
+
+impl WinCondition {
+	// The parts of the goal which count molecules, in the order they
+	// are written, each with the product chamber it is limited to
+	pub fn sub_goals(&self) -> Vec<(Option<usize>, &WinCondition)> {
+		let mut sub_goals = Vec::new();
+		self.collect_sub_goals(None, &mut sub_goals);
+		sub_goals
+	}
+
+	fn collect_sub_goals<'a>(
+		&'a self,
+		chamber: Option<usize>,
+		sub_goals: &mut Vec<(Option<usize>, &'a WinCondition)>,
+	) {
+		match self {
+			WinCondition::InChamber(reactor_id, goal) => goal.collect_sub_goals(Some(*reactor_id), sub_goals),
+			WinCondition::Sustain(_, goal) => goal.collect_sub_goals(chamber, sub_goals),
+			WinCondition::All(goals) | WinCondition::Any(goals) => {
+				for goal in goals.iter() {
+					goal.collect_sub_goals(chamber, sub_goals);
+				}
+			},
+			_ => sub_goals.push((chamber, self)),
+		}
+	}
+
+	// Counts the molecules for each sub-goal, where molecule_count gives
+	// the number of a molecule held in one product chamber, or in all
+	// of them when no chamber is given. Deliveries are counted from
+	// the flow records within each sub-goal's window of time
+	pub fn progress(
+		&self,
+		molecule_count: impl Fn(Option<usize>, usize) -> usize,
+		flow: &[FlowRecord],
+		tick: u64,
+		product_chambers: &[usize],
+	) -> Vec<SubGoalProgress> {
+		let flow_count = |seconds: f32, matches: &dyn Fn(&FlowRecord) -> bool| flow.iter()
+			.filter(|record| tick.saturating_sub(record.tick) < seconds_to_ticks(seconds) && matches(record))
+			.count();
+		self.sub_goals().into_iter().map(|(chamber, goal)| {
+			let count = |desired_molecule| molecule_count(chamber, desired_molecule);
+			match *goal {
+				WinCondition::GreaterThan(desired_quantity, desired_molecule) => {
+					let count = count(desired_molecule);
+					SubGoalProgress{count, other_count: 0, met: count >= desired_quantity}
+				},
+				WinCondition::LessThan(desired_quantity, desired_molecule) => {
+					let count = count(desired_molecule);
+					SubGoalProgress{count, other_count: 0, met: count < desired_quantity}
+				},
+				WinCondition::Exactly(desired_quantity, desired_molecule) => {
+					let count = count(desired_molecule);
+					SubGoalProgress{count, other_count: 0, met: count == desired_quantity}
+				},
+				WinCondition::Ratio(desired_molecule, other_molecule, ratio) => {
+					let (count, other_count) = (count(desired_molecule), count(other_molecule));
+					SubGoalProgress{count, other_count, met: count > 0 && count as f32 >= ratio * other_count as f32}
+				},
+				WinCondition::Delivered(desired_quantity, desired_molecule, connection_id, seconds) => {
+					let count = flow_count(seconds, &|record| record.connection_id == connection_id && record.index == desired_molecule);
+					SubGoalProgress{count, other_count: 0, met: count >= desired_quantity}
+				},
+				WinCondition::Received(desired_quantity, desired_molecule, seconds) => {
+					let count = flow_count(seconds, &|record| record.index == desired_molecule && match chamber {
+						Some(reactor_id) => record.reactor_id == reactor_id,
+						None => product_chambers.contains(&record.reactor_id),
+					});
+					SubGoalProgress{count, other_count: 0, met: count >= desired_quantity}
+				},
+				WinCondition::InChamber(..) | WinCondition::All(..) | WinCondition::Any(..) | WinCondition::Sustain(..) => unreachable!("sub-goals never contain other goals"),
+			}
+		}).collect()
+	}
+
+	// The goal with the time it must be held for to complete
+	// the level taken off the top, which the win countdown shows
+	pub fn split_win_countdown(&self) -> (f32, WinCondition) {
+		match self {
+			WinCondition::Sustain(seconds, goal) => (*seconds, (**goal).clone()),
+			_ => (WIN_COUNTDOWN_LENGTH, self.clone()),
+		}
+	}
+
+	// Number of sustained parts of the goal, each of which
+	// needs a count of how long it has been held for
+	pub fn sustain_count(&self) -> usize {
+		match self {
+			WinCondition::Sustain(_, goal) => 1 + goal.sustain_count(),
+			WinCondition::InChamber(_, goal) => goal.sustain_count(),
+			WinCondition::All(goals) | WinCondition::Any(goals) => goals.iter().map(|goal| goal.sustain_count()).sum(),
+			_ => 0,
+		}
+	}
+
+	// Seconds of flow records the goal needs kept
+	pub fn flow_window(&self) -> f32 {
+		match self {
+			WinCondition::Delivered(.., seconds) | WinCondition::Received(.., seconds) => *seconds,
+			WinCondition::InChamber(_, goal) | WinCondition::Sustain(_, goal) => goal.flow_window(),
+			WinCondition::All(goals) | WinCondition::Any(goals) => goals.iter().map(|goal| goal.flow_window()).fold(0.0, f32::max),
+			_ => 0.0,
+		}
+	}
+
+	// Whether the goal is met, given the progress of each of its
+	// sub-goals in the order returned by sub_goals. Sustained parts
+	// add the seconds passed to how long they have been held for
+	pub fn is_met(
+		&self,
+		sub_goals: &[SubGoalProgress],
+		sustained: &mut [f32],
+		delta_seconds: f32,
+	) -> bool {
+		self.evaluate(&mut sub_goals.iter(), &mut sustained.iter_mut(), delta_seconds)
+	}
+
+	// Every branch is evaluated so the sub-goals stay in step
+	fn evaluate(
+		&self,
+		sub_goals: &mut std::slice::Iter<SubGoalProgress>,
+		sustained: &mut std::slice::IterMut<f32>,
+		delta_seconds: f32,
+	) -> bool {
+		match self {
+			WinCondition::InChamber(_, goal) => goal.evaluate(sub_goals, sustained, delta_seconds),
+			WinCondition::All(goals) => goals.iter().map(|goal| goal.evaluate(sub_goals, sustained, delta_seconds)).collect::<Vec<_>>().into_iter().all(|met| met),
+			WinCondition::Any(goals) => goals.iter().map(|goal| goal.evaluate(sub_goals, sustained, delta_seconds)).collect::<Vec<_>>().into_iter().any(|met| met),
+			WinCondition::Sustain(seconds, goal) => {
+				let Some(held) = sustained.next() else {
+					return false;
+				};
+				*held = if goal.evaluate(sub_goals, sustained, delta_seconds) {*held + delta_seconds} else {0.0};
+				*held >= *seconds
+			},
+			_ => sub_goals.next().is_some_and(|progress| progress.met),
+		}
+	}
+
+	// A line describing a sub-goal and how close it is to being met,
+	// shown in the goal box while the level is played
+	pub fn describe(
+		&self,
+		chamber: Option<usize>,
+		progress: SubGoalProgress,
+		registry: &MoleculeRegistry,
+		locale: &Locale,
+	) -> String {
+		let name = |index: usize| registry.get(index).name.clone();
+		let description = match *self {
+			WinCondition::GreaterThan(desired_quantity, desired_molecule) => locale.format("goal.greater_than", &[name(desired_molecule), progress.count.to_string(), desired_quantity.to_string()]),
+			WinCondition::LessThan(desired_quantity, desired_molecule) => locale.format("goal.less_than", &[name(desired_molecule), progress.count.to_string(), desired_quantity.to_string()]),
+			WinCondition::Exactly(desired_quantity, desired_molecule) => locale.format("goal.exactly", &[name(desired_molecule), progress.count.to_string(), desired_quantity.to_string()]),
+			WinCondition::Ratio(desired_molecule, other_molecule, ratio) => locale.format("goal.ratio", &[name(desired_molecule), name(other_molecule), progress.count.to_string(), progress.other_count.to_string(), ratio.to_string()]),
+			WinCondition::Delivered(desired_quantity, desired_molecule, connection_id, seconds) => locale.format("goal.delivered", &[name(desired_molecule), connection_id.to_string(), progress.count.to_string(), desired_quantity.to_string(), seconds.to_string()]),
+			WinCondition::Received(desired_quantity, desired_molecule, seconds) => locale.format("goal.received", &[name(desired_molecule), progress.count.to_string(), desired_quantity.to_string(), seconds.to_string()]),
+			WinCondition::InChamber(..) | WinCondition::All(..) | WinCondition::Any(..) | WinCondition::Sustain(..) => String::new(),
+		};
+		match chamber {
+			Some(reactor_id) => locale.format("goal.in_chamber", &[reactor_id.to_string(), description]),
+			None => description,
+		}
+	}
+}
\ No newline at end of file
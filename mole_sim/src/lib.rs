@@ -0,0 +1,266 @@
+// Bevy systems routinely take many parameters and nested query types
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+// Import Bevy game engine essentials
+use bevy::{prelude::*, ecs::system::CommandQueue};
+// Import seedable random number generation for the simulation
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+// Import file reading for tools loading the assets folder directly
+use std::{fs, path::Path};
+
+// MODULES
+pub mod components;
+pub mod levels;
+pub mod locale;
+pub mod molecules;
+pub mod reactor;
+pub mod registry;
+pub mod replay;
+
+// Import components, resources, and events
+use components::*;
+use molecules::*;
+use reactor::*;
+use replay::*;
+
+// Plugin for the headless molecule simulation, which owns the
+// reactors, molecules, connections, reactions and win checking
+// without needing a window or renderer
+pub struct SimPlugin;
+
+impl Plugin for SimPlugin {
+    fn build(&self, app: &mut App) {
+        app
+			// Events
+			.add_event::<ConnectionEvent>()
+			.add_event::<CollisionEvent>()
+			.add_event::<LevelCompleteEvent>()
+			.add_event::<LevelFailedEvent>()
+			.add_event::<ReactorInputEvent>()
+			// Resources
+			.insert_resource(FixedTime::new_from_secs(PHYSICS_TIMESTEP))
+			.init_resource::<MoleculeRegistry>()
+			.init_resource::<ReactionTable>()
+			.init_resource::<SimRng>()
+			.init_resource::<SpatialHash>()
+			.init_resource::<MoleculeCount>()
+			.init_resource::<MoleculeFlow>()
+			.init_resource::<InputQueue>()
+			.init_resource::<ReplayRecording>()
+			// Simulation runs at a fixed rate in a fixed order so the
+			// outcome does not depend on the frame rate
+			.add_systems(FixedUpdate, (
+				feed_replay,
+				apply_inputs,
+				update_molecule_count,
+				decay_velocity,
+				update_molecule_lifetime,
+				molecule_spawner,
+				(
+					molecule_movement,
+					clamp_inside_reactor,
+				).chain().in_set(MoleculeMovementSet),
+				intake_connections,
+				outlet_connections,
+				record_molecule_flow,
+				check_goal,
+				advance_tick,
+			).chain()
+			.in_set(SimulationSet)
+			)
+		;
+	}
+}
+
+// Steps a single level with only the simulation plugin, so tests
+// and tooling can play levels on machines without a GPU
+pub struct Simulation {
+	pub app: App,
+	pub steps: u64,
+	pub level_id: usize,
+	pub level: LevelDefinition,
+}
+
+impl Simulation {
+	pub fn new(
+		level_id: usize,
+		level: &LevelDefinition,
+		registry: MoleculeRegistry,
+		reactions: ReactionTable,
+		seed: u64,
+	) -> Self {
+		let mut app = App::new();
+		app
+			.add_plugins(SimPlugin)
+			.insert_resource(registry)
+			.insert_resource(reactions)
+		;
+		let mut sim_rng = SimRng::new(Some(seed));
+		let mut queue = CommandQueue::default();
+		let mut commands = Commands::new(&mut queue, &app.world);
+		spawn_level(&mut commands, level_id, level, app.world.resource::<MoleculeRegistry>(), &mut sim_rng);
+		queue.apply(&mut app.world);
+		app.insert_resource(sim_rng);
+		Simulation{
+			app,
+			steps: 0,
+			level_id,
+			level: level.clone(),
+		}
+	}
+
+	// Reads the molecule catalogue, reaction table and level
+	// straight from an assets folder, without an asset server
+	pub fn from_assets(
+		assets: &Path,
+		level_id: usize,
+		seed: u64,
+	) -> Result<Self, String> {
+		let read = |path: String| fs::read(assets.join(&path)).map_err(|error| format!("{}: {}", path, error));
+		let registry = MoleculeRegistry::from_ron(&read(get_molecule_registry_path())?)
+			.map_err(|error| format!("{}: {}", get_molecule_registry_path(), error))?;
+		let reactions = ReactionTable::from_ron(&read(get_reaction_table_path())?)
+			.map_err(|error| format!("{}: {}", get_reaction_table_path(), error))?;
+		let level = LevelDefinition::from_ron(&read(get_level_path(level_id))?)
+			.map_err(|error| format!("{}: {}", get_level_path(level_id), error))?;
+		Ok(Simulation::new(level_id, &level, registry, reactions, seed))
+	}
+
+	// Plays back a replay from the start of the attempt, the
+	// simulation should be created with the replay's seed. Replays
+	// with inputs the game could not have made are turned away
+	pub fn play(
+		&mut self,
+		replay: Replay,
+	) -> Result<(), String> {
+		replay.validate(self.level_id, &self.level, self.app.world.resource::<MoleculeRegistry>())?;
+		self.app.insert_resource(ReplayPlayback::new(replay));
+		Ok(())
+	}
+
+	// Runs one fixed timestep, then clears out events which
+	// have been around for two steps as the game would
+	pub fn step(&mut self) {
+		self.app.world.run_schedule(FixedUpdate);
+		self.app.world.run_schedule(First);
+		self.steps += 1;
+	}
+
+	// Steps until the level is complete or failed, or the step
+	// limit is reached, returning whether the level was completed
+	pub fn run(
+		&mut self,
+		max_steps: u64,
+	) -> bool {
+		while !self.is_complete() && self.failed().is_none() && self.steps < max_steps {
+			self.step();
+		}
+		self.is_complete()
+	}
+
+	pub fn elapsed_secs(&self) -> f32 {
+		self.steps as f32 * PHYSICS_TIMESTEP
+	}
+
+	pub fn is_complete(&self) -> bool {
+		self.app.world.get_resource::<LevelAttempt>().is_some_and(|attempt| attempt.complete)
+	}
+
+	pub fn failed(&self) -> Option<FailReason> {
+		self.app.world.get_resource::<LevelAttempt>().and_then(|attempt| attempt.failed)
+	}
+
+	// Queues an input to be applied on the next step, the
+	// same way the game passes on the player's inputs
+	pub fn input(
+		&mut self,
+		input: ReactorInput,
+	) {
+		self.app.world.resource_mut::<InputQueue>().0.push(input);
+	}
+
+	// Inputs applied so far, along with the seed they were applied with
+	pub fn recording(&self) -> &Replay {
+		&self.app.world.resource::<ReplayRecording>().0
+	}
+
+	// Total cost of every molecule launched so far, counted
+	// the same way as the cost shown in the game
+	pub fn cost(&self) -> usize {
+		let registry = self.app.world.resource::<MoleculeRegistry>();
+		self.recording().inputs.iter()
+			.map(|recorded| match recorded.input {
+				ReactorInput::Launch{index, ..} => registry.cost(index),
+				_ => 0,
+			})
+			.sum()
+	}
+
+	// Number of molecules of the given type in a reactor
+	pub fn molecule_count(
+		&mut self,
+		reactor_id: usize,
+		index: usize,
+	) -> usize {
+		self.app.world.query_filtered::<(&MoleculeInfo, &ReactorInfo), With<Molecule>>()
+			.iter(&self.app.world)
+			.filter(|(m_info, r_info)| r_info.reactor_id == reactor_id && m_info.index == index)
+			.count()
+	}
+}
+
+impl SimRng {
+	pub fn new(
+		pinned_seed: Option<u64>,
+	) -> Self {
+		let seed = pinned_seed.unwrap_or(0);
+		SimRng{
+			seed,
+			pinned_seed,
+			rng: ChaCha8Rng::seed_from_u64(seed),
+		}
+	}
+
+	// Restarts the stream for a new level attempt, using the pinned
+	// seed if there is one, otherwise picking a new random seed
+	pub fn new_attempt(&mut self) {
+		self.reseed(self.pinned_seed.unwrap_or_else(rand::random));
+	}
+
+	// Restarts the stream from a known seed, such as the
+	// one a replay was recorded with
+	pub fn reseed(
+		&mut self,
+		seed: u64,
+	) {
+		self.seed = seed;
+		self.rng = ChaCha8Rng::seed_from_u64(seed);
+		info!("Simulation seed: {}", seed);
+	}
+}
+
+impl Default for SimRng {
+	fn default() -> Self {
+		SimRng::new(None)
+	}
+}
+
+impl Default for SpatialHash {
+	fn default() -> Self {
+		SpatialHash{
+			enabled: true,
+			cell_size: 0.0,
+			cells: default(),
+		}
+	}
+}
+
+impl Default for MoleculeCount {
+	fn default() -> Self {
+		MoleculeCount{
+			total: 0,
+			cap: MOLECULE_CAP,
+		}
+	}
+}
\ No newline at end of file
@@ -0,0 +1,179 @@
+// Import ordered sets so reports list keys in order
+use std::collections::BTreeSet;
+// Import components, resources, and events
+use crate::components::*;
+
+// Every value needs some text, and keys are written as lowercase
+// words separated by dots so they are easy to search for
+pub fn validate_string_table(
+	table: &StringTable,
+) -> Result<(), String> {
+	if table.language.trim().is_empty() {
+		return Err("string table has no language name".to_string());
+	}
+	for (key, value) in table.strings.iter() {
+		if !is_string_key(key) {
+			return Err(format!("{} is not a valid key, keys are lowercase words separated by dots", key));
+		}
+		if value.trim().is_empty() {
+			return Err(format!("{} has no text", key));
+		}
+		get_placeholders(value).map_err(|error| format!("{}: {}", key, error))?;
+	}
+	Ok(())
+}
+
+pub fn is_string_key(
+	key: &str,
+) -> bool {
+	let mut parts = key.split('.');
+	let count = parts.clone().count();
+	count > 1 && parts.all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'))
+}
+
+// Numbers of the placeholders used in some text, each
+// one written as {0}, {1} and so on
+pub fn get_placeholders(
+	text: &str,
+) -> Result<BTreeSet<usize>, String> {
+	let mut placeholders = BTreeSet::new();
+	let mut rest = text;
+	while let Some(start) = rest.find(['{', '}']) {
+		if rest[start..].starts_with('}') {
+			return Err("} without a matching {".to_string());
+		}
+		let Some(end) = rest[start..].find('}') else {
+			return Err("{ without a matching }".to_string());
+		};
+		let number = &rest[start + 1..start + end];
+		placeholders.insert(number.parse().map_err(|_| format!("{{{}}} is not a numbered placeholder", number))?);
+		rest = &rest[start + end + 1..];
+	}
+	Ok(placeholders)
+}
+
+// Replaces each numbered placeholder with its argument,
+// placeholders without one are left as they are
+pub fn fill_placeholders(
+	text: &str,
+	args: &[String],
+) -> String {
+	args.iter().enumerate().fold(text.to_string(), |text, (i, arg)| text.replace(&format!("{{{}}}", i), arg))
+}
+
+// Level and molecule text is written in English in the data files,
+// so other languages translate it under these keys
+pub fn get_level_goal_key(
+	level: usize,
+) -> String {
+	format!("level.{}.goal", level)
+}
+
+pub fn get_level_intro_key(
+	level: usize,
+) -> String {
+	format!("level.{}.intro", level)
+}
+
+pub fn get_molecule_tooltip_key(
+	index: usize,
+	unlocked: bool,
+) -> String {
+	format!("molecule.{}.{}", index, if unlocked {"tooltip"} else {"locked_tooltip"})
+}
+
+pub fn get_data_string_keys() -> Vec<String> {
+	let levels = (0..NUMBER_OF_LEVELS).flat_map(|level| [get_level_goal_key(level), get_level_intro_key(level)]);
+	let molecules = (0..TOTAL_MOLECULE_TYPES).flat_map(|index| [get_molecule_tooltip_key(index, true), get_molecule_tooltip_key(index, false)]);
+	levels.chain(molecules).collect()
+}
+
+// Differences between a translation and the fallback language,
+// listed by key
+#[derive(Default)]
+pub struct LocaleReport {
+	// Shown in the fallback language instead
+	pub missing: Vec<String>,
+	// Not used by the game, most likely left over or misspelt
+	pub unknown: Vec<String>,
+	// Using different placeholders than the fallback text
+	pub mismatched: Vec<String>,
+}
+
+impl LocaleReport {
+	pub fn is_complete(&self) -> bool {
+		self.missing.is_empty() && self.mismatched.is_empty()
+	}
+}
+
+// Every key in the fallback table and every level and molecule
+// text should be translated, with the same placeholders
+pub fn check_string_table(
+	table: &StringTable,
+	fallback: &StringTable,
+) -> LocaleReport {
+	let expected: BTreeSet<String> = fallback.strings.keys().cloned().chain(get_data_string_keys()).collect();
+	let placeholders = |text: &str| get_placeholders(text).unwrap_or_default();
+	LocaleReport{
+		missing: expected.iter().filter(|key| !table.strings.contains_key(*key)).cloned().collect(),
+		unknown: table.strings.keys().filter(|key| !expected.contains(*key)).cloned().collect(),
+		mismatched: table.strings.iter()
+			.filter(|(key, value)| fallback.strings.get(*key).is_some_and(|text| placeholders(text) != placeholders(value)))
+			.map(|(key, _)| key.clone())
+			.collect(),
+	}
+}
+
+impl Locale {
+	// True once the fallback table has been copied in, the
+	// splash screen waits for this before moving on to the menu
+	pub fn is_loaded(&self) -> bool {
+		!self.strings.is_empty()
+	}
+
+	// Keys missing from every table are shown as they are,
+	// so they are easy to spot and add
+	pub fn get(
+		&self,
+		key: &str,
+	) -> String {
+		self.strings.get(key).cloned().unwrap_or_else(|| key.to_string())
+	}
+
+	// Level and molecule text is written in English in the data
+	// files, which is used unless the language translates it
+	pub fn get_or(
+		&self,
+		key: &str,
+		default: &str,
+	) -> String {
+		self.strings.get(key).cloned().unwrap_or_else(|| default.to_string())
+	}
+
+	pub fn format(
+		&self,
+		key: &str,
+		args: &[String],
+	) -> String {
+		fill_placeholders(&self.get(key), args)
+	}
+}
+
+impl StringTable {
+	// Reads and validates a string table written in RON, used by
+	// the game's asset loader and by tools reading the file directly
+	pub fn from_ron(
+		bytes: &[u8],
+	) -> Result<Self, String> {
+		let table = ron::de::from_bytes::<StringTable>(bytes).map_err(|error| error.to_string())?;
+		validate_string_table(&table)?;
+		Ok(table)
+	}
+
+	pub fn get(
+		&self,
+		key: &str,
+	) -> Option<&str> {
+		self.strings.get(key).map(String::as_str)
+	}
+}
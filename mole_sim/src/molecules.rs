@@ -0,0 +1,385 @@
+// Import Bevy game engine essentials
+use bevy::{prelude::*, math::Vec3Swizzles};
+// Import random number generation for the simulation
+use rand::Rng;
+// Import components, resources, and events
+use crate::components::*;
+
+impl MoleculeBundle {
+	// A freshly spawned molecule of the given type, with its
+	// lifetime drawn from the simulation's random stream
+	pub fn new(
+		registry: &MoleculeRegistry,
+		sim_rng: &mut SimRng,
+		index: usize,
+		reactor: ReactorInfo,
+		location: Vec2,
+		velocity: Vec2,
+	) -> Self {
+		MoleculeBundle{
+			molecule: Molecule(registry.lifetime(index, &mut sim_rng.rng)),
+			info: MoleculeInfo {
+				index,
+				reacted: false,
+				radius: registry.radius(index),
+				mass: registry.mass(index),
+			},
+			reactor,
+			velocity: Velocity(velocity),
+			order: SimOrder(sim_rng.rng.gen()),
+			transform: TransformBundle::from_transform(Transform::from_translation(location.extend(MOLECULE_Z))),
+			name: Name::new("Molecule"),
+		}
+	}
+}
+
+// Counts the current number of molecules, this is used
+// to cap molecule spawning in other systems
+pub fn update_molecule_count(
+	molecule_query: Query<With<Molecule>>,
+	mut molecule_count: ResMut<MoleculeCount>,
+) {
+	molecule_count.total = 0;
+	for _ in molecule_query.iter() {
+		molecule_count.total += 1;
+	}
+}
+
+// If the temperature and pressure conditions are correct then increment
+// each molecule's lifetimer timer, triggering a reaction when it expires
+pub fn update_molecule_lifetime(
+	mut commands: Commands,
+	reactor_condition_query: Query<(&ReactorCondition, &ReactorInfo)>,
+	mut molecule_query: Query<(Entity, &mut Molecule, &MoleculeInfo, &Transform, &ReactorInfo, &SimOrder)>,
+	registry: Res<MoleculeRegistry>,
+	mut sim_rng: ResMut<SimRng>,
+	reactions: Res<ReactionTable>,
+	molecule_count: Res<MoleculeCount>,
+	fixed_time: Res<FixedTime>,
+) {
+	let mut molecules: Vec<_> = molecule_query.iter_mut().collect();
+	molecules.sort_by_key(|(.., order)| **order);
+	for (entity, mut molecule, m_info, transform, r_info, _) in molecules {
+		let (mut current_temperature, mut current_pressure) = (0.0, 0.0);
+		for (condition, info) in reactor_condition_query.iter() {
+			if info.reactor_id == r_info.reactor_id {
+				(current_temperature, current_pressure) = (condition.temperature, condition.pressure);
+			}
+		}
+		match &mut molecule.0 {
+			Lifetime::Unstable(ref mut lifetime) => {
+				if !reactions.has_decay(m_info.index) {
+					lifetime.tick(fixed_time.period);
+					if lifetime.finished() {
+						commands.entity(entity).despawn_recursive()
+					}
+				} else if let Some(decay) = reactions.decay(m_info.index, current_temperature, current_pressure) {
+					lifetime.tick(fixed_time.period);
+					if lifetime.finished() {
+						commands.entity(entity).despawn_recursive();
+						for &product in decay.products.iter() {
+							if molecule_count.total <= molecule_count.cap {
+								let velocity = registry.launch_speed(product);
+								let direction = Vec2::new(sim_rng.rng.gen::<f32>() - 0.5, sim_rng.rng.gen::<f32>() - 0.5).normalize();
+								let location = Vec2::new(
+									transform.translation.x + sim_rng.rng.gen::<f32>(),
+									transform.translation.y + sim_rng.rng.gen::<f32>(),
+								);
+								let velocity = Vec2::new((sim_rng.rng.gen::<f32>()-0.5)*velocity, (sim_rng.rng.gen::<f32>()-0.5)*velocity) * direction;
+								commands.spawn(MoleculeBundle::new(&registry, &mut sim_rng, product, *r_info, location, velocity));
+							}
+						}
+					}
+				}
+			},
+			Lifetime::Stable => (),
+		}
+	}
+}
+
+// Decreases velocity by a percentage of its value every step to
+// simulate friction and eventually bring all molecules to a stop
+pub fn decay_velocity(
+	mut molecule_query: Query<(&mut Velocity, With<Molecule>)>,
+	fixed_time: Res<FixedTime>,
+) {
+	for (mut velocity, _) in molecule_query.iter_mut() {
+		let prev_velocity = velocity.0;
+		velocity.0 -= prev_velocity * FRICTION * fixed_time.period.as_secs_f32();
+	}
+}
+
+// Handles all molecule movement and collision logic, including
+// spawning new molecules from reactions if the current temperature
+// and pressure are correct
+pub fn molecule_movement(
+	mut commands: Commands,
+	mut molecule_query: Query<(Entity, &mut MoleculeInfo, &mut ReactorInfo, &mut Transform, &mut Velocity)>,
+	order_query: Query<&SimOrder>,
+	reactor_condition_query: Query<(&ReactorCondition, &ReactorInfo, Without<MoleculeInfo>)>,
+	mut ev_w_collision: EventWriter<CollisionEvent>,
+	molecule_count: Res<MoleculeCount>,
+	registry: Res<MoleculeRegistry>,
+	mut sim_rng: ResMut<SimRng>,
+	reactions: Res<ReactionTable>,
+	mut spatial_hash: ResMut<SpatialHash>,
+	fixed_time: Res<FixedTime>,
+) {
+	// Broad phase, finds the pairs of molecules close enough to collide
+	let mut molecules: Vec<(Entity, usize, Vec2, f32)> = molecule_query.iter()
+		.map(|(entity, m_info, r_info, transform, _)| (entity, r_info.reactor_id, transform.translation.xy(), m_info.radius))
+		.collect();
+	molecules.sort_by_key(|(entity, ..)| order_query.get(*entity).ok().copied());
+	let pairs = if spatial_hash.enabled {
+		spatial_hash.rebuild(molecules.into_iter());
+		spatial_hash.pairs()
+	} else {
+		let mut pairs = Vec::new();
+		for (i, &(entity_a, ..)) in molecules.iter().enumerate() {
+			for &(entity_b, ..) in molecules[i + 1..].iter() {
+				pairs.push([entity_a, entity_b]);
+			}
+		}
+		pairs
+	};
+	for pair in pairs {
+		let Ok([
+			(entity_a, mut m_info_a, r_info_a, mut transform_a, mut velocity_a),
+			(entity_b, mut m_info_b, r_info_b, mut transform_b, mut velocity_b),
+		]) = molecule_query.get_many_mut(pair) else {
+			continue;
+		};
+		// Skip over molecule pairs which are not in the same reactor or that have already reacted
+		if r_info_a.reactor_id != r_info_b.reactor_id || m_info_a.reacted || m_info_b.reacted {
+			continue;
+		};
+		let mut baby = false;
+		let mut bounce = false;
+		let offset = transform_a.translation.xy() - transform_b.translation.xy();
+		// Molecule collision check takes place here
+		if offset.length() <= m_info_a.radius + m_info_b.radius {
+			ev_w_collision.send(CollisionEvent{index: m_info_a.index, location: transform_a.translation.xy()});
+			ev_w_collision.send(CollisionEvent{index: m_info_b.index, location: transform_b.translation.xy()});
+
+			let (mut current_temperature, mut current_pressure) = (0.0, 0.0);
+			for (condition, info, _) in reactor_condition_query.iter() {
+				if info.reactor_id == r_info_a.reactor_id {
+					(current_temperature, current_pressure) = (condition.temperature, condition.pressure);
+				}
+			}
+			match reactions.combination(m_info_a.index, m_info_b.index, current_temperature, current_pressure) {
+				// Reaction takes place here
+				Some(ReactionDefinition{products, ..}) => {
+					m_info_a.reacted = true;
+					m_info_b.reacted = true;
+					let mut input_a_accounted_for = false;
+					let mut input_b_accounted_for = false;
+					let product_contains_a = products.contains(&m_info_a.index);
+					let product_contains_b = products.contains(&m_info_b.index);
+					if !product_contains_a {commands.entity(entity_a).despawn_recursive();};
+					if !product_contains_b {commands.entity(entity_b).despawn_recursive();};
+					if product_contains_a && product_contains_b {
+						baby = true;
+					};
+
+					let mass_a_in = m_info_a.mass;
+					let mass_b_in = m_info_b.mass;
+					let velocity_a_in = velocity_a.0;
+					let velocity_b_in = velocity_b.0;
+					let momentum_a = mass_a_in * velocity_a_in;
+					let momentum_b = mass_b_in * velocity_b_in;
+					let velocity_out = (momentum_a + momentum_b)/(mass_a_in + mass_b_in);
+
+					let total_products = products.len();
+
+					for &product in products.iter() {
+						if product == m_info_a.index && !input_a_accounted_for {
+							match total_products {
+								1 => {
+									velocity_a.0 = velocity_out;
+								}
+								_ => {
+									velocity_b.0 = velocity_out * Vec2::new(sim_rng.rng.gen::<f32>() - 0.5, sim_rng.rng.gen::<f32>() - 0.5).normalize();
+								}
+							}
+							input_a_accounted_for = true;
+						}
+						else if product == m_info_b.index && !input_b_accounted_for {
+							match total_products {
+								1 => {
+									velocity_b.0 = velocity_out;
+								}
+								_ => {
+									velocity_b.0 = velocity_out * Vec2::new(sim_rng.rng.gen::<f32>() - 0.5, sim_rng.rng.gen::<f32>() - 0.5).normalize();
+								}
+							}
+							input_b_accounted_for = true;
+						}
+						else if molecule_count.total <= molecule_count.cap {
+							let direction = if total_products == 1 {velocity_out.normalize()}
+								else {Vec2::new(sim_rng.rng.gen::<f32>() - 0.5, sim_rng.rng.gen::<f32>() - 0.5).normalize()};
+							let location = Vec2::new(
+								transform_b.translation.x + offset.x/2.0 + sim_rng.rng.gen::<f32>(),
+								transform_b.translation.y + offset.y/2.0 + sim_rng.rng.gen::<f32>(),
+							);
+							let velocity = Vec2::new(
+								registry.launch_speed(product),
+								registry.launch_speed(product),
+							) * direction;
+							commands.spawn(MoleculeBundle::new(&registry, &mut sim_rng, product, *r_info_a, location, velocity));
+						}
+					}
+				},
+				None => bounce = true,
+			};
+
+			// Molecule collision repel takes place here
+			if bounce || baby {
+				let relative_velocity = velocity_a.0 - velocity_b.0;
+				let dp = offset * relative_velocity.dot(offset) / ((offset.length_squared()) * (m_info_a.mass + m_info_b.mass));
+
+				velocity_a.0 -= 2.0 * m_info_b.mass * dp;
+				velocity_b.0 += 2.0 * m_info_a.mass * dp;
+
+				let push = (offset.normalize() * 1.01 * (m_info_a.radius + m_info_b.radius) - offset).extend(0.0);
+				transform_a.translation += push;
+				transform_b.translation -= push;
+			}
+		}
+	}
+
+	// Edge collision takes place here, split into substeps so fast
+	// molecules cannot pass through the reactor walls in a single step
+	let substep = fixed_time.period.as_secs_f32() / PHYSICS_SUBSTEPS as f32;
+	for (_, mut m_info, r_info, mut transform, mut velocity) in molecule_query.iter_mut() {
+		m_info.reacted = false;
+		for _ in 0..PHYSICS_SUBSTEPS {
+			let target = Vec2::new(
+				transform.translation.x + velocity.0.x * substep,
+				transform.translation.y + velocity.0.y * substep
+			);
+			match r_info.reactor_type {
+				ReactorType::Rectangle{origin, dimensions } => {
+					let offset = (target - origin).abs();
+					if offset.x > dimensions.width / 2.0 - m_info.radius {
+						velocity.0.x = -velocity.0.x;
+						ev_w_collision.send(CollisionEvent{index: m_info.index, location: transform.translation.xy()});
+					}
+					if offset.y > dimensions.height / 2.0 - m_info.radius {
+						velocity.0.y = -velocity.0.y;
+						ev_w_collision.send(CollisionEvent{index: m_info.index, location: transform.translation.xy()});
+					}
+				},
+				ReactorType::Circle{origin, radius } => {
+					let offset = (target - origin).length();
+					if offset > radius - m_info.radius {
+						let prev_velocity = velocity.0;
+						let normal = (origin - transform.translation.xy()).normalize();
+						let new_velocity = prev_velocity - (2.0*prev_velocity.dot(normal)*normal);
+						velocity.0.x = new_velocity.x;
+						velocity.0.y = new_velocity.y;
+						ev_w_collision.send(CollisionEvent{index: m_info.index, location: transform.translation.xy()});
+					}
+				},
+			}
+
+			transform.translation.x += velocity.0.x * substep;
+			transform.translation.y += velocity.0.y * substep;
+		}
+	}
+}
+
+impl SpatialHash {
+	// Sorts molecules into cells at least as wide as the largest
+	// molecule, so colliding molecules are never more than one cell apart
+	pub fn rebuild(
+		&mut self,
+		molecules: impl Iterator<Item = (Entity, usize, Vec2, f32)>,
+	) {
+		self.cells.clear();
+		let molecules: Vec<(Entity, usize, Vec2, f32)> = molecules.collect();
+		self.cell_size = molecules.iter().fold(1.0, |size: f32, (.., radius)| size.max(radius * 2.0));
+		for (entity, reactor_id, position, _) in molecules {
+			let cell = (position / self.cell_size).floor().as_ivec2();
+			self.cells.entry((reactor_id, cell)).or_default().push(entity);
+		}
+	}
+
+	// Each pair is only returned once, by looking at half of the
+	// neighbouring cells along with the molecule's own cell. Cells are
+	// visited in a fixed order so collisions resolve the same way every run
+	pub fn pairs(&self) -> Vec<[Entity; 2]> {
+		let mut pairs = Vec::new();
+		let mut keys: Vec<(usize, IVec2)> = self.cells.keys().copied().collect();
+		keys.sort_by_key(|&(reactor_id, cell)| (reactor_id, cell.x, cell.y));
+		for (reactor_id, cell) in keys {
+			let entities = &self.cells[&(reactor_id, cell)];
+			for (i, &entity_a) in entities.iter().enumerate() {
+				for &entity_b in entities[i + 1..].iter() {
+					pairs.push([entity_a, entity_b]);
+				}
+			}
+			for offset in [IVec2::new(1, 0), IVec2::new(1, 1), IVec2::new(0, 1), IVec2::new(-1, 1)] {
+				if let Some(neighbours) = self.cells.get(&(reactor_id, cell + offset)) {
+					for &entity_a in entities.iter() {
+						for &entity_b in neighbours.iter() {
+							pairs.push([entity_a, entity_b]);
+						}
+					}
+				}
+			}
+		}
+		pairs
+	}
+}
+
+pub fn clamp_inside_reactor(
+	mut molecule_query: Query<(&MoleculeInfo, &ReactorInfo, &mut Transform, With<Molecule>)>,
+) {
+	for (m_info, r_info, mut transform, _) in molecule_query.iter_mut() {
+		match r_info.reactor_type {
+			ReactorType::Rectangle{origin, dimensions } => {
+				let offset = (transform.translation.xy() - origin).abs();
+				if offset.x > dimensions.width / 2.0 - m_info.radius {
+					transform.translation.x = origin.x + (transform.translation.x - origin.x).signum() * (dimensions.width / 2.0 - m_info.radius);
+				}
+				if offset.y > dimensions.height / 2.0 - m_info.radius{
+					transform.translation.y = origin.y + (transform.translation.y - origin.y).signum() * (dimensions.height / 2.0 - m_info.radius);
+				}
+			},
+			ReactorType::Circle{origin, radius } => {
+				let offset = (transform.translation.xy() - origin).length();
+				if offset > radius - m_info.radius {
+					transform.translation = (origin + (transform.translation.xy() - origin).normalize() * (radius - m_info.radius)).extend(transform.translation.z);
+				}
+			},
+		}
+	}
+}
+
+// Causes any dropped molecule spawners to spawn
+// molecules at fixed intervals
+pub fn molecule_spawner(
+	mut commands: Commands,
+	mut molecule_spawner_query: Query<(&Transform, &mut MoleculeSpawnerInfo, &ReactorInfo)>,
+	registry: Res<MoleculeRegistry>,
+	mut sim_rng: ResMut<SimRng>,
+	molecule_count: Res<MoleculeCount>,
+	fixed_time: Res<FixedTime>,
+) {
+	for (transform, mut s_info, r_info) in molecule_spawner_query.iter_mut() {
+		s_info.spawner_timer.tick(fixed_time.period);
+		if s_info.spawner_timer.just_finished() && molecule_count.total <= molecule_count.cap {
+			let molecule_index = s_info.spawner_index;
+			let (target, distance) = match r_info.reactor_type {
+				ReactorType::Rectangle{dimensions, ..} => (Vec2::new(transform.translation.x, transform.translation.y - dimensions.height / 2.0), dimensions.height / 2.0),
+				ReactorType::Circle{origin, radius} => (origin, radius),
+			};
+			let direction = (target - transform.translation.xy()).normalize();
+			let velocity = registry.launch_speed(molecule_index);
+			let location = (transform.translation.xy() - target)
+				.clamp_length_max(distance - registry.radius(molecule_index)) + target;
+			commands.spawn(MoleculeBundle::new(&registry, &mut sim_rng, molecule_index, *r_info, location, Vec2::new(velocity, velocity) * direction));
+		}
+	}
+}
\ No newline at end of file
@@ -0,0 +1,252 @@
+// Import Bevy game engine essentials
+use bevy::{prelude::*, math::Vec3Swizzles};
+// Import random number generation for the simulation
+use rand::Rng;
+// Import components, resources, and events
+use crate::components::*;
+
+// Spawns the reactors and connections for a level,
+// then starts the first attempt at it
+pub fn spawn_level(
+	commands: &mut Commands,
+	level_id: usize,
+	level: &LevelDefinition,
+	registry: &MoleculeRegistry,
+	sim_rng: &mut SimRng,
+) {
+	for (i, reactor_definition) in level.reactors.iter().enumerate() {
+		let reactor = reactor_definition.reactor_info(i);
+		commands.spawn((
+			TransformBundle::from_transform(Transform::from_translation(reactor.reactor_type.origin().extend(10.0))),
+			reactor,
+			ReactorCondition{
+				temperature: 0.0,
+				pressure: 0.0,
+			},
+			Name::new(format!("Reactor {}", i)),
+		));
+		for (direction, connection) in reactor_definition.connections(i) {
+			let transform = reactor.reactor_type.connection_transform(direction, &connection);
+			let name = if connection.intake {"Connection In"} else {"Connection Out"};
+			commands.spawn((
+				TransformBundle::from_transform(transform),
+				connection,
+				Name::new(name),
+			));
+		}
+	}
+	start_attempt(commands, level_id, level, registry, sim_rng, None);
+}
+
+// Reseeds the simulation and spawns the starting molecules, used
+// both when a level is entered and when it is replayed. Playing
+// back a replay passes in the seed it was recorded with
+pub fn start_attempt(
+	commands: &mut Commands,
+	level_id: usize,
+	level: &LevelDefinition,
+	registry: &MoleculeRegistry,
+	sim_rng: &mut SimRng,
+	seed: Option<u64>,
+) {
+	match seed {
+		Some(seed) => sim_rng.reseed(seed),
+		None => sim_rng.new_attempt(),
+	}
+	commands.insert_resource(InputQueue::default());
+	commands.insert_resource(ReplayRecording(Replay{
+		level: level_id,
+		seed: sim_rng.seed,
+		inputs: Vec::new(),
+	}));
+	commands.insert_resource(MoleculeFlow::default());
+	let (win_countdown_length, goal) = level.goal.split_win_countdown();
+	commands.insert_resource(LevelAttempt{
+		tick: 0,
+		sub_goals: Vec::new(),
+		sustained: vec![0.0; goal.sustain_count()],
+		goal,
+		goal_met: false,
+		cost: 0,
+		cost_limit: level.cost_limit,
+		time_limit: level.time_limit,
+		failed: None,
+		win_countdown: Timer::from_seconds(win_countdown_length, TimerMode::Once),
+		complete: false,
+	});
+	for (i, reactor_definition) in level.reactors.iter().enumerate() {
+		let reactor = reactor_definition.reactor_info(i);
+		let origin = reactor.reactor_type.origin();
+		for &InitialMolecule{index, location, velocity} in reactor_definition.molecules.iter() {
+			let location = Vec2::new(
+				origin.x + location.x + sim_rng.rng.gen::<f32>(),
+				origin.y + location.y + sim_rng.rng.gen::<f32>(),
+			);
+			commands.spawn(MoleculeBundle::new(registry, sim_rng, index, reactor, location, velocity));
+		}
+	}
+}
+
+// Check for collisions between intake connections and molecules
+// and if so emit a connection event
+pub fn intake_connections(
+	mut commands: Commands,
+	mut ev_w_connection: EventWriter<ConnectionEvent>,
+	molecule_query: Query<(Entity, &Transform, &MoleculeInfo, &Velocity, &ReactorInfo, Option<&SelectedMolecule>, &SimOrder), With<Molecule>>,
+	connection_query: Query<(&Transform, &Connection)>,
+) {
+	let mut molecules: Vec<_> = molecule_query.iter().collect();
+	molecules.sort_by_key(|(.., order)| **order);
+	for (c_transform, connection) in sorted_connections(&connection_query) {
+		for &(entity, m_transform, m_info, velocity, r_info, selected, _) in molecules.iter() {
+			if connection.reactor_id == r_info.reactor_id
+			&& (c_transform.translation.xy() - m_transform.translation.xy()).length() < CONNECTION_IN_WIDTH
+			&& connection.intake
+			&& connection.filter[m_info.index] {
+				ev_w_connection.send(ConnectionEvent{
+					connection_id: connection.connection_id,
+					m_info: *m_info,
+					r_info: *r_info,
+					velocity: velocity.0,
+					selected: selected.is_some(),
+				});
+				commands.entity(entity).despawn_recursive();
+			}
+		}
+	}
+}
+
+// Connections sorted by reactor and ID, so
+// molecules pass through them in the same order every run
+fn sorted_connections<'a>(
+	connection_query: &'a Query<(&Transform, &Connection)>,
+) -> Vec<(&'a Transform, &'a Connection)> {
+	let mut connections: Vec<_> = connection_query.iter().collect();
+	connections.sort_by_key(|(_, connection)| (connection.reactor_id, connection.connection_id, connection.intake));
+	connections
+}
+
+// Handles connection events by spawning a new molecule
+// at every outlet connection with a matching ID
+pub fn outlet_connections(
+	mut commands: Commands,
+	mut ev_r_connection: EventReader<ConnectionEvent>,
+	registry: Res<MoleculeRegistry>,
+	mut sim_rng: ResMut<SimRng>,
+	connection_query: Query<(&Transform, &Connection)>,
+	reactor_query: Query<&ReactorInfo, With<ReactorCondition>>,
+) {
+	for ev in ev_r_connection.iter() {
+		for (transform, connection) in sorted_connections(&connection_query) {
+			if connection.connection_id == ev.connection_id && !connection.intake {
+				let mut r_info = ev.r_info;
+				for r_new_info in reactor_query.iter() {
+					if connection.reactor_id == r_new_info.reactor_id {
+						r_info = *r_new_info;
+					}
+				}
+
+				let mut prev_transform = *transform;
+				prev_transform.rotate_local_z((sim_rng.rng.gen::<f32>() - 0.5) * 0.75_f32);
+				let direction = -prev_transform.local_y().xy().normalize();
+				let location = transform.translation.xy() + direction * ev.m_info.radius * 1.01;
+
+				let mut mole = commands.spawn(MoleculeBundle{
+					info: ev.m_info,
+					..MoleculeBundle::new(&registry, &mut sim_rng, ev.m_info.index, r_info, location, ev.velocity.length() * direction)
+				});
+				if ev.selected {
+					mole.insert(SelectedMolecule);
+				}
+			}
+		}
+	}
+}
+
+// Records each molecule leaving an outlet, so goals can count
+// what has passed through a connection or into a chamber
+pub fn record_molecule_flow(
+	attempt: Option<Res<LevelAttempt>>,
+	mut flow: ResMut<MoleculeFlow>,
+	mut ev_r_connection: EventReader<ConnectionEvent>,
+	connection_query: Query<(&Transform, &Connection)>,
+) {
+	let Some(attempt) = attempt else {
+		ev_r_connection.clear();
+		return;
+	};
+	for ev in ev_r_connection.iter() {
+		for (_, connection) in sorted_connections(&connection_query) {
+			if connection.connection_id == ev.connection_id && !connection.intake {
+				flow.0.push(FlowRecord{
+					tick: attempt.tick,
+					connection_id: connection.connection_id,
+					reactor_id: connection.reactor_id,
+					index: ev.m_info.index,
+				});
+			}
+		}
+	}
+	// Records older than any the goal counts are dropped
+	let window = (attempt.goal.flow_window() / PHYSICS_TIMESTEP).ceil() as u64;
+	flow.0.retain(|record| attempt.tick.saturating_sub(record.tick) < window);
+}
+
+// Counts the molecules held in the product chambers for each of
+// the goal's sub-goals, completing the level once the goal has
+// been held for the length of the win countdown, or failing it
+// once a cost or time limit has been passed
+pub fn check_goal(
+	attempt: Option<ResMut<LevelAttempt>>,
+	flow: Res<MoleculeFlow>,
+	mut ev_w_level_complete: EventWriter<LevelCompleteEvent>,
+	mut ev_w_level_failed: EventWriter<LevelFailedEvent>,
+	reactor_query: Query<&ReactorInfo, With<ReactorCondition>>,
+	molecule_query: Query<(&MoleculeInfo, &ReactorInfo), With<Molecule>>,
+	fixed_time: Res<FixedTime>,
+) {
+	let Some(mut attempt) = attempt else {
+		return;
+	};
+	if attempt.complete || attempt.failed.is_some() {
+		return;
+	}
+	let product_chambers: Vec<usize> = reactor_query.iter()
+		.filter(|r_info| r_info.product_chamber)
+		.map(|r_info| r_info.reactor_id)
+		.collect();
+	let mut counts = vec![[0; TOTAL_MOLECULE_TYPES]; product_chambers.iter().max().map_or(0, |reactor_id| reactor_id + 1)];
+	for (m_info, r_info) in molecule_query.iter() {
+		if product_chambers.contains(&r_info.reactor_id) {
+			counts[r_info.reactor_id][m_info.index] += 1;
+		}
+	}
+	attempt.sub_goals = attempt.goal.progress(|chamber, desired_molecule| match chamber {
+		Some(reactor_id) => counts.get(reactor_id).map_or(0, |counts| counts[desired_molecule]),
+		None => product_chambers.iter().map(|reactor_id| counts[*reactor_id][desired_molecule]).sum(),
+	}, &flow.0, attempt.tick, &product_chambers);
+	let attempt = &mut *attempt;
+	let goal_met = !product_chambers.is_empty() && attempt.goal.is_met(&attempt.sub_goals, &mut attempt.sustained, fixed_time.period.as_secs_f32());
+	attempt.goal_met = goal_met;
+	if goal_met {
+		attempt.win_countdown.tick(fixed_time.period);
+		if attempt.win_countdown.just_finished() {
+			attempt.complete = true;
+			ev_w_level_complete.send(LevelCompleteEvent);
+			return;
+		}
+	} else {
+		attempt.win_countdown.reset();
+	}
+	let failed = if attempt.cost_limit.is_some_and(|cost_limit| attempt.cost > cost_limit) {
+		Some(FailReason::OverBudget)
+	} else if attempt.time_limit.is_some_and(|time_limit| (attempt.tick + 1) as f32 * PHYSICS_TIMESTEP >= time_limit) {
+		Some(FailReason::OutOfTime)
+	} else {
+		None
+	};
+	if let Some(reason) = failed {
+		attempt.failed = Some(reason);
+		ev_w_level_failed.send(LevelFailedEvent(reason));
+	}
+}
\ No newline at end of file
@@ -0,0 +1,289 @@
+// Import Bevy game engine essentials
+use bevy::prelude::*;
+// Import random number generation for decay times
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+// Import components, resources, and events
+use crate::components::*;
+
+// Every molecule index used by the game needs an entry,
+// and every palette needs a colour for each molecule
+pub fn validate_molecule_registry(
+	registry: &MoleculeRegistry,
+) -> Result<(), String> {
+	if registry.molecules.len() != TOTAL_MOLECULE_TYPES {
+		return Err(format!("expected {} molecules but found {}", TOTAL_MOLECULE_TYPES, registry.molecules.len()));
+	}
+	for (index, molecule) in registry.molecules.iter().enumerate() {
+		if molecule.colors.len() != NUMBER_OF_PALETTES {
+			return Err(format!("{} has {} colours but there are {} palettes", molecule.name, molecule.colors.len(), NUMBER_OF_PALETTES));
+		}
+		if molecule.glyph.is_empty() || molecule.glyph.chars().count() > 2 {
+			return Err(format!("{} needs a glyph of one or two characters", molecule.name));
+		}
+		if registry.molecules[..index].iter().any(|other| other.glyph == molecule.glyph) {
+			return Err(format!("{} shares its glyph {} with another molecule", molecule.name, molecule.glyph));
+		}
+		if molecule.radius <= 0.0 || molecule.mass <= 0.0 {
+			return Err(format!("molecule {} must have a positive radius and mass", index));
+		}
+		if let Some(Limits(min, max)) = molecule.decay {
+			if min < 0.0 || max < min {
+				return Err(format!("{} has an invalid decay range", molecule.name));
+			}
+		}
+	}
+	Ok(())
+}
+
+// Catches molecules missing from the registry, and rules for
+// the same reactants which could both apply at once
+pub fn validate_reaction_table(
+	table: &ReactionTable,
+) -> Result<(), String> {
+	for (i, reaction) in table.reactions.iter().enumerate() {
+		let (a, b) = reaction.reactants;
+		check_molecules(&[a, b], &reaction.products, reaction.temperature, reaction.pressure)
+			.map_err(|error| format!("reaction {} ({} + {}): {}", i, a, b, error))?;
+		for (j, other) in table.reactions.iter().enumerate().skip(i + 1) {
+			if sorted_pair(reaction.reactants) == sorted_pair(other.reactants)
+			&& reaction.temperature.overlaps(other.temperature)
+			&& reaction.pressure.overlaps(other.pressure) {
+				return Err(describe_overlap("reactions", i, j, &reaction.products, &other.products));
+			}
+		}
+	}
+	for (i, decay) in table.decays.iter().enumerate() {
+		check_molecules(&[decay.reactant], &decay.products, decay.temperature, decay.pressure)
+			.map_err(|error| format!("decay {} ({}): {}", i, decay.reactant, error))?;
+		for (j, other) in table.decays.iter().enumerate().skip(i + 1) {
+			if decay.reactant == other.reactant
+			&& decay.temperature.overlaps(other.temperature)
+			&& decay.pressure.overlaps(other.pressure) {
+				return Err(describe_overlap("decays", i, j, &decay.products, &other.products));
+			}
+		}
+	}
+	Ok(())
+}
+
+fn check_molecules(
+	reactants: &[usize],
+	products: &[usize],
+	temperature: Limits,
+	pressure: Limits,
+) -> Result<(), String> {
+	for index in reactants.iter().chain(products.iter()) {
+		if *index >= TOTAL_MOLECULE_TYPES {
+			return Err(format!("molecule {} is not in the molecule registry", index));
+		}
+	}
+	if temperature.0 > temperature.1 || pressure.0 > pressure.1 {
+		return Err("limits must be written as (min, max)".to_string());
+	}
+	Ok(())
+}
+
+fn describe_overlap(
+	list: &str,
+	i: usize,
+	j: usize,
+	products_i: &[usize],
+	products_j: &[usize],
+) -> String {
+	let (mut sorted_i, mut sorted_j) = (products_i.to_vec(), products_j.to_vec());
+	sorted_i.sort();
+	sorted_j.sort();
+	if sorted_i == sorted_j {
+		format!("{} {} and {} are duplicates", list, i, j)
+	} else {
+		format!("{} {} and {} conflict, they share reactants and their limits overlap", list, i, j)
+	}
+}
+
+fn sorted_pair(
+	(a, b): (usize, usize),
+) -> (usize, usize) {
+	(a.min(b), a.max(b))
+}
+
+impl MoleculeRegistry {
+	// Reads and validates a catalogue written in RON, used by
+	// the game's asset loader and by tools reading the file directly
+	pub fn from_ron(
+		bytes: &[u8],
+	) -> Result<Self, String> {
+		let registry = ron::de::from_bytes::<MoleculeRegistry>(bytes).map_err(|error| error.to_string())?;
+		validate_molecule_registry(&registry)?;
+		Ok(registry)
+	}
+
+	// True once the catalogue has been copied in, the splash
+	// screen waits for this before moving on to the menu
+	pub fn is_loaded(&self) -> bool {
+		!self.molecules.is_empty()
+	}
+
+	pub fn get(
+		&self,
+		index: usize,
+	) -> &MoleculeDefinition {
+		&self.molecules[index]
+	}
+
+	pub fn path(
+		&self,
+		index: usize,
+	) -> String {
+		self.get(index).sprite.clone()
+	}
+
+	pub fn glyph(
+		&self,
+		index: usize,
+	) -> String {
+		self.get(index).glyph.clone()
+	}
+
+	pub fn radius(
+		&self,
+		index: usize,
+	) -> f32 {
+		self.get(index).radius
+	}
+
+	pub fn mass(
+		&self,
+		index: usize,
+	) -> f32 {
+		self.get(index).mass
+	}
+
+	pub fn cost(
+		&self,
+		index: usize,
+	) -> usize {
+		self.get(index).cost
+	}
+
+	pub fn launch_speed(
+		&self,
+		index: usize,
+	) -> f32 {
+		self.get(index).launch_speed
+	}
+
+	pub fn note(
+		&self,
+		index: usize,
+	) -> usize {
+		self.get(index).note
+	}
+
+	// Unstable molecules get a new decay timer each
+	// time one is spawned
+	pub fn lifetime(
+		&self,
+		index: usize,
+		rng: &mut ChaCha8Rng,
+	) -> Lifetime {
+		match self.get(index).decay {
+			Some(Limits(min, max)) => Lifetime::Unstable(Timer::from_seconds(rng.gen::<f32>() * (max - min) + min, TimerMode::Once)),
+			None => Lifetime::Stable,
+		}
+	}
+
+	pub fn tooltip(
+		&self,
+		index: usize,
+		unlocked: bool,
+	) -> String {
+		if unlocked {
+			self.get(index).tooltip.clone()
+		} else {
+			self.get(index).locked_tooltip.clone()
+		}
+	}
+}
+
+impl ReactionTable {
+	// Reads and validates a reaction table written in RON,
+	// building the lookups once the rules are known to be sound
+	pub fn from_ron(
+		bytes: &[u8],
+	) -> Result<Self, String> {
+		let mut table = ron::de::from_bytes::<ReactionTable>(bytes).map_err(|error| error.to_string())?;
+		validate_reaction_table(&table)?;
+		table.build_lookups();
+		Ok(table)
+	}
+
+	// Indexes every rule by its reactants so collisions
+	// only have to look at the rules which could apply
+	pub fn build_lookups(&mut self) {
+		self.reaction_lookup.clear();
+		self.decay_lookup.clear();
+		for (i, reaction) in self.reactions.iter().enumerate() {
+			self.reaction_lookup.entry(sorted_pair(reaction.reactants)).or_default().push(i);
+		}
+		for (i, decay) in self.decays.iter().enumerate() {
+			self.decay_lookup.entry(decay.reactant).or_default().push(i);
+		}
+	}
+
+	pub fn is_loaded(&self) -> bool {
+		!self.reactions.is_empty() || !self.decays.is_empty()
+	}
+
+	// The reaction between two molecules under the current
+	// conditions, if there is one they bounce off each other
+	pub fn combination(
+		&self,
+		mol_a: usize,
+		mol_b: usize,
+		temperature: f32,
+		pressure: f32,
+	) -> Option<&ReactionDefinition> {
+		self.reaction_lookup.get(&sorted_pair((mol_a, mol_b)))?
+			.iter()
+			.map(|i| &self.reactions[*i])
+			.find(|reaction| reaction.temperature.contains(temperature) && reaction.pressure.contains(pressure))
+	}
+
+	// Molecules without any decay rules simply disappear
+	// when their lifetime runs out
+	pub fn has_decay(
+		&self,
+		index: usize,
+	) -> bool {
+		self.decay_lookup.contains_key(&index)
+	}
+
+	pub fn decay(
+		&self,
+		index: usize,
+		temperature: f32,
+		pressure: f32,
+	) -> Option<&DecayDefinition> {
+		self.decay_lookup.get(&index)?
+			.iter()
+			.map(|i| &self.decays[*i])
+			.find(|decay| decay.temperature.contains(temperature) && decay.pressure.contains(pressure))
+	}
+}
+
+impl Limits {
+	pub fn contains(
+		&self,
+		value: f32,
+	) -> bool {
+		value >= self.0 && value <= self.1
+	}
+
+	pub fn overlaps(
+		&self,
+		other: Limits,
+	) -> bool {
+		self.0 <= other.1 && other.0 <= self.1
+	}
+}
\ No newline at end of file
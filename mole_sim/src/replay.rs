@@ -0,0 +1,297 @@
+// Import Bevy game engine essentials
+use bevy::prelude::*;
+// Import the maths for finding a launch tube's place around a reactor
+use std::f32::consts::{PI, TAU};
+// Import components, resources, and events
+use crate::components::*;
+
+// How far a launch can be from where the launch tube would have
+// put it, allowing for rounding in the positions written to replays
+const LAUNCH_TOLERANCE: f32 = 0.5;
+// How far a launch speed can be from the molecule's launch
+// speed, as a fraction of that speed
+const SPEED_TOLERANCE: f32 = 0.001;
+
+impl Replay {
+	// Replays are written without any whitespace to
+	// keep the files small enough to pass around
+	pub fn to_ron(&self) -> Result<String, String> {
+		ron::ser::to_string(self).map_err(|error| error.to_string())
+	}
+
+	pub fn from_ron(
+		bytes: &[u8],
+	) -> Result<Self, String> {
+		let replay = ron::de::from_bytes::<Replay>(bytes).map_err(|error| error.to_string())?;
+		if replay.inputs.windows(2).any(|pair| pair[0].tick > pair[1].tick) {
+			return Err("replay inputs are not in order".to_string());
+		}
+		Ok(replay)
+	}
+
+	// Rejects replays holding inputs the game could not have made on
+	// the level, such as launching a molecule the level does not offer,
+	// before any of them reach the simulation
+	pub fn validate(
+		&self,
+		level_id: usize,
+		level: &LevelDefinition,
+		registry: &MoleculeRegistry,
+	) -> Result<(), String> {
+		if self.level != level_id {
+			return Err(format!("replay is of level {}, not level {}", self.level, level_id));
+		}
+		for recorded in self.inputs.iter() {
+			recorded.input.validate(level, registry)
+				.map_err(|error| format!("step {}: {}", recorded.tick, error))?;
+		}
+		Ok(())
+	}
+}
+
+impl ReactorInput {
+	pub fn validate(
+		&self,
+		level: &LevelDefinition,
+		registry: &MoleculeRegistry,
+	) -> Result<(), String> {
+		match *self {
+			ReactorInput::MoveLaunchTube{reactor_id, location, angle, tilt} => {
+				get_input_chamber(level, reactor_id)?;
+				if !(location.is_finite() && angle.is_finite() && tilt.abs() <= LAUNCH_TUBE_MAX_TILT) {
+					return Err(format!("launch tube {} was moved somewhere it cannot go", reactor_id));
+				}
+			},
+			ReactorInput::Launch{reactor_id, index, location, velocity} => {
+				let reactor = get_input_chamber(level, reactor_id)?;
+				check_available(level, index)?;
+				if !is_on_launch_tube(reactor, registry.radius(index), location) {
+					return Err(format!("molecule {} was launched from ({}, {}) which is not on launch tube {}", index, location.x, location.y, reactor_id));
+				}
+				if !is_launch_velocity(reactor, registry.launch_speed(index), location, velocity) {
+					return Err(format!("molecule {} was launched at a speed or angle launch tube {} cannot give it", index, reactor_id));
+				}
+			},
+			ReactorInput::SetConditions{reactor_id, temperature, pressure} => {
+				get_reactor(level, reactor_id)?;
+				if !((0.0..=1.0).contains(&temperature) && (0.0..=1.0).contains(&pressure)) {
+					return Err(format!("reactor {} was set to a temperature of {} and pressure of {}, outside of the levers", reactor_id, temperature, pressure));
+				}
+			},
+			ReactorInput::PlaceSpawner{reactor_id, index, location, angle} => {
+				get_input_chamber(level, reactor_id)?;
+				check_available(level, index)?;
+				if !(location.is_finite() && angle.is_finite()) {
+					return Err(format!("spawner for molecule {} was placed somewhere it cannot go", index));
+				}
+			},
+			ReactorInput::SelectReactor(reactor_id) => {
+				get_reactor(level, reactor_id)?;
+			},
+			ReactorInput::SelectMolecule(index) => {
+				if index >= TOTAL_MOLECULE_TYPES {
+					return Err(format!("molecule {} does not exist", index));
+				}
+			},
+		}
+		Ok(())
+	}
+}
+
+fn get_reactor(
+	level: &LevelDefinition,
+	reactor_id: usize,
+) -> Result<&ReactorDefinition, String> {
+	level.reactors.get(reactor_id).ok_or_else(|| format!("reactor {} does not exist", reactor_id))
+}
+
+// Only input chambers have a launch tube
+fn get_input_chamber(
+	level: &LevelDefinition,
+	reactor_id: usize,
+) -> Result<&ReactorDefinition, String> {
+	let reactor = get_reactor(level, reactor_id)?;
+	if !reactor.input_chamber {
+		return Err(format!("reactor {} is not an input chamber", reactor_id));
+	}
+	Ok(reactor)
+}
+
+fn check_available(
+	level: &LevelDefinition,
+	index: usize,
+) -> Result<(), String> {
+	if index >= TOTAL_MOLECULE_TYPES {
+		return Err(format!("molecule {} does not exist", index));
+	}
+	if !level.available_molecules.contains(&index) {
+		return Err(format!("molecule {} is not available on this level", index));
+	}
+	Ok(())
+}
+
+// Launched molecules start just inside the wall at the launch tube,
+// which slides along the top of rectangles between its limits and
+// around circles outside of the gap between its limits
+fn is_on_launch_tube(
+	reactor: &ReactorDefinition,
+	radius: f32,
+	location: Vec2,
+) -> bool {
+	let Limits(min, max) = reactor.launch_tube_limits;
+	match reactor.reactor_type {
+		ReactorType::Rectangle{origin, dimensions} => {
+			let travel = dimensions.width / 2.0 - LAUNCH_TUBE_WIDTH / 2.0;
+			let offset = location.x - origin.x;
+			(location.y - (origin.y + dimensions.height / 2.0 - radius)).abs() <= LAUNCH_TOLERANCE
+				&& offset >= -travel * min - LAUNCH_TOLERANCE
+				&& offset <= travel * max + LAUNCH_TOLERANCE
+		},
+		ReactorType::Circle{origin, radius: reactor_radius} => {
+			let offset = location - origin;
+			let percent = (-Vec2::Y.perp_dot(offset).atan2(-Vec2::Y.dot(offset)) + PI) / TAU;
+			let tolerance = LAUNCH_TOLERANCE / (TAU * reactor_radius);
+			(offset.length() - (reactor_radius - radius)).abs() <= LAUNCH_TOLERANCE
+				&& !(percent > min + tolerance && percent < max - tolerance)
+		},
+	}
+}
+
+// Molecules leave the launch tube at their launch speed, pointing
+// into the reactor give or take the tube's tilt
+fn is_launch_velocity(
+	reactor: &ReactorDefinition,
+	speed: f32,
+	location: Vec2,
+	velocity: Vec2,
+) -> bool {
+	let inward = match reactor.reactor_type {
+		ReactorType::Rectangle{..} => Vec2::NEG_Y,
+		ReactorType::Circle{origin, ..} => origin - location,
+	};
+	(velocity.length() - speed).abs() <= speed * SPEED_TOLERANCE
+		&& velocity.angle_between(inward).abs().to_degrees() <= LAUNCH_TUBE_MAX_TILT + SPEED_TOLERANCE
+}
+
+impl ReplayRecording {
+	// Launch tubes and levers move every frame while they are
+	// held, so only the last input within each step is kept
+	pub fn record(
+		&mut self,
+		tick: u64,
+		input: ReactorInput,
+	) {
+		if let Some(last) = self.0.inputs.last_mut() {
+			let replaces_last = last.tick == tick && match (last.input, input) {
+				(ReactorInput::MoveLaunchTube{reactor_id: a, ..}, ReactorInput::MoveLaunchTube{reactor_id: b, ..}) => a == b,
+				(ReactorInput::SetConditions{reactor_id: a, ..}, ReactorInput::SetConditions{reactor_id: b, ..}) => a == b,
+				_ => false,
+			};
+			if replaces_last {
+				last.input = input;
+				return;
+			}
+		}
+		self.0.inputs.push(RecordedInput{
+			tick,
+			input,
+		});
+	}
+}
+
+impl ReplayPlayback {
+	pub fn new(
+		replay: Replay,
+	) -> Self {
+		ReplayPlayback{
+			replay,
+			next_input: 0,
+		}
+	}
+
+	pub fn finished(&self) -> bool {
+		self.next_input >= self.replay.inputs.len()
+	}
+}
+
+// Queues every replay input stamped with the current step
+pub fn feed_replay(
+	playback: Option<ResMut<ReplayPlayback>>,
+	attempt: Option<Res<LevelAttempt>>,
+	mut input_queue: ResMut<InputQueue>,
+) {
+	let (Some(mut playback), Some(attempt)) = (playback, attempt) else {
+		return;
+	};
+	while let Some(recorded) = playback.replay.inputs.get(playback.next_input) {
+		if recorded.tick > attempt.tick {
+			break;
+		}
+		input_queue.0.push(recorded.input);
+		playback.next_input += 1;
+	}
+}
+
+// Applies the queued inputs at the start of a step, recording
+// each one so the attempt can be saved as a replay
+pub fn apply_inputs(
+	mut commands: Commands,
+	mut input_queue: ResMut<InputQueue>,
+	mut recording: ResMut<ReplayRecording>,
+	mut ev_w_input: EventWriter<ReactorInputEvent>,
+	mut reactor_query: Query<(&ReactorInfo, &mut ReactorCondition)>,
+	mut attempt: Option<ResMut<LevelAttempt>>,
+	registry: Res<MoleculeRegistry>,
+	mut sim_rng: ResMut<SimRng>,
+) {
+	let tick = attempt.as_ref().map_or(0, |attempt| attempt.tick);
+	for input in input_queue.0.drain(..) {
+		match input {
+			ReactorInput::Launch{reactor_id, index, location, velocity} => {
+				if let Some((r_info, _)) = reactor_query.iter().find(|(r_info, _)| r_info.reactor_id == reactor_id) {
+					commands.spawn(MoleculeBundle::new(&registry, &mut sim_rng, index, *r_info, location, velocity));
+				}
+				if let Some(attempt) = attempt.as_mut() {
+					attempt.cost += registry.cost(index);
+				}
+			},
+			ReactorInput::SetConditions{reactor_id, temperature, pressure} => {
+				for (r_info, mut condition) in reactor_query.iter_mut() {
+					if r_info.reactor_id == reactor_id {
+						condition.temperature = temperature;
+						condition.pressure = pressure;
+					}
+				}
+			},
+			ReactorInput::PlaceSpawner{reactor_id, index, location, angle} => {
+				if let Some((r_info, _)) = reactor_query.iter().find(|(r_info, _)| r_info.reactor_id == reactor_id) {
+					commands.spawn((
+						TransformBundle::from_transform(Transform::from_translation(location.extend(SPAWNER_Z))
+							.with_rotation(Quat::from_rotation_z(angle))),
+						*r_info,
+						MoleculeSpawnerInfo{
+							spawner_index: index,
+							spawner_timer: Timer::from_seconds(SPAWNER_INTERVAL, TimerMode::Repeating),
+						},
+						Name::new("Molecule Spawner"),
+					));
+				}
+			},
+			ReactorInput::MoveLaunchTube{..}
+			| ReactorInput::SelectReactor(_)
+			| ReactorInput::SelectMolecule(_) => (),
+		}
+		recording.record(tick, input);
+		ev_w_input.send(ReactorInputEvent(input));
+	}
+}
+
+// Moves the attempt on to the next step once
+// everything else in the step has run
+pub fn advance_tick(
+	attempt: Option<ResMut<LevelAttempt>>,
+) {
+	if let Some(mut attempt) = attempt {
+		attempt.tick += 1;
+	}
+}
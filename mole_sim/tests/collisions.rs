@@ -0,0 +1,147 @@
+// Checks the spatial hash broad phase finds the same collisions as
+// checking every pair of molecules, and benchmarks the collision step
+// with and without it on the reactor grid filled to the molecule cap
+
+// Import file reading, paths to the assets folder and timing for the benchmark
+use std::{collections::BTreeSet, fs, path::PathBuf, time::Instant};
+// Import Bevy's entity ids and maths
+use bevy::prelude::*;
+// Import random number generation for placing molecules
+use rand::Rng;
+// Import the headless simulation
+use mole_sim::{Simulation, components::*};
+
+// A fixed seed so every run places the same molecules
+const SEED: u64 = 1234;
+
+// The sandbox layout of 32 connected circular reactors, where
+// the collision step stutters with the molecule cap reached
+const REACTOR_GRID: &str = "levels/drafts/reactor_grid.level.ron";
+
+// The grid is a draft without a level number of its own,
+// so it is simulated in place of the first sandbox level
+const SANDBOX_LEVEL: usize = 7;
+
+// Steps timed for each broad phase in the benchmark
+const BENCHMARK_STEPS: u32 = 120;
+
+// Funda only bounces off itself at the sandbox's conditions, so
+// the reactor stays at the molecule cap for the whole benchmark
+const FUNDA: usize = 0;
+
+fn assets_path() -> PathBuf {
+	PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../assets")
+}
+
+// Molecules of mixed sizes scattered over a few reactors, some
+// packed closely enough that many of them are touching
+fn scatter_molecules(
+	sim_rng: &mut SimRng,
+	count: usize,
+) -> Vec<(Entity, usize, Vec2, f32)> {
+	(0..count)
+		.map(|i| (
+			Entity::from_raw(i as u32),
+			sim_rng.rng.gen_range(0..3),
+			Vec2::new(sim_rng.rng.gen_range(-400.0..400.0), sim_rng.rng.gen_range(-400.0..400.0)),
+			sim_rng.rng.gen_range(5.0..40.0),
+		))
+		.collect()
+}
+
+// Pairs in the same reactor which are close enough to collide,
+// written smallest entity first so pairs can be compared
+fn colliding(
+	molecules: &[(Entity, usize, Vec2, f32)],
+	pairs: impl Iterator<Item = [Entity; 2]>,
+) -> Vec<[Entity; 2]> {
+	let find = |entity: Entity| molecules.iter().find(|(other, ..)| *other == entity).unwrap();
+	pairs
+		.filter(|&[a, b]| {
+			let (_, reactor_a, position_a, radius_a) = find(a);
+			let (_, reactor_b, position_b, radius_b) = find(b);
+			reactor_a == reactor_b && position_a.distance(*position_b) <= radius_a + radius_b
+		})
+		.map(|[a, b]| if a < b {[a, b]} else {[b, a]})
+		.collect()
+}
+
+fn load<T>(
+	path: &str,
+	from_ron: impl Fn(&[u8]) -> Result<T, String>,
+) -> T {
+	let bytes = fs::read(assets_path().join(path)).unwrap_or_else(|error| panic!("{}: {}", path, error));
+	from_ron(&bytes).unwrap_or_else(|error| panic!("{}: {}", path, error))
+}
+
+// Fills the reactor grid with as many molecules as the cap
+// allows, dealt out evenly between its reactors
+fn fill_reactor_grid(
+	spatial_hash: bool,
+) -> Simulation {
+	let mut simulation = Simulation::new(
+		SANDBOX_LEVEL,
+		&load(REACTOR_GRID, LevelDefinition::from_ron),
+		load(&get_molecule_registry_path(), MoleculeRegistry::from_ron),
+		load(&get_reaction_table_path(), ReactionTable::from_ron),
+		SEED,
+	);
+	simulation.app.world.resource_mut::<SpatialHash>().enabled = spatial_hash;
+	let mut reactors: Vec<ReactorInfo> = simulation.app.world.query::<&ReactorInfo>().iter(&simulation.app.world).copied().collect();
+	reactors.sort_by_key(|reactor| reactor.reactor_id);
+	simulation.app.world.resource_scope(|world, mut sim_rng: Mut<SimRng>| {
+		let registry = world.resource::<MoleculeRegistry>().clone();
+		for i in 0..MOLECULE_CAP {
+			let reactor = reactors[i % reactors.len()];
+			let ReactorType::Circle{origin, radius: reactor_radius} = reactor.reactor_type else {
+				panic!("{} should only have circular reactors", REACTOR_GRID);
+			};
+			let radius = registry.radius(FUNDA);
+			let location = origin + Vec2::from_angle(sim_rng.rng.gen::<f32>() * std::f32::consts::TAU)
+				* sim_rng.rng.gen::<f32>().sqrt() * (reactor_radius - radius);
+			let velocity = Vec2::from_angle(sim_rng.rng.gen::<f32>() * std::f32::consts::TAU) * registry.launch_speed(FUNDA);
+			world.spawn(MoleculeBundle::new(&registry, &mut sim_rng, FUNDA, reactor, location, velocity));
+		}
+	});
+	simulation
+}
+
+#[test]
+fn spatial_hash_finds_every_colliding_pair() {
+	let mut sim_rng = SimRng::new(Some(SEED));
+	for count in [0, 1, 50, 400] {
+		let molecules = scatter_molecules(&mut sim_rng, count);
+		let mut spatial_hash = SpatialHash::default();
+		spatial_hash.rebuild(molecules.iter().copied());
+		let pairs = spatial_hash.pairs();
+		let unique: BTreeSet<[Entity; 2]> = pairs.iter().map(|&[a, b]| if a < b {[a, b]} else {[b, a]}).collect();
+		assert_eq!(unique.len(), pairs.len(), "the spatial hash returned a pair twice with {} molecules", count);
+
+		let every_pair = molecules.iter().enumerate()
+			.flat_map(|(i, (a, ..))| molecules[i + 1..].iter().map(move |(b, ..)| [*a, *b]));
+		let expected: BTreeSet<[Entity; 2]> = colliding(&molecules, every_pair).into_iter().collect();
+		let found: BTreeSet<[Entity; 2]> = colliding(&molecules, pairs.into_iter()).into_iter().collect();
+		assert_eq!(found, expected, "the spatial hash missed collisions with {} molecules", count);
+	}
+}
+
+// Run with `cargo test -p mole_sim --release -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn benchmark_collision_step() {
+	for spatial_hash in [false, true] {
+		let mut simulation = fill_reactor_grid(spatial_hash);
+		// Warms up before timing, as the first step allocates the cells
+		simulation.step();
+		let start = Instant::now();
+		for _ in 0..BENCHMARK_STEPS {
+			simulation.step();
+		}
+		println!("Step ({}): {:.3} ms average over {} steps with {} molecules",
+			if spatial_hash {"spatial hash"} else {"brute force"},
+			start.elapsed().as_secs_f64() * 1000.0 / BENCHMARK_STEPS as f64,
+			BENCHMARK_STEPS,
+			simulation.app.world.resource::<MoleculeCount>().total,
+		);
+	}
+}
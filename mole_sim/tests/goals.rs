@@ -0,0 +1,142 @@
+// Checks compound goals are met only when their sub-goals
+// combine the way the goal tree says they should, and that
+// goals measured over time count the right window
+
+// Import the goal types
+use mole_sim::components::*;
+
+const COMBA: usize = 0;
+const MORTA: usize = 1;
+
+const PRODUCT_CHAMBERS: [usize; 2] = [1, 2];
+
+// Counts held by two product chambers, reactors 1 and 2
+fn progress(
+	goal: &WinCondition,
+	counts: [[usize; 2]; 2],
+) -> Vec<SubGoalProgress> {
+	flow_progress(goal, counts, &[], 0)
+}
+
+fn flow_progress(
+	goal: &WinCondition,
+	counts: [[usize; 2]; 2],
+	flow: &[FlowRecord],
+	tick: u64,
+) -> Vec<SubGoalProgress> {
+	goal.progress(|chamber, desired_molecule| match chamber {
+		Some(reactor_id) => counts[reactor_id - 1][desired_molecule],
+		None => counts[0][desired_molecule] + counts[1][desired_molecule],
+	}, flow, tick, &PRODUCT_CHAMBERS)
+}
+
+fn is_met(
+	goal: &WinCondition,
+	counts: [[usize; 2]; 2],
+) -> bool {
+	goal.is_met(&progress(goal, counts), &mut vec![0.0; goal.sustain_count()], PHYSICS_TIMESTEP)
+}
+
+// A molecule out of the given outlet into the given reactor
+fn record(
+	tick: u64,
+	connection_id: usize,
+	reactor_id: usize,
+	index: usize,
+) -> FlowRecord {
+	FlowRecord{tick, connection_id, reactor_id, index}
+}
+
+#[test]
+fn single_conditions_count_every_product_chamber() {
+	let goal = WinCondition::GreaterThan(5, COMBA);
+	assert!(is_met(&goal, [[3, 0], [2, 0]]));
+	assert!(!is_met(&goal, [[3, 0], [1, 0]]));
+	let goal = WinCondition::LessThan(1, MORTA);
+	assert!(is_met(&goal, [[3, 0], [2, 0]]));
+	assert!(!is_met(&goal, [[0, 0], [0, 1]]));
+	let goal = WinCondition::Exactly(4, COMBA);
+	assert!(is_met(&goal, [[2, 0], [2, 0]]));
+	assert!(!is_met(&goal, [[2, 0], [3, 0]]));
+}
+
+#[test]
+fn all_needs_every_sub_goal_and_any_needs_one() {
+	let sub_goals = vec![WinCondition::GreaterThan(5, COMBA), WinCondition::LessThan(1, MORTA)];
+	let all = WinCondition::All(sub_goals.clone());
+	let any = WinCondition::Any(sub_goals);
+	assert!(is_met(&all, [[5, 0], [0, 0]]));
+	assert!(!is_met(&all, [[5, 0], [0, 1]]));
+	assert!(is_met(&any, [[5, 0], [0, 1]]));
+	assert!(is_met(&any, [[0, 0], [0, 0]]));
+	assert!(!is_met(&any, [[4, 0], [0, 1]]));
+}
+
+#[test]
+fn chamber_goals_only_count_their_chamber() {
+	let goal = WinCondition::All(vec![
+		WinCondition::InChamber(1, Box::new(WinCondition::GreaterThan(2, COMBA))),
+		WinCondition::InChamber(2, Box::new(WinCondition::GreaterThan(2, MORTA))),
+	]);
+	assert!(is_met(&goal, [[2, 0], [0, 2]]));
+	assert!(!is_met(&goal, [[0, 2], [2, 0]]));
+	let progress = progress(&goal, [[2, 0], [0, 1]]);
+	assert_eq!(progress.iter().map(|progress| (progress.count, progress.met)).collect::<Vec<_>>(), vec![(2, true), (1, false)]);
+}
+
+#[test]
+fn ratios_need_the_first_molecule_held() {
+	let goal = WinCondition::Ratio(COMBA, MORTA, 2.0);
+	assert!(is_met(&goal, [[4, 1], [0, 1]]));
+	assert!(!is_met(&goal, [[3, 1], [0, 1]]));
+	assert!(is_met(&goal, [[1, 0], [0, 0]]));
+	assert!(!is_met(&goal, [[0, 0], [0, 0]]));
+}
+
+#[test]
+fn goals_are_read_from_level_files() {
+	let goal: WinCondition = ron::from_str("All([GreaterThan(5, 0), InChamber(2, LessThan(1, 1)), Ratio(0, 1, 1.5)])").unwrap();
+	assert_eq!(goal.sub_goals().into_iter().map(|(chamber, _)| chamber).collect::<Vec<_>>(), vec![None, Some(2), None]);
+}
+
+#[test]
+fn deliveries_are_counted_within_their_window() {
+	let goal = WinCondition::Delivered(2, COMBA, 3, 1.0);
+	let flow = [record(0, 3, 1, COMBA), record(50, 3, 1, COMBA), record(55, 4, 1, COMBA), record(58, 3, 0, MORTA)];
+	assert_eq!(flow_progress(&goal, [[0; 2]; 2], &flow, 59)[0].count, 2);
+	assert_eq!(flow_progress(&goal, [[0; 2]; 2], &flow, 60)[0].count, 1);
+}
+
+#[test]
+fn received_molecules_are_counted_by_chamber() {
+	let flow = [record(0, 3, 1, COMBA), record(1, 4, 2, COMBA), record(2, 5, 0, COMBA)];
+	let goal = WinCondition::Received(2, COMBA, 10.0);
+	assert_eq!(flow_progress(&goal, [[0; 2]; 2], &flow, 2)[0].count, 2);
+	let goal = WinCondition::InChamber(2, Box::new(WinCondition::Received(2, COMBA, 10.0)));
+	assert_eq!(flow_progress(&goal, [[0; 2]; 2], &flow, 2)[0].count, 1);
+}
+
+#[test]
+fn sustained_goals_must_be_held_without_a_break() {
+	let goal = WinCondition::All(vec![
+		WinCondition::Sustain(1.0, Box::new(WinCondition::GreaterThan(10, COMBA))),
+		WinCondition::LessThan(1, MORTA),
+	]);
+	let mut sustained = vec![0.0; goal.sustain_count()];
+	let mut step = |counts| goal.is_met(&progress(&goal, counts), &mut sustained, 0.25);
+	assert!(!step([[10, 0], [0, 0]]));
+	assert!(!step([[10, 0], [0, 0]]));
+	assert!(!step([[9, 0], [0, 0]]));
+	for _ in 0..3 {
+		assert!(!step([[10, 0], [0, 0]]));
+	}
+	assert!(step([[10, 0], [0, 0]]));
+	assert!(!step([[10, 0], [0, 1]]));
+}
+
+#[test]
+fn the_outer_sustain_sets_the_win_countdown() {
+	let goal = WinCondition::Sustain(15.0, Box::new(WinCondition::GreaterThan(10, COMBA)));
+	assert_eq!(goal.split_win_countdown(), (15.0, WinCondition::GreaterThan(10, COMBA)));
+	assert_eq!(WinCondition::LessThan(1, MORTA).split_win_countdown(), (WIN_COUNTDOWN_LENGTH, WinCondition::LessThan(1, MORTA)));
+}
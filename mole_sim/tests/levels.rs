@@ -0,0 +1,272 @@
+// Regression tests over every shipped level, playing each level's
+// reference solution headlessly and checking the level files for
+// layouts the game would not be able to play
+
+// Import file reading and paths to the assets folder
+use std::{fs, path::PathBuf};
+// Import Bevy's maths for placing inputs
+use bevy::prelude::Vec2;
+// Import the headless simulation
+use mole_sim::{Simulation, components::*};
+
+// Simulated seconds a reference solution is given to complete its level
+const TIME_BUDGET: f32 = 120.0;
+
+fn assets_path() -> PathBuf {
+	PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../assets")
+}
+
+// Reference solutions are replays recorded in the game, or written
+// by hand, named the same way as the replays the game saves
+fn solution_path(
+	level: usize,
+) -> PathBuf {
+	PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!("tests/solutions/level_{:02}.replay.ron", level))
+}
+
+fn load_level(
+	level: usize,
+) -> LevelDefinition {
+	let bytes = fs::read(assets_path().join(get_level_path(level)))
+		.unwrap_or_else(|error| panic!("{}: {}", get_level_path(level), error));
+	LevelDefinition::from_ron(&bytes)
+		.unwrap_or_else(|error| panic!("{}: {}", get_level_path(level), error))
+}
+
+fn load_reactions() -> ReactionTable {
+	let bytes = fs::read(assets_path().join(get_reaction_table_path()))
+		.unwrap_or_else(|error| panic!("{}: {}", get_reaction_table_path(), error));
+	ReactionTable::from_ron(&bytes)
+		.unwrap_or_else(|error| panic!("{}: {}", get_reaction_table_path(), error))
+}
+
+fn load_registry() -> MoleculeRegistry {
+	let bytes = fs::read(assets_path().join(get_molecule_registry_path()))
+		.unwrap_or_else(|error| panic!("{}: {}", get_molecule_registry_path(), error));
+	MoleculeRegistry::from_ron(&bytes)
+		.unwrap_or_else(|error| panic!("{}: {}", get_molecule_registry_path(), error))
+}
+
+// Every molecule which could ever be in the level, starting from the
+// ones placed in it and the ones the player can launch, then following
+// the reaction table until nothing new can be made
+fn reachable_molecules(
+	level: &LevelDefinition,
+	reactions: &ReactionTable,
+) -> [bool; TOTAL_MOLECULE_TYPES] {
+	let mut reachable = [false; TOTAL_MOLECULE_TYPES];
+	for index in level.available_molecules.iter() {
+		reachable[*index] = true;
+	}
+	for reactor in level.reactors.iter() {
+		for molecule in reactor.molecules.iter() {
+			reachable[molecule.index] = true;
+		}
+	}
+	loop {
+		let mut changed = false;
+		let products: Vec<usize> = reactions.reactions.iter()
+			.filter(|reaction| reachable[reaction.reactants.0] && reachable[reaction.reactants.1])
+			.flat_map(|reaction| reaction.products.iter().copied())
+			.chain(reactions.decays.iter()
+				.filter(|decay| reachable[decay.reactant])
+				.flat_map(|decay| decay.products.iter().copied()))
+			.collect();
+		for product in products {
+			if !reachable[product] {
+				reachable[product] = true;
+				changed = true;
+			}
+		}
+		if !changed {
+			return reachable;
+		}
+	}
+}
+
+// Sandbox levels ask for a molecule that can never be made,
+// so they are left open for the player to experiment in
+fn is_sandbox(
+	level: &LevelDefinition,
+	reactions: &ReactionTable,
+) -> bool {
+	needs_unreachable(&level.goal, &reachable_molecules(level, reactions))
+}
+
+fn needs_unreachable(
+	goal: &WinCondition,
+	reachable: &[bool; TOTAL_MOLECULE_TYPES],
+) -> bool {
+	match goal {
+		WinCondition::GreaterThan(desired_quantity, desired_molecule)
+		| WinCondition::Exactly(desired_quantity, desired_molecule)
+		| WinCondition::Delivered(desired_quantity, desired_molecule, ..)
+		| WinCondition::Received(desired_quantity, desired_molecule, _) => *desired_quantity > 0 && !reachable[*desired_molecule],
+		WinCondition::LessThan(..) => false,
+		WinCondition::Ratio(desired_molecule, ..) => !reachable[*desired_molecule],
+		WinCondition::InChamber(_, goal) | WinCondition::Sustain(_, goal) => needs_unreachable(goal, reachable),
+		WinCondition::All(goals) => goals.iter().any(|goal| needs_unreachable(goal, reachable)),
+		WinCondition::Any(goals) => goals.iter().all(|goal| needs_unreachable(goal, reachable)),
+	}
+}
+
+#[test]
+fn reference_solutions_complete_their_levels() {
+	let reactions = load_reactions();
+	for level_id in 0..NUMBER_OF_LEVELS {
+		let level = load_level(level_id);
+		let Ok(bytes) = fs::read(solution_path(level_id)) else {
+			assert!(is_sandbox(&level, &reactions), "level {} has no reference solution at {}", level_id, solution_path(level_id).display());
+			continue;
+		};
+		let replay = Replay::from_ron(&bytes)
+			.unwrap_or_else(|error| panic!("{}: {}", solution_path(level_id).display(), error));
+		assert_eq!(replay.level, level_id, "{} is a replay of another level", solution_path(level_id).display());
+
+		let mut simulation = Simulation::from_assets(&assets_path(), level_id, replay.seed).unwrap();
+		for (reactor_id, reactor) in level.reactors.iter().enumerate() {
+			for index in 0..TOTAL_MOLECULE_TYPES {
+				let expected = reactor.molecules.iter().filter(|molecule| molecule.index == index).count();
+				assert_eq!(simulation.molecule_count(reactor_id, index), expected,
+					"level {} reactor {} starts with the wrong number of molecule {}", level_id, reactor_id, index);
+			}
+		}
+		simulation.play(replay)
+			.unwrap_or_else(|error| panic!("{}: {}", solution_path(level_id).display(), error));
+		assert!(simulation.run((TIME_BUDGET / PHYSICS_TIMESTEP).ceil() as u64),
+			"level {} was not completed by its reference solution within {} seconds", level_id, TIME_BUDGET);
+		// Par values are set so every star can be earned
+		assert_eq!(level.stars(simulation.elapsed_secs(), simulation.cost()), MAX_STARS,
+			"level {} reference solution took {:.2} s and {} c, missing its par", level_id, simulation.elapsed_secs(), simulation.cost());
+	}
+}
+
+// Sandbox levels have no par, so they never give more than
+// the star for completing them
+#[test]
+fn missing_pars_give_no_star() {
+	let level = LevelDefinition{par_time: None, par_cost: None, ..load_level(0)};
+	assert_eq!(level.stars(0.0, 0), 1);
+	let level = LevelDefinition{par_cost: None, ..load_level(0)};
+	assert_eq!(level.stars(0.0, 0), 2);
+}
+
+// The first level's solution replayed with limits it cannot meet
+#[test]
+fn limits_fail_an_attempt() {
+	let replay = Replay::from_ron(&fs::read(solution_path(0)).unwrap()).unwrap();
+	let limits = [
+		(Some(1), None, FailReason::OverBudget),
+		(None, Some(1.0), FailReason::OutOfTime),
+	];
+	for (cost_limit, time_limit, reason) in limits {
+		let level = LevelDefinition{cost_limit, time_limit, ..load_level(0)};
+		let mut simulation = Simulation::new(0, &level, load_registry(), load_reactions(), replay.seed);
+		simulation.play(replay.clone()).unwrap();
+		assert!(!simulation.run((TIME_BUDGET / PHYSICS_TIMESTEP).ceil() as u64));
+		assert_eq!(simulation.failed(), Some(reason));
+	}
+}
+
+// Replays edited by hand to launch what the level does not offer, or
+// from somewhere the launch tube cannot reach, are turned away before
+// any of their inputs are played
+#[test]
+fn forged_replays_are_turned_away() {
+	let replay = Replay::from_ron(&fs::read(solution_path(0)).unwrap()).unwrap();
+	let RecordedInput{input: ReactorInput::Launch{location, velocity, ..}, ..} = replay.inputs[0] else {
+		panic!("level 0 solution should start with a launch");
+	};
+	let forgeries = [
+		// The goal molecule, which the level does not offer
+		ReactorInput::Launch{reactor_id: 0, index: 2, location, velocity},
+		ReactorInput::Launch{reactor_id: 0, index: 99, location, velocity},
+		ReactorInput::Launch{reactor_id: 1, index: 0, location, velocity},
+		// Placed in the middle of the reactor, away from the launch tube
+		ReactorInput::Launch{reactor_id: 0, index: 0, location: Vec2::ZERO, velocity},
+		ReactorInput::Launch{reactor_id: 0, index: 0, location, velocity: velocity * 2.0},
+		ReactorInput::Launch{reactor_id: 0, index: 0, location, velocity: -velocity},
+		ReactorInput::SetConditions{reactor_id: 0, temperature: 2.0, pressure: 0.0},
+		ReactorInput::SetConditions{reactor_id: 0, temperature: 0.0, pressure: f32::NAN},
+		ReactorInput::PlaceSpawner{reactor_id: 0, index: 99, location, angle: 0.0},
+		ReactorInput::SelectReactor(1),
+	];
+	for forgery in forgeries {
+		let mut forged = replay.clone();
+		forged.inputs[0].input = forgery;
+		let mut simulation = Simulation::from_assets(&assets_path(), 0, forged.seed).unwrap();
+		assert!(simulation.play(forged).is_err(), "a replay with {:?} was played", forgery);
+	}
+	let mut simulation = Simulation::from_assets(&assets_path(), 1, replay.seed).unwrap();
+	assert!(simulation.play(replay).is_err(), "a replay of level 0 was played on level 1");
+}
+
+#[test]
+fn solutions_are_only_kept_for_shipped_levels() {
+	for entry in fs::read_dir(solution_path(0).parent().unwrap()).unwrap() {
+		let name = entry.unwrap().file_name().into_string().unwrap();
+		let shipped = (0..NUMBER_OF_LEVELS).any(|level| solution_path(level).ends_with(&name));
+		assert!(shipped, "tests/solutions/{} does not belong to a shipped level", name);
+	}
+}
+
+#[test]
+fn every_intake_has_a_matching_outlet() {
+	for level_id in 0..NUMBER_OF_LEVELS {
+		let level = load_level(level_id);
+		for (reactor_id, reactor) in level.reactors.iter().enumerate() {
+			for intake in reactor.connections.iter().filter(|connection| connection.intake) {
+				let outlets = level.reactors.iter()
+					.flat_map(|reactor| reactor.connections.iter())
+					.filter(|connection| !connection.intake && connection.connection_id == intake.connection_id)
+					.count();
+				assert_eq!(outlets, 1, "level {} reactor {} intake {} has {} outlets", level_id, reactor_id, intake.connection_id, outlets);
+			}
+		}
+	}
+}
+
+// Goals limited to one chamber count nothing in the others,
+// so those would have no reason to be product chambers
+#[test]
+fn every_product_chamber_is_counted_by_the_goal() {
+	for level_id in 0..NUMBER_OF_LEVELS {
+		let level = load_level(level_id);
+		let sub_goals = level.goal.sub_goals();
+		if sub_goals.iter().any(|(chamber, _)| chamber.is_none()) {
+			continue;
+		}
+		for (reactor_id, _) in level.reactors.iter().enumerate().filter(|(_, reactor)| reactor.product_chamber) {
+			assert!(sub_goals.iter().any(|(chamber, _)| *chamber == Some(reactor_id)),
+				"level {} product chamber {} is not counted by the goal", level_id, reactor_id);
+		}
+	}
+}
+
+#[test]
+fn every_level_has_an_input_chamber() {
+	for level_id in 0..NUMBER_OF_LEVELS {
+		let level = load_level(level_id);
+		assert!(level.reactors.iter().any(|reactor| reactor.input_chamber), "level {} has no input chamber", level_id);
+	}
+}
+
+// Spawners are placed through the same inputs as launches, so
+// they are kept in the replay and placed again when it is played
+#[test]
+fn spawners_are_recorded_and_played_back() {
+	let sandbox = (0..NUMBER_OF_LEVELS).find(|level| is_sandbox(&load_level(*level), &load_reactions())).unwrap();
+	let steps = (SPAWNER_INTERVAL / PHYSICS_TIMESTEP).ceil() as u64 + 2;
+	let mut simulation = Simulation::from_assets(&assets_path(), sandbox, 0).unwrap();
+	let placed = simulation.molecule_count(0, 0);
+	simulation.input(ReactorInput::PlaceSpawner{reactor_id: 0, index: 0, location: Vec2::ZERO, angle: 0.0});
+	simulation.run(steps);
+	assert!(matches!(simulation.recording().inputs[..], [RecordedInput{tick: 0, input: ReactorInput::PlaceSpawner{..}}]));
+	assert_eq!(simulation.molecule_count(0, 0), placed + 1, "the spawner did not spawn a molecule");
+
+	let replay = simulation.recording().clone();
+	let mut playback = Simulation::from_assets(&assets_path(), sandbox, replay.seed).unwrap();
+	playback.play(replay).unwrap();
+	playback.run(steps);
+	assert_eq!(playback.molecule_count(0, 0), placed + 1, "the spawner was not placed again by the replay");
+}
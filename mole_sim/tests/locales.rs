@@ -0,0 +1,83 @@
+// Checks over the shipped string tables. Translations may be missing
+// keys, as those are shown in the fallback language, but every key
+// the game looks up must be in the fallback table
+
+// Import file reading and paths to the assets and game source
+use std::{fs, path::PathBuf};
+// Import the string tables and their checks
+use mole_sim::{components::*, locale::*};
+
+fn assets_path() -> PathBuf {
+	PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../assets")
+}
+
+fn load_string_table(
+	language: &str,
+) -> StringTable {
+	let bytes = fs::read(assets_path().join(get_string_table_path(language)))
+		.unwrap_or_else(|error| panic!("{}: {}", get_string_table_path(language), error));
+	StringTable::from_ron(&bytes)
+		.unwrap_or_else(|error| panic!("{}: {}", get_string_table_path(language), error))
+}
+
+// Keys written out in full in the game's and the simulation's
+// source, found as string literals shaped like a key in one of the
+// fallback's sections, so file extensions such as level.ron are not
+// mistaken for keys
+fn get_keys_used_by_game(
+	fallback: &StringTable,
+) -> Vec<(String, String)> {
+	let sections: Vec<&str> = fallback.strings.keys().filter_map(|key| key.split('.').next()).collect();
+	let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+	let paths = [manifest.join("../src"), manifest.join("src")].into_iter()
+		.flat_map(|source| fs::read_dir(source).expect("game source should be readable"))
+		.map(|entry| entry.expect("game source should be readable").path())
+		.filter(|path| path.is_file());
+	let mut keys = Vec::new();
+	for path in paths {
+		let text = fs::read_to_string(&path).unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+		for literal in text.split('"').skip(1).step_by(2) {
+			if is_string_key(literal) && literal.split('.').next().is_some_and(|section| sections.contains(&section)) {
+				keys.push((path.file_name().unwrap().to_string_lossy().to_string(), literal.to_string()));
+			}
+		}
+	}
+	keys
+}
+
+#[test]
+fn every_language_has_a_string_table() {
+	for (language, name) in LANGUAGES {
+		let table = load_string_table(language);
+		assert_eq!(table.language, name, "{} should be named the same as in LANGUAGES", get_string_table_path(language));
+	}
+	assert!(LANGUAGES.iter().any(|(language, _)| *language == FALLBACK_LANGUAGE));
+}
+
+#[test]
+fn translations_only_use_keys_from_the_fallback() {
+	let fallback = load_string_table(FALLBACK_LANGUAGE);
+	for (language, _) in LANGUAGES {
+		let report = check_string_table(&load_string_table(language), &fallback);
+		assert!(report.unknown.is_empty(), "{} has keys the game does not use: {:?}", language, report.unknown);
+		assert!(report.mismatched.is_empty(), "{} has placeholders which differ from the fallback: {:?}", language, report.mismatched);
+	}
+}
+
+#[test]
+fn fallback_has_every_key_the_game_uses() {
+	let fallback = load_string_table(FALLBACK_LANGUAGE);
+	let keys = get_keys_used_by_game(&fallback);
+	assert!(!keys.is_empty(), "no keys were found in the game source");
+	for (file, key) in keys {
+		assert!(fallback.get(&key).is_some(), "{} looks up {} but the fallback table does not have it", file, key);
+	}
+}
+
+#[test]
+fn placeholders_are_filled_in() {
+	assert_eq!(get_placeholders("{1} of {0}, {1}"), Ok([0, 1].into()));
+	assert!(get_placeholders("{one}").is_err());
+	assert!(get_placeholders("{0").is_err());
+	assert_eq!(fill_placeholders("{1} of {0}, {1}", &["a".to_string(), "b".to_string()]), "b of a, b");
+}
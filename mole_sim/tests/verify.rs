@@ -0,0 +1,58 @@
+// Runs the verify command on replays written to a temporary folder,
+// checking the exit code the way a script checking solutions would
+
+// Import file writing, paths and running the command
+use std::{fs, path::PathBuf, process::Command};
+
+fn assets_path() -> PathBuf {
+	PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../assets")
+}
+
+fn solution_path(
+	level: usize,
+) -> PathBuf {
+	PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!("tests/solutions/level_{:02}.replay.ron", level))
+}
+
+// Writes the replay where only this test will read it, then runs
+// verify on it, returning the exit code and what was printed
+fn verify(
+	name: &str,
+	level: usize,
+	replay: &str,
+) -> (Option<i32>, String) {
+	let path = std::env::temp_dir().join(format!("mole_rancher_{}_{}.replay.ron", std::process::id(), name));
+	fs::write(&path, replay).unwrap();
+	let output = Command::new(env!("CARGO_BIN_EXE_mole_rancher"))
+		.args(["verify", &level.to_string()])
+		.arg(&path)
+		.arg("--assets")
+		.arg(assets_path())
+		.output()
+		.unwrap();
+	fs::remove_file(&path).unwrap();
+	(output.status.code(), String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[test]
+fn reference_solution_passes() {
+	let (code, stdout) = verify("solution", 0, &fs::read_to_string(solution_path(0)).unwrap());
+	assert_eq!(code, Some(0), "{}", stdout);
+	assert!(stdout.contains("PASS"), "{}", stdout);
+}
+
+// Launching the goal molecule, which level 0 does not offer, or a
+// molecule which does not exist fails rather than passing or panicking
+#[test]
+fn forged_replays_fail() {
+	let forgeries = [
+		("goal_molecule", "(level:0,seed:0,inputs:[(tick:0,input:Launch(reactor_id:0,index:2,location:(0.0,952.0),velocity:(0.0,-1000.0)))])"),
+		("missing_molecule", "(level:0,seed:0,inputs:[(tick:0,input:Launch(reactor_id:0,index:99,location:(0.0,0.0),velocity:(0.0,0.0)))])"),
+		("missing_reactor", "(level:0,seed:0,inputs:[(tick:0,input:SetConditions(reactor_id:4,temperature:0.5,pressure:0.5))])"),
+	];
+	for (name, replay) in forgeries {
+		let (code, stdout) = verify(name, 0, replay);
+		assert_eq!(code, Some(1), "{}: {}", name, stdout);
+		assert!(stdout.contains("FAIL") && stdout.contains("Illegal input"), "{}: {}", name, stdout);
+	}
+}
@@ -1,10 +1,10 @@
 // Import Bevy game engine essentials
-use bevy::{prelude::*, app::AppExit, render::view::RenderLayers};
+use bevy::{prelude::*, app::AppExit};
 use bevy_kira_audio::{Audio, AudioControl};
 // Import Pkv Store for saving and loading game data
 use bevy_pkv::PkvStore;
 // Import components, resources, and events
-use crate::components::*;
+use crate::{components::*, layout::get_scaled_box_size, levels::Levels, replay::load_replay, save::{clear_replays, copy_replays, export_save, get_display_name, get_profile_key, import_save, load_profile, read_save_export}};
 
 // Plugin for handling button interactions and
 // state changes
@@ -34,9 +34,12 @@ fn standard_buttons(
 	ortho_size: Res<OrthoSize>,
 	mouse: Res<Input<MouseButton>>,
 	current_state: Res<State<PauseState>>,
-	pkv: Res<PkvStore>,
+	settings: Res<Settings>,
 	audio: Res<Audio>,
 	asset_server: Res<AssetServer>,
+	(registry, locale): (Res<MoleculeRegistry>, Res<Locale>),
+	selected_profile: Res<SelectedProfile>,
+	focus: Res<ButtonFocus>,
 	mut button_query: Query<(&mut Sprite, &StandardButton, &ButtonEffect)>,
 	mut tooltip_text_query: Query<(&mut Text, With<TooltipText>)>,
 	mut animation_query: Query<(&mut TextureAtlasSprite, &mut AnimationTimer, &AnimationIndices, &MoleculeButton)>,
@@ -56,48 +59,45 @@ fn standard_buttons(
 			if button.enabled {
 				if *current_state == PauseState::Paused {
 					match effect {
-						ButtonEffect::PopupButton(_) => (),
+						ButtonEffect::Popup(_) => (),
 						_ => continue,
 					}
 				}
-				sprite.color = button.idle_color;
+				// The focused button is drawn as if hovered
+				sprite.color = if focus.0 == Some(*effect) {button.hovered_color} else {button.idle_color};
 				if (button.location.x - p.x).abs() < button.dimensions.width / 2.0 && (button.location.y - p.y).abs() < button.dimensions.height / 2.0 {
 					hovering_any = true;
 					sprite.color = button.hovered_color;
-					match effect {
-						ButtonEffect::ReactorButton(ReactorButton::SelectMolecule(index)) => {
-							for (mut transform, _) in tooltip_query.iter_mut() {
-								let offset = if index < &9 {
-									Vec2::new(TOOLTIP_WIDTH/2.0, -TOOLTIP_HEIGHT/2.0)
-								} else {
-									Vec2::new(TOOLTIP_WIDTH/2.0, TOOLTIP_HEIGHT/2.0)
-								};
-								transform.translation = Vec3::new(
-									p.x + offset.x,
-									p.y + offset.y,
-									900.0,
-								);
-								for (mut spritesheet, mut timer, indices, molecule) in animation_query.iter_mut() {
-									if molecule.0 == *index {
-										timer.0.tick(time.delta());
-										if timer.0.just_finished() {
-											spritesheet.index = (spritesheet.index + 1) % indices.total + indices.first;
-										}
+					if let ButtonEffect::Reactor(ReactorButton::SelectMolecule(index)) = effect {
+						for (mut transform, _) in tooltip_query.iter_mut() {
+							let size = get_scaled_box_size(ScaledBox::Tooltip, settings.ui_scale);
+							let offset = if index < &9 {
+								Vec2::new(size.x/2.0, -size.y/2.0)
+							} else {
+								Vec2::new(size.x/2.0, size.y/2.0)
+							};
+							transform.translation = Vec3::new(
+								p.x + offset.x,
+								p.y + offset.y,
+								900.0,
+							);
+							for (mut spritesheet, mut timer, indices, molecule) in animation_query.iter_mut() {
+								if molecule.0 == *index {
+									timer.0.tick(time.delta());
+									if timer.0.just_finished() {
+										spritesheet.index = (spritesheet.index + 1) % indices.total + indices.first;
 									}
-								};
-							}
-							for (mut text, _) in tooltip_text_query.iter_mut() {
-								text.sections[0].value = get_tooltip_text(*index, true);
-							}
-						},
-						_ => (),
+								}
+							};
+						}
+						for (mut text, _) in tooltip_text_query.iter_mut() {
+							text.sections[0].value = locale.get_or(&get_molecule_tooltip_key(*index, true), &registry.tooltip(*index, true));
+						}
 					}
 					if mouse.just_pressed(MouseButton::Left) {
-						if let Ok(save_data) = pkv.get::<SaveData>("save_data") {
-							audio
-								.play(asset_server.load("audio/haptics/click.wav"))
-								.with_volume(save_data.sfx_volume);
-						}
+						audio
+							.play(asset_server.load("audio/haptics/click.wav"))
+							.with_volume(settings.sfx_volume);
 						ev_w_button_call.send(ButtonCall(*effect));
 					}
 				}
@@ -105,34 +105,32 @@ fn standard_buttons(
 				sprite.color = button.disabled_color;
 				if (button.location.x - p.x).abs() < button.dimensions.width / 2.0 && (button.location.y - p.y).abs() < button.dimensions.height / 2.0 {
 					hovering_any = true;
-					match effect {
-						ButtonEffect::ReactorButton(ReactorButton::SelectMolecule(index)) => {
-							for (mut transform, _) in tooltip_query.iter_mut() {
-								let offset = if index < &9 {
-									Vec2::new(TOOLTIP_WIDTH/2.0, -TOOLTIP_HEIGHT/2.0)
-								} else {
-									Vec2::new(TOOLTIP_WIDTH/2.0, TOOLTIP_HEIGHT/2.0)
-								};
-								transform.translation = Vec3::new(
-									p.x + offset.x,
-									p.y + offset.y,
-									900.0,
-								);
-								for (mut spritesheet, mut timer, indices, molecule) in animation_query.iter_mut() {
-									if molecule.0 == *index {
-										timer.0.tick(time.delta());
-										if timer.0.just_finished() {
-											spritesheet.index = (spritesheet.index + 1) % indices.total + indices.first;
-										}
+					if let ButtonEffect::Reactor(ReactorButton::SelectMolecule(index)) = effect {
+						for (mut transform, _) in tooltip_query.iter_mut() {
+							let size = get_scaled_box_size(ScaledBox::Tooltip, settings.ui_scale);
+							let offset = if index < &9 {
+								Vec2::new(size.x/2.0, -size.y/2.0)
+							} else {
+								Vec2::new(size.x/2.0, size.y/2.0)
+							};
+							transform.translation = Vec3::new(
+								p.x + offset.x,
+								p.y + offset.y,
+								900.0,
+							);
+							for (mut spritesheet, mut timer, indices, molecule) in animation_query.iter_mut() {
+								if molecule.0 == *index {
+									timer.0.tick(time.delta());
+									if timer.0.just_finished() {
+										spritesheet.index = (spritesheet.index + 1) % indices.total + indices.first;
 									}
-								};
-							}
-							for (mut text, _) in tooltip_text_query.iter_mut() {
-								text.sections[0].value = get_tooltip_text(*index, false);
-							}
-						},
-						_ => (),
-					};
+								}
+							};
+						}
+						for (mut text, _) in tooltip_text_query.iter_mut() {
+							text.sections[0].value = locale.get_or(&get_molecule_tooltip_key(*index, false), &registry.tooltip(*index, false));
+						}
+					}
 				}
 			}
 		}
@@ -147,30 +145,35 @@ fn standard_buttons(
 		};
 	}
 	for (mut sprite, button, effect) in button_query.iter_mut() {
-		if let Ok(save_data) = pkv.get::<SaveData>("save_data") {
-			match effect {
-				ButtonEffect::PopupButton(PopupButton::BgmVolume(i)) => {
-					if (save_data.bgm_volume * 10.0) as usize == *i {
-						sprite.color = button.disabled_color;
-					}
-				},
-				ButtonEffect::PopupButton(PopupButton::SfxVolume(i)) => {
-					if (save_data.sfx_volume * 10.0) as usize == *i {
-						sprite.color = button.disabled_color;
-					}
-				}
-				ButtonEffect::PopupButton(PopupButton::ParticleTrails(enable)) => {	
-					if save_data.particles_enabled == *enable {
-						sprite.color = button.disabled_color;
-					}
-				}
-				ButtonEffect::PopupButton(PopupButton::Fullscreen(enable)) => {	
-					if save_data.fullscreen_enabled == *enable {
-						sprite.color = button.disabled_color;
-					}
-				}
-				_ => (),
-			}
+		match effect {
+			ButtonEffect::Popup(PopupButton::BgmVolume(i)) if (settings.bgm_volume * 10.0) as usize == *i => {
+				sprite.color = button.disabled_color;
+			},
+			ButtonEffect::Popup(PopupButton::SfxVolume(i)) if (settings.sfx_volume * 10.0) as usize == *i => {
+				sprite.color = button.disabled_color;
+			},
+			ButtonEffect::Popup(PopupButton::ParticleTrails(enable)) if settings.particles_enabled == *enable => {
+				sprite.color = button.disabled_color;
+			},
+			ButtonEffect::Popup(PopupButton::Fullscreen(enable)) if settings.fullscreen_enabled == *enable => {
+				sprite.color = button.disabled_color;
+			},
+			ButtonEffect::Popup(PopupButton::Glyphs(enable)) if settings.glyphs_enabled == *enable => {
+				sprite.color = button.disabled_color;
+			},
+			ButtonEffect::Popup(PopupButton::TextScale(i)) if settings.text_scale == SCALE_STEPS[*i] => {
+				sprite.color = button.disabled_color;
+			},
+			ButtonEffect::Popup(PopupButton::UiScale(i)) if settings.ui_scale == SCALE_STEPS[*i] => {
+				sprite.color = button.disabled_color;
+			},
+			ButtonEffect::Popup(PopupButton::Language(i)) if settings.language == LANGUAGES[*i].0 => {
+				sprite.color = button.disabled_color;
+			},
+			ButtonEffect::Popup(PopupButton::SelectProfile(slot)) if selected_profile.0 == *slot => {
+				sprite.color = button.disabled_color;
+			},
+			_ => (),
 		}
 	}
 }
@@ -184,21 +187,7 @@ fn custom_lab_buttons(
 	mut interaction_query: Query<(&mut Visibility, &ButtonEffect)>,
 	mut ev_w_button_call: EventWriter<ButtonCall>,
 ) {
-	// Top Left, Top Right, Bottom Right, Bottom Left, Lab Interaction
-	let buttons = [
-		([Vec2::new(270.0, 88.0), Vec2::new(596.0, 74.0), Vec2::new(573.0, -163.0), Vec2::new(253.0, -97.0)], 
-		ButtonEffect::CustomLabButton(CustomLabButton::MonitorActivate)), // Monitor
-		([Vec2::new(260.0, -136.0), Vec2::new(546.0, -213.0), Vec2::new(490.0, -307.0), Vec2::new(156.0, -186.0)], 
-		ButtonEffect::CustomLabButton(CustomLabButton::MonitorActivate)), // Keyboard
-		([Vec2::new(-604.0, -177.0), Vec2::new(-275.0, -146.0), Vec2::new(-117.0, -287.0), Vec2::new(-639.0, -351.0)], 
-		ButtonEffect::CustomLabButton(CustomLabButton::LogbookOpen)), // Logbook
-		([Vec2::new(-800.0, 450.0), Vec2::new(-786.0, 450.0), Vec2::new(-666.0, -195.0), Vec2::new(-800.0, -287.0)], 
-		ButtonEffect::CustomLabButton(CustomLabButton::ExitLab)), // Door
-		([Vec2::new(-730.0, 417.0), Vec2::new(-543.0, 413.0), Vec2::new(-538.0, 336.0), Vec2::new(-730.0, 336.0)], 
-		ButtonEffect::CustomLabButton(CustomLabButton::ExitLab)), // Exit Sign
-		/*([Vec2::new(492.0, 322.0), Vec2::new(796.0, 350.0), Vec2::new(652.0, -18.0), Vec2::new(404.0, 34.0)], 
-		ButtonEffect::CustomLabButton(CustomLabButton::Poster)), // Poster */
-	];
+	let buttons = get_lab_button_shapes();
 
 	// Get the current window, and the cursor position scaled 
 	// to the window size
@@ -250,15 +239,35 @@ fn custom_lab_buttons(
 	}
 }
 
+// Outlines of the interactable elements in the lab, also
+// used to move focus between them without the mouse
+pub fn get_lab_button_shapes() -> [([Vec2; 4], ButtonEffect); 5] {
+	// Top Left, Top Right, Bottom Right, Bottom Left, Lab Interaction
+	[
+		([Vec2::new(270.0, 88.0), Vec2::new(596.0, 74.0), Vec2::new(573.0, -163.0), Vec2::new(253.0, -97.0)], 
+		ButtonEffect::CustomLab(CustomLabButton::MonitorActivate)), // Monitor
+		([Vec2::new(260.0, -136.0), Vec2::new(546.0, -213.0), Vec2::new(490.0, -307.0), Vec2::new(156.0, -186.0)], 
+		ButtonEffect::CustomLab(CustomLabButton::MonitorActivate)), // Keyboard
+		([Vec2::new(-604.0, -177.0), Vec2::new(-275.0, -146.0), Vec2::new(-117.0, -287.0), Vec2::new(-639.0, -351.0)], 
+		ButtonEffect::CustomLab(CustomLabButton::LogbookOpen)), // Logbook
+		([Vec2::new(-800.0, 450.0), Vec2::new(-786.0, 450.0), Vec2::new(-666.0, -195.0), Vec2::new(-800.0, -287.0)], 
+		ButtonEffect::CustomLab(CustomLabButton::ExitLab)), // Door
+		([Vec2::new(-730.0, 417.0), Vec2::new(-543.0, 413.0), Vec2::new(-538.0, 336.0), Vec2::new(-730.0, 336.0)], 
+		ButtonEffect::CustomLab(CustomLabButton::ExitLab)), // Exit Sign
+		/*([Vec2::new(492.0, 322.0), Vec2::new(796.0, 350.0), Vec2::new(652.0, -18.0), Vec2::new(404.0, 34.0)], 
+		ButtonEffect::CustomLab(CustomLabButton::Poster)), // Poster */
+	]
+}
+
 // Handle all the buttons calls by calling the respective transitions
 // or toggling visibility on sprites
 fn handle_button_calls(
-	asset_server: Res<AssetServer>,
-	mut pkv: ResMut<PkvStore>,
+	(asset_server, locale, mut input_queue, mut selected_molecule_type): (Res<AssetServer>, Res<Locale>, ResMut<InputQueue>, ResMut<SelectedMoleculeType>),
+	(mut pkv, mut settings, mut rebinding, mut profiles, mut selected_profile, mut pending_profile_action, mut save_transfer_message, mut pending_save_import): (ResMut<PkvStore>, ResMut<Settings>, ResMut<Rebinding>, ResMut<Profiles>, ResMut<SelectedProfile>, ResMut<PendingProfileAction>, ResMut<SaveTransferMessage>, ResMut<PendingSaveImport>),
+	(mut commands, popup_entity_query): (Commands, Query<Entity, With<DespawnOnExitPauseState>>),
 	mut cutscene_tracker: ResMut<CutsceneTracker>,
-	mut selected_level: ResMut<SelectedLevel>,
+	(mut selected_level, levels, registry): (ResMut<SelectedLevel>, Levels, Res<MoleculeRegistry>),
 	mut selected_palette: ResMut<SelectedPalette>,
-	mut selected_molecule_type: ResMut<SelectedMoleculeType>,
 	mut ev_r_button_call: EventReader<ButtonCall>,
 	mut ev_w_exit: EventWriter<AppExit>,
 	mut ev_w_fade_transition: EventWriter<FadeTransitionEvent>,
@@ -266,28 +275,37 @@ fn handle_button_calls(
 	mut ev_w_popup: EventWriter<PopupEvent>,
 	mut logbook_text_query: Query<(&mut Text, &LogbookText)>,
 	mut bright_lab_query: Query<(&mut Visibility, With<BrightLab>)>,
-	mut palette_query: Query<(&mut Sprite, &Palette)>,
 	mut next_pause_state: ResMut<NextState<PauseState>>,
 	mut logbook_tab_query: Query<(&mut Transform, &ButtonEffect)>,
 ) {
 	for ev in ev_r_button_call.iter() {
 		match ev.0 {
-			ButtonEffect::MenuButton(ref effect) => {
+			ButtonEffect::Menu(ref effect) => {
 				match effect {
 					MenuButton::StartGame => {
-						if let Ok(mut save_data) = pkv.get::<SaveData>("save_data") {
-							if save_data.levels_unlocked[0] == false {
+						if let Ok(mut save_data) = pkv.get::<SaveData>(get_profile_key(selected_profile.0)) {
+							if !save_data.levels_unlocked[0] {
 								cutscene_tracker.cutscene_state = CutsceneState::Initialize;
 								ev_w_fade_transition.send(FadeTransitionEvent(GameState::Cutscene));
 								save_data.levels_unlocked[0] = true;
-								pkv.set("save_data", &save_data)
+								pkv.set(get_profile_key(selected_profile.0), &save_data)
 									.expect("Unable to save data");
 							} else {
 								ev_w_fade_transition.send(FadeTransitionEvent(GameState::Lab));
 							}
 						}
 					},
+					MenuButton::Profiles => {
+						next_pause_state.set(PauseState::Paused);
+						ev_w_popup.send(PopupEvent{ 
+							origin: Vec2::new(0.0, -70.0), 
+							image: asset_server.load("sprites/popup/popup.png"),
+							alpha: 1.0,
+							popup_type: PopupType::Profiles,
+						});
+					},
 					MenuButton::Settings => {
+						save_transfer_message.0 = "".to_string();
 						next_pause_state.set(PauseState::Paused);
 						ev_w_popup.send(PopupEvent{ 
 							origin: Vec2::new(0.0, -70.0), 
@@ -299,7 +317,7 @@ fn handle_button_calls(
 					MenuButton::ExitGame => ev_w_exit.send(AppExit),
 				}
 			}
-			ButtonEffect::CustomLabButton(ref effect) => {
+			ButtonEffect::CustomLab(ref effect) => {
 				match effect {
 					// Show bright lab, play reactor sfx, display level select popup
 					CustomLabButton::MonitorActivate => {
@@ -326,71 +344,87 @@ fn handle_button_calls(
 						// Fade transition back to menu, play door sfx
 						ev_w_fade_transition.send(FadeTransitionEvent(GameState::Menu));
 					},
-					CustomLabButton::Poster => {
-						// Play cat sfx
-					},
 				};
 			},
-			ButtonEffect::PopupButton(ref effect) => {
+			ButtonEffect::Popup(ref effect) => {
 				match effect {
 					PopupButton::BgmVolume(volume) => {
-						if let Ok(mut save_data) = pkv.get::<SaveData>("save_data") {
-							save_data.bgm_volume = *volume as f64/10.0;
-							pkv.set("save_data", &save_data)
-									.expect("Unable to save data");
-						}
+						settings.bgm_volume = *volume as f64/10.0;
 					},
 					PopupButton::SfxVolume(volume) => {
-						if let Ok(mut save_data) = pkv.get::<SaveData>("save_data") {
-							save_data.sfx_volume = *volume as f64/10.0;
-							pkv.set("save_data", &save_data)
-									.expect("Unable to save data");
-						}
+						settings.sfx_volume = *volume as f64/10.0;
 					},
 					PopupButton::PaletteToggle => {
-						selected_palette.0 = (selected_palette.0 + 1) % 4;
-						for (mut sprite, palette) in palette_query.iter_mut() {
-							sprite.color = get_molecule_color(palette.0, selected_palette.0);
-						}
-						if let Ok(mut save_data) = pkv.get::<SaveData>("save_data") {
-							save_data.selected_palette = selected_palette.0;
-							pkv.set("save_data", &save_data)
-									.expect("Unable to save data");
-						}
+						selected_palette.0 = (selected_palette.0 + 1) % NUMBER_OF_PALETTES;
+						settings.selected_palette = selected_palette.0;
 					},
 					PopupButton::ParticleTrails(enable) => {
-						if let Ok(mut save_data) = pkv.get::<SaveData>("save_data") {
-							save_data.particles_enabled = *enable;
-							pkv.set("save_data", &save_data)
-									.expect("Unable to save data");
-						}
+						settings.particles_enabled = *enable;
 					},
 					PopupButton::Fullscreen(enable) => {
-						if let Ok(mut save_data) = pkv.get::<SaveData>("save_data") {
-							save_data.fullscreen_enabled = *enable;
-							pkv.set("save_data", &save_data)
-									.expect("Unable to save data");
+						settings.fullscreen_enabled = *enable;
+					},
+					PopupButton::Glyphs(enable) => {
+						settings.glyphs_enabled = *enable;
+					},
+					PopupButton::TextScale(i) => {
+						settings.text_scale = SCALE_STEPS[*i];
+					},
+					PopupButton::UiScale(i) => {
+						settings.ui_scale = SCALE_STEPS[*i];
+					},
+					PopupButton::ExportSave => {
+						*pending_profile_action = PendingProfileAction::None;
+						save_transfer_message.0 = match export_save(&pkv, &profiles, selected_profile.0, &settings, &locale) {
+							Ok(name) => locale.format("save.exported", &[name, SAVE_EXPORT_PATH.to_string()]),
+							Err(error) => locale.format("save.export_failed", &[error]),
+						};
+					},
+					PopupButton::ImportSave => {
+						// The export is checked on the first press, and only
+						// replaces the profile once pressed again to confirm
+						let confirmed = *pending_profile_action == PendingProfileAction::Import;
+						*pending_profile_action = PendingProfileAction::None;
+						match pending_save_import.0.take() {
+							Some(export) if confirmed => {
+								import_save(&mut pkv, &mut profiles, selected_profile.0, &export);
+								*settings = export.settings.clone();
+								selected_palette.0 = settings.selected_palette;
+								save_transfer_message.0 = locale.format("save.imported", &[get_display_name(&export.name, selected_profile.0, &locale)]);
+							},
+							_ => match read_save_export(|level| levels.try_get(level), &registry, &locale) {
+								Ok(export) => {
+									let current = profiles.display_name(selected_profile.0, &locale).unwrap_or_default();
+									*pending_profile_action = PendingProfileAction::Import;
+									save_transfer_message.0 = locale.format("save.confirm_import", &[
+										current,
+										get_display_name(&export.name, selected_profile.0, &locale),
+										export.save_data.levels_unlocked.iter().filter(|unlocked| **unlocked).count().to_string(),
+										export.save_data.stars.iter().sum::<usize>().to_string(),
+									]);
+									pending_save_import.0 = Some(export);
+								},
+								Err(error) => save_transfer_message.0 = locale.format("save.import_failed", &[error]),
+							},
 						}
 					},
 					PopupButton::LogbookPage(page) => {
 						for (mut transform, button) in logbook_tab_query.iter_mut() {
-							match button {
-								ButtonEffect::PopupButton(PopupButton::LogbookPage(i)) => {
-									if page == i {
-										transform.translation.z = 810.0;
-									} else {
-										transform.translation.z = 801.0;
-									}
-								},
-								_ => (),
+							if let ButtonEffect::Popup(PopupButton::LogbookPage(i)) = button {
+								transform.translation.z = if page == i {810.0} else {801.0};
 							}
 						}
 						for (mut text, side) in logbook_text_query.iter_mut() {
-							text.sections[0].value = get_logbook_text(*page, side.0);
+							text.sections[0].value = locale.get(get_logbook_key(*page, side.0));
 						}
 					},
+					// A level whose file failed to load is kept
+					// closed rather than crashing once entered
+					PopupButton::LevelSelect(level) if !levels.is_loaded(*level) => {
+						error!("Level file {} failed to load, so it cannot be played", get_level_path(*level));
+					},
 					PopupButton::LevelSelect(level) => {
-						if let Ok(save_data) = pkv.get::<SaveData>("save_data") {
+						if let Ok(save_data) = pkv.get::<SaveData>(get_profile_key(selected_profile.0)) {
 							if save_data.levels_unlocked[*level] {
 								selected_level.0 = *level;
 								next_pause_state.set(PauseState::Unpaused);
@@ -398,22 +432,89 @@ fn handle_button_calls(
 							}
 						}
 					},
+					PopupButton::SelectProfile(_)
+					| PopupButton::RenameProfile(_)
+					| PopupButton::CopyProfile(_)
+					| PopupButton::DeleteProfile(_) => {
+						// Pressing any profile button finishes typing a name
+						// and cancels a delete waiting to be confirmed
+						let pending = std::mem::take(&mut *pending_profile_action);
+						if let PendingProfileAction::Rename(slot) = pending {
+							profiles.finish_name(slot);
+						}
+						match effect {
+							PopupButton::SelectProfile(slot) => {
+								let start_over = pending == PendingProfileAction::StartOver(*slot);
+								if profiles.create(*slot) || start_over {
+									pkv.set(get_profile_key(*slot), &SaveData::default())
+										.expect("Unable to save data");
+									clear_replays(&mut pkv, *slot);
+								}
+								match load_profile(&mut pkv, *slot) {
+									Ok(()) => {
+										profiles.selected = *slot;
+										selected_profile.0 = *slot;
+									},
+									// An unreadable save is only replaced once
+									// the player presses the profile again
+									Err(error) => {
+										error!("{}", error);
+										*pending_profile_action = PendingProfileAction::StartOver(*slot);
+									},
+								}
+							},
+							PopupButton::RenameProfile(slot) if pending != PendingProfileAction::Rename(*slot) => {
+								*pending_profile_action = PendingProfileAction::Rename(*slot);
+							},
+							PopupButton::CopyProfile(slot) => {
+								if let Ok(save_data) = pkv.get::<SaveData>(get_profile_key(*slot)) {
+									let name = locale.format("profiles.copy_name", &[profiles.display_name(*slot, &locale).unwrap_or_default()]);
+									if let Some(copy) = profiles.copy(*slot, &name) {
+										pkv.set(get_profile_key(copy), &save_data)
+											.expect("Unable to save data");
+										copy_replays(&mut pkv, *slot, copy);
+									}
+								}
+							},
+							PopupButton::DeleteProfile(slot) => {
+								// Deleting needs a second press to confirm
+								if pending != PendingProfileAction::Delete(*slot) {
+									*pending_profile_action = PendingProfileAction::Delete(*slot);
+								} else if profiles.delete(*slot) {
+									pkv.set(get_profile_key(*slot), &SaveData::default())
+										.expect("Unable to save data");
+									clear_replays(&mut pkv, *slot);
+									selected_profile.0 = profiles.selected;
+									if let Err(error) = load_profile(&mut pkv, profiles.selected) {
+										error!("{}", error);
+									}
+								}
+							},
+							_ => (),
+						}
+					},
 					PopupButton::ReturnToLab => {
 						next_pause_state.set(PauseState::Unpaused);
 						ev_w_fade_transition.send(FadeTransitionEvent(GameState::Lab));
 					}
 					PopupButton::ReplayLevel => {
 						next_pause_state.set(PauseState::Unpaused);
-						ev_w_replay_level.send(ReplayLevelEvent);
+						ev_w_replay_level.send(ReplayLevelEvent(None));
+					},
+					PopupButton::WatchReplay => {
+						next_pause_state.set(PauseState::Unpaused);
+						if let Some(replay) = load_replay(&pkv, selected_profile.0, selected_level.0) {
+							ev_w_replay_level.send(ReplayLevelEvent(Some(replay)));
+						}
 					},
 					PopupButton::CompleteLevel => {
 						next_pause_state.set(PauseState::Unpaused);
-						if let Ok(mut save_data) = pkv.get::<SaveData>("save_data") {
+						if let Ok(mut save_data) = pkv.get::<SaveData>(get_profile_key(selected_profile.0)) {
 							if save_data.cutscenes_unlocked[selected_level.0 + 1] {
 								ev_w_fade_transition.send(FadeTransitionEvent(GameState::Lab));
 							} else {
 								save_data.cutscenes_unlocked[selected_level.0 + 1] = true;
-								pkv.set("save_data", &save_data)
+								pkv.set(get_profile_key(selected_profile.0), &save_data)
 										.expect("Unable to save data");
 								cutscene_tracker.cutscene_state = CutsceneState::Initialize;
 								cutscene_tracker.current_scene = selected_level.0 + 1;
@@ -421,18 +522,49 @@ fn handle_button_calls(
 							}
 						}
 					},
+					// Swaps the settings and controls popups without
+					// leaving the pause. Changing the language redraws
+					// the settings so they are shown in the new one
+					PopupButton::OpenControls | PopupButton::OpenSettings | PopupButton::Language(_) => {
+						if let PopupButton::Language(i) = effect {
+							settings.language = LANGUAGES[*i].0.to_string();
+						}
+						for entity in popup_entity_query.iter() {
+							commands.entity(entity).despawn_recursive();
+						}
+						rebinding.0 = None;
+						save_transfer_message.0 = "".to_string();
+						ev_w_popup.send(PopupEvent{ 
+							origin: Vec2::ZERO, 
+							image: asset_server.load("sprites/popup/popup.png"),
+							alpha: 1.0,
+							popup_type: if *effect == PopupButton::OpenControls {PopupType::Controls} else {PopupType::Settings},
+						});
+					},
+					PopupButton::Rebind(action, slot) => {
+						rebinding.0 = Some((*action, *slot));
+					},
+					PopupButton::ResetControls => {
+						rebinding.0 = None;
+						settings.controls = Controls::default();
+					},
 					PopupButton::ExitPopup => {
 						next_pause_state.set(PauseState::Unpaused);
+						rebinding.0 = None;
+						if let PendingProfileAction::Rename(slot) = std::mem::take(&mut *pending_profile_action) {
+							profiles.finish_name(slot);
+						}
 					},
 				}
 			},
-			ButtonEffect::ReactorButton(ref effect) => {
+			ButtonEffect::Reactor(ref effect) => {
 				match effect {
 					ReactorButton::SelectMolecule(molecule_index) => {
 						selected_molecule_type.0 = *molecule_index;
+						input_queue.0.push(ReactorInput::SelectMolecule(*molecule_index));
 					},
 					ReactorButton::RestartLevel => {
-						ev_w_replay_level.send(ReplayLevelEvent);
+						ev_w_replay_level.send(ReplayLevelEvent(None));
 					},
 					ReactorButton::PauseLevel => {
 						next_pause_state.set(PauseState::Paused);
@@ -448,7 +580,7 @@ fn handle_button_calls(
 					},
 				}
 			},
-			ButtonEffect::CutsceneButton(CutsceneButton::SkipCutscene) => {
+			ButtonEffect::Cutscene(CutsceneButton::SkipCutscene) => {
 				ev_w_fade_transition.send(FadeTransitionEvent(GameState::Lab));
 			},
 		}
@@ -457,22 +589,21 @@ fn handle_button_calls(
 
 fn replay_level(
 	molecule_query: Query<(Entity, With<Molecule>)>,
-	asset_server: Res<AssetServer>,
+	(asset_server, registry, mut sim_rng): (Res<AssetServer>, Res<MoleculeRegistry>, ResMut<SimRng>),
 	level: Res<SelectedLevel>,
-	selected_palette: Res<SelectedPalette>,
+	levels: Levels,
 	mut current_cost: ResMut<CurrentCost>,
 	mut ev_r_replay_level: EventReader<ReplayLevelEvent>,
 	mut ev_w_popup: EventWriter<PopupEvent>,
 	mut commands: Commands,
-	mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 	mut next_state: ResMut<NextState<PauseState>>,
 	mut selected_molecule_type: ResMut<SelectedMoleculeType>,
-	mut stopwatch_text_query: Query<(&mut Text, &mut StopwatchText)>,
+	mut stopwatch_text_query: Query<&mut Text, With<StopwatchText>>,
 	mut reactor_query: Query<(Entity, &mut ReactorCondition)>,
 	mut launch_tube_query: Query<(&mut Transform, &mut LaunchTube, Without<ReactorCamera>)>,
 	mut reactor_camera_query: Query<(&mut OrthographicProjection, &mut Transform, With<ReactorCamera>)>,
 ) {
-	for _ in ev_r_replay_level.iter() {
+	for ev in ev_r_replay_level.iter() {
 		next_state.set(PauseState::Paused);
 		ev_w_popup.send(PopupEvent{ 
 			origin: Vec2::new(0.0, 0.0), 
@@ -481,19 +612,18 @@ fn replay_level(
 			popup_type: PopupType::LevelIntro(level.0),
 		});
 		for i in 0..TOTAL_MOLECULE_TYPES {
-			if get_available_molecules(level.0)[i] {
+			if levels.get(level.0).available_molecules.contains(&i) {
 				selected_molecule_type.0 = i;
 				break;
 			}
 		}
 		current_cost.0 = 0;
 		let (mut ortho_proj, mut transform, _) = reactor_camera_query.single_mut();
-		ortho_proj.scale = get_initial_zoom(level.0);
+		ortho_proj.scale = levels.get(level.0).initial_zoom;
 		transform.translation.x = 0.0;
 		transform.translation.y = 0.0;
-		for (mut text, mut stopwatch) in stopwatch_text_query.iter_mut() {
+		for mut text in stopwatch_text_query.iter_mut() {
 			text.sections[0].value = "".to_string();
-			stopwatch.0.reset();
 		}
 		for (entity, _) in molecule_query.iter() {
 			commands.entity(entity).despawn_recursive();
@@ -503,106 +633,32 @@ fn replay_level(
 			condition.pressure = 0.0;
 			commands.entity(entity).remove::<SelectedReactor>();
 		}
-		let reactors = get_reactors(level.0);
-		let z = 910.0;
-		for reactor in reactors.iter() {
-			match reactor.reactor_type {
-				ReactorType::Rectangle{origin, dimensions} => {
-					for (index, location, velocity) in get_reactor_initialization(level.0, reactor.reactor_id) {
-						commands
-							.spawn((SpriteSheetBundle {
-								transform: Transform::from_xyz(
-									origin.x + location.x + rand::random::<f32>(),
-									origin.y + location.y + rand::random::<f32>(),
-									500.0,
-								),
-								texture_atlas: texture_atlases.add(TextureAtlas::from_grid(asset_server.load(get_molecule_path(index)), Vec2::new(32.0, 32.0), 4, 2, None, None)).clone(),
-								sprite: TextureAtlasSprite{
-									color: get_molecule_color(index, selected_palette.0),
-									index: 0,
-									custom_size: Some(Vec2::new(get_molecule_radius(index) * 2.0, get_molecule_radius(index) * 2.0)),
-									..Default::default()
-								},
-								..Default::default()
-							},
-							*reactor,
-							Molecule(get_molecule_lifetime(index)),
-							MoleculeInfo {
-								index: index,
-								reacted: false,
-								radius: get_molecule_radius(index),
-								mass: get_molecule_mass(index),
-							},
-							ParticleTrail{
-								spawn_timer: Timer::from_seconds(PARTICLE_SPAWN_DELAY, TimerMode::Repeating),
-								duration: PARTICLE_DURATION,
-							},
-							Velocity(velocity),
-							AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-							AnimationIndices{ 
-								first: 0, 
-								total: 8,
-							},
-							RenderLayers::layer(1),
-							DespawnOnExitGameState,
-							Name::new("Molecule")
-						));
-					}
-					for (mut transform, mut launch_tube, _) in launch_tube_query.iter_mut() {
-						if launch_tube.id == reactor.reactor_id {
-							*transform = Transform::from_translation(Vec3::new(origin.x, origin.y + dimensions.height / 2.0, z));
-							launch_tube.current_rotation = 0.0;
-						}
-					}
-				},
-				ReactorType::Circle{origin, radius} => {
-					for (index, location, velocity) in get_reactor_initialization(level.0, reactor.reactor_id) {
-						commands
-							.spawn((SpriteSheetBundle {
-								transform: Transform::from_xyz(
-									origin.x + location.x + rand::random::<f32>(),
-									origin.y + location.y + rand::random::<f32>(),
-									500.0,
-								),
-								texture_atlas: texture_atlases.add(TextureAtlas::from_grid(asset_server.load(get_molecule_path(index)), Vec2::new(32.0, 32.0), 4, 2, None, None)).clone(),
-								sprite: TextureAtlasSprite{
-									color: get_molecule_color(index, selected_palette.0),
-									index: 0,
-									custom_size: Some(Vec2::new(get_molecule_radius(index) * 2.0, get_molecule_radius(index) * 2.0)),
-									..Default::default()
-								},
-								..Default::default()
-							},
-							*reactor,
-							Molecule(get_molecule_lifetime(index)),
-							MoleculeInfo {
-								index: index,
-								reacted: false,
-								radius: get_molecule_radius(index),
-								mass: get_molecule_mass(index),
-							},
-							ParticleTrail{
-								spawn_timer: Timer::from_seconds(PARTICLE_SPAWN_DELAY, TimerMode::Repeating),
-								duration: PARTICLE_DURATION,
-							},
-							Velocity(velocity),
-							AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-							AnimationIndices{ 
-								first: 0, 
-								total: 8,
-							},
-							RenderLayers::layer(1),
-							DespawnOnExitGameState,
-							Name::new("Molecule")
-						));
-					}
-					for (mut transform, mut launch_tube, _) in launch_tube_query.iter_mut() {
-						if launch_tube.id == reactor.reactor_id {
-							*transform = Transform::from_translation(Vec3::new(origin.x, origin.y + radius, z));
-							launch_tube.current_rotation = 0.0;
-						}
-					}
-				},
+		// Playing back a replay restarts with the seed it was recorded
+		// with, a replay the game could not have made starts a new attempt
+		let replay = ev.0.as_ref().filter(|replay| match replay.validate(level.0, levels.get(level.0), &registry) {
+			Ok(()) => true,
+			Err(error) => {
+				error!("Unable to play back the replay: {}", error);
+				false
+			},
+		});
+		let seed = match replay {
+			Some(replay) => {
+				commands.insert_resource(ReplayPlayback::new(replay.clone()));
+				Some(replay.seed)
+			},
+			None => {
+				commands.remove_resource::<ReplayPlayback>();
+				None
+			},
+		};
+		mole_sim::reactor::start_attempt(&mut commands, level.0, levels.get(level.0), &registry, &mut sim_rng, seed);
+		for (i, reactor_definition) in levels.get(level.0).reactors.iter().enumerate() {
+			for (mut transform, mut launch_tube, _) in launch_tube_query.iter_mut() {
+				if launch_tube.id == i {
+					*transform = Transform::from_translation(reactor_definition.reactor_type.wall_point(Vec2::Y).extend(910.0));
+					launch_tube.current_rotation = 0.0;
+				}
 			}
 		}
 	}
@@ -1,8 +1,7 @@
 // Import Bevy game engine essentials
 use bevy::{prelude::*, window::WindowResized, render::camera::Viewport, input::mouse::{MouseMotion, MouseWheel}};
-use bevy_pkv::PkvStore;
 // Import components, resources, and events
-use crate::components::*;
+use crate::{components::*, controls::ActionInput, levels::Levels};
 
 // Plugin for handling camera movement and resizing
 // the viewport for the reactor camera
@@ -19,6 +18,9 @@ impl Plugin for CameraPlugin {
 			.add_systems( OnEnter(GameState::Reactor),(
 				reset_reactor_camera,
 			))
+			.add_systems( OnEnter(GameState::Editor),(
+				reset_reactor_camera,
+			))
 		;
 	}
 }
@@ -27,10 +29,11 @@ impl Plugin for CameraPlugin {
 // when re-entering the reactor
 fn reset_reactor_camera(
 	level: Res<SelectedLevel>,
+	levels: Levels,
 	mut reactor_camera_query: Query<(&mut OrthographicProjection, &mut Transform, With<ReactorCamera>)>,
 ) {
 	let (mut ortho_proj, mut transform, _) = reactor_camera_query.single_mut();
-	ortho_proj.scale = get_initial_zoom(level.0);
+	ortho_proj.scale = levels.get(level.0).initial_zoom;
 	transform.translation.x = 0.0;
 	transform.translation.y = 0.0;
 }
@@ -72,7 +75,9 @@ fn resize_reactor_camera_viewport(
 // the reactor viewport
 fn pan_zoom_reactor_camera(
 	ortho_size: Res<OrthoSize>,
-	mouse: Res<Input<MouseButton>>,
+	actions: ActionInput,
+	game_state: Res<State<GameState>>,
+	pause_state: Res<State<PauseState>>,
 	window_query: Query<&Window>,
 	tracked_query: Query<(Entity, &Transform, With<SelectedMolecule>)>,
 	mut reactor_camera_query: Query<(&mut Transform, &mut OrthographicProjection, With<ReactorCamera>, Without<SelectedMolecule>)>,
@@ -94,7 +99,7 @@ fn pan_zoom_reactor_camera(
 		);
 		if (p.x - REACTOR_VIEWPORT_CENTER.x).abs() <= REACTOR_VIEWPORT_WIDTH / 2.0 && 
 		(p.y - REACTOR_VIEWPORT_CENTER.y).abs() <= REACTOR_VIEWPORT_HEIGHT / 2.0 {
-			if mouse.pressed(MouseButton::Right) {
+			if actions.pressed(InputAction::Pan) {
 				for ev in ev_r_motion.iter() {
 					pan += ev.delta;
 				}
@@ -108,28 +113,39 @@ fn pan_zoom_reactor_camera(
 			}
 		}
 	}
+	// Zoom keys zoom toward the center of the view, and are left
+	// to the editor's own shortcuts while it is open, and to
+	// moving between buttons while paused
+	if *game_state.get() == GameState::Reactor && *pause_state.get() == PauseState::Unpaused {
+		if actions.just_pressed(InputAction::ZoomIn) {
+			scroll = 1.0;
+			offset = Vec2::ZERO;
+		} else if actions.just_pressed(InputAction::ZoomOut) {
+			scroll = -1.0;
+			offset = Vec2::ZERO;
+		}
+	}
 
 	let scale_limits = (MAX_ZOOM, MIN_ZOOM);
     for (mut transform, mut ortho_projection, _, _) in reactor_camera_query.iter_mut() {
 		if scroll.abs() > 0.0 {
 			ortho_projection.scale = ((ortho_projection.scale.ln() - scroll*ZOOM_SPEED).exp()).clamp(scale_limits.0, scale_limits.1);
 			if ortho_projection.scale != scale_limits.0 && ortho_projection.scale != scale_limits.1 {
-				transform.translation.x = transform.translation.x + offset.x * ortho_projection.scale * ZOOM_TRANSLATION_SPEED * scroll.signum();
-				transform.translation.y = transform.translation.y + offset.y * ortho_projection.scale * ZOOM_TRANSLATION_SPEED * scroll.signum();
+				transform.translation.x += offset.x * ortho_projection.scale * ZOOM_TRANSLATION_SPEED * scroll.signum();
+				transform.translation.y += offset.y * ortho_projection.scale * ZOOM_TRANSLATION_SPEED * scroll.signum();
 			}
 		}
 		if pan.length_squared() > 0.0 {
-			transform.translation.x = transform.translation.x - pan.x * ortho_projection.scale;
-			transform.translation.y = transform.translation.y + pan.y * ortho_projection.scale;
+			transform.translation.x -= pan.x * ortho_projection.scale;
+			transform.translation.y += pan.y * ortho_projection.scale;
 			for (tracked_entity, _, _) in tracked_query.iter() {
 				commands.entity(tracked_entity).remove::<SelectedMolecule>();
 			}
 		} else {
-			for (_, tracked_transform, _) in tracked_query.iter() {
+			if let Some((_, tracked_transform, _)) = tracked_query.iter().next() {
 				transform.translation.x = (transform.translation.x * 3.0 + tracked_transform.translation.x) / 4.0;
 				transform.translation.y = (transform.translation.y * 3.0 + tracked_transform.translation.y) / 4.0;
-				break;
-			};
+			}
 		}
 		transform.translation.x = transform.translation.x.clamp(
 			(-scale_limits.1 * ortho_size.width + ortho_projection.scale * ortho_size.width) / 2.0,
@@ -144,29 +160,18 @@ fn pan_zoom_reactor_camera(
 }
 
 fn toggle_fullscreen(
-	keyboard: Res<Input<KeyCode>>,
-	pkv: Res<PkvStore>,
+	settings: Res<Settings>,
 	mut window_query: Query<&mut Window>,
 ) {
-	/*if keyboard.just_pressed(KeyCode::F) {
+	if settings.fullscreen_enabled {
 		for mut window in window_query.iter_mut() {
-			match window.mode {
-				bevy::window::WindowMode::Windowed => window.mode = bevy::window::WindowMode::BorderlessFullscreen,
-				_ => window.mode = bevy::window::WindowMode::Windowed,
-			} 
-		}
-	}*/
-	if let Ok(save_data) = pkv.get::<SaveData>("save_data") {
-		if save_data.fullscreen_enabled {
-			for mut window in window_query.iter_mut() {
-        		if window.mode == bevy::window::WindowMode::Windowed {
-            		window.mode = bevy::window::WindowMode::BorderlessFullscreen;
-				}
-			}
-   		} else {
-			for mut window in window_query.iter_mut() {
-           		window.mode = bevy::window::WindowMode::Windowed;
+        	if window.mode == bevy::window::WindowMode::Windowed {
+            	window.mode = bevy::window::WindowMode::BorderlessFullscreen;
 			}
 		}
+   	} else {
+		for mut window in window_query.iter_mut() {
+           	window.mode = bevy::window::WindowMode::Windowed;
+		}
 	}
 }
\ No newline at end of file
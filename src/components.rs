@@ -1,42 +1,110 @@
 // Import Bevy game engine essentials
-use bevy::{prelude::*, time::Stopwatch};
+use bevy::prelude::*;
 // Import Kira audio for Bevy to handle loading sound files
 use bevy_kira_audio::AudioInstance;
 // Import serde for serializing and deserializing
 // data for save files
 use serde::{Serialize, Deserialize};
+// Import the headless simulation types, re-exported so every
+// module can reach them through this one
+pub use mole_sim::{components::*, locale::*};
 
 // CONTENTS
 // - Save Data
+// - Molecule Registry
 // - Constants
 // - States
 // - Enums
-// - Structs
-// - System Sets
 // - Components
 // - Resources
 // - Events
 // - Audio Helper Functions
-// - Molecule Helper Functions
 // - Reactor Helper Functions
 // - Text Styles
 // - Cutscene Helper Functions
 
 // SAVE DATA
-#[derive(Serialize, Deserialize)]
+// Progress for one profile. New fields go at the end and are
+// given a default, so older saves can still be read and then
+// migrated by the save module
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
 pub struct SaveData {
+	pub levels_unlocked: Vec<bool>,
+	pub best_times: Vec<f32>,
+	pub best_costs: Vec<usize>,
+	pub cutscenes_unlocked: Vec<bool>,
+	pub stars: Vec<usize>,
+	// Saves from before the version was stored read as version 0
+	#[serde(default)]
+	pub version: u32,
+}
+
+// Settings shared by every profile on this machine, read once
+// at startup and written back shortly after they change
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
 	pub sfx_volume: f64,
 	pub bgm_volume: f64,
 	pub selected_palette: usize,
 	pub fullscreen_enabled: bool,
 	pub particles_enabled: bool,
-	pub levels_unlocked: Vec<bool>,
-	pub best_times: Vec<f32>,
-	pub best_costs: Vec<usize>,
-	pub cutscenes_unlocked: Vec<bool>,
+	pub controls: Controls,
+	pub glyphs_enabled: bool,
+	pub ui_scale: f32,
+	pub text_scale: f32,
+	// Code of the language the game is shown in
+	pub language: String,
+}
+
+// The keys and mouse buttons bound to each action, with
+// up to two bindings per action
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Controls {
+	pub bindings: Vec<(InputAction, [Option<InputBinding>; 2])>,
+}
+
+// Everything needed to move a profile to another machine,
+// written to a single file from the settings popup
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SaveExport {
+	pub name: String,
+	pub settings: Settings,
+	pub save_data: SaveData,
+	pub replays: Vec<Replay>,
+}
+
+// The name of the profile in each slot, or None for an empty
+// slot, and the slot that was last played
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct Profiles {
+	pub names: Vec<Option<String>>,
+	pub selected: usize,
 }
 
 
+// MOLECULE REGISTRY
+// Palette colours need the renderer, so they are converted
+// by the game rather than by the simulation crate
+pub trait MoleculeColor {
+	fn color(&self, index: usize, palette: usize) -> Color;
+}
+
+// Names shown beside the palette preview in the settings
+pub const PALETTE_KEYS: [&str; NUMBER_OF_PALETTES] = [
+	"palette.standard",
+	"palette.rainbow",
+	"palette.violet",
+	"palette.greyscale",
+	"palette.deuteranopia",
+	"palette.protanopia",
+	"palette.tritanopia",
+];
+
+
 // CONSTANTS
 // Window Resolution
 pub const ASPECT_RATIO: f32 = 16.0 / 9.0;
@@ -50,6 +118,8 @@ pub const BOOT_DURATION: f32 = 10.0;
 pub const TEXT_BOX_WIDTH: f32 = 1200.0;
 pub const TEXT_BOX_HEIGHT: f32 = 300.0;
 pub const TEXT_BOX_MARGINS: f32 = 25.0;
+pub const TEXT_BOX_X: f32 = 150.0;
+pub const TEXT_BOX_Y: f32 = -250.0;
 
 pub const PORTRAIT_WIDTH: f32 = 300.0;
 pub const PORTRAIT_HEIGHT: f32 = 300.0;
@@ -97,6 +167,10 @@ pub const GOAL_BOX_WIDTH: f32 = 480.0;
 pub const GOAL_BOX_HEIGHT: f32 = 100.0;
 pub const GOAL_BOX_MARGINS: f32 = 8.0;
 
+// Fraction of a level's cost or time limit used up
+// before its HUD box starts to warn the player
+pub const LIMIT_WARNING_FRACTION: f32 = 0.75;
+
 pub const PARTICLE_SPAWN_DELAY: f32 = 0.01;
 pub const PARTICLE_DURATION: f32 = 0.6;
 
@@ -105,21 +179,15 @@ pub const LEVER_WIDTH: f32 = 160.0;
 pub const LEVER_HEIGHT: f32 = 40.0;
 
 pub const CONNECTION_OUT_WIDTH: f32 = 128.0;
-pub const CONNECTION_IN_WIDTH: f32 = 189.0;
 pub const CONNECTION_HEIGHT: f32 = 256.0;
 
-pub const LAUNCH_TUBE_WIDTH: f32 = 146.0;
 pub const LAUNCH_TUBE_HEIGHT: f32 = 256.0;
 pub const LAUNCH_TUBE_SPEED: f32 = 1.0;
 pub const LAUNCH_TUBE_ROTATIONAL_SPEED: f32 = 150.0;
 
 
 // General Parameters
-pub const TOTAL_MOLECULE_TYPES: usize = 18;
 pub const LAUNCH_COOLDOWN: f32 = 0.2;
-pub const MOLECULE_CAP: usize = 800;
-
-pub const FRICTION: f32 = 0.05;
 
 pub const POPUP_EXPAND_TIME: f32 = 0.5;
 pub const POPUP_WIDTH: f32 = 1440.0;
@@ -127,11 +195,45 @@ pub const POPUP_HEIGHT: f32 = 810.0;
 pub const LOGBOOK_MARGINS: f32 = 80.0;
 
 pub const FADE_TRANSITION_DURATION: f32 = 0.2;
-pub const WIN_COUNTDOWN_LENGTH: f32 = 3.0;
 
-pub const NUMBER_OF_LEVELS: usize = 31;
 pub const NUMBER_OF_CUTSCENES: usize = 33;
 
+// Save Data
+// Raised whenever a migration is added to the save module
+pub const SAVE_VERSION: u32 = 1;
+pub const NUMBER_OF_PROFILES: usize = 5;
+pub const MAX_PROFILE_NAME_LENGTH: usize = 16;
+#[cfg(not(target_arch = "wasm32"))]
+pub const SAVE_EXPORT_PATH: &str = "saves/save_export.ron";
+// The browser downloads exports under this name instead
+#[cfg(target_arch = "wasm32")]
+pub const SAVE_EXPORT_PATH: &str = "save_export.ron";
+pub const SETTINGS_SAVE_DELAY: f32 = 0.5;
+
+// Gamepad
+pub const GAMEPAD_DEADZONE: f32 = 0.2;
+// Seconds between focus moves while a stick is held over
+pub const GAMEPAD_REPEAT_DELAY: f32 = 0.25;
+// Fraction of a lever's travel per second at full trigger
+pub const GAMEPAD_LEVER_SPEED: f32 = 0.5;
+pub const FOCUS_OUTLINE_WIDTH: f32 = 6.0;
+
+// Interface Scale
+// The choices offered in the settings for both the UI scale,
+// which sizes the HUD and text boxes, and the text size
+pub const SCALE_STEPS: [f32; 4] = [0.8, 1.0, 1.2, 1.4];
+
+// Level Editor
+pub const EDITOR_GRID_SIZE: f32 = 50.0;
+pub const EDITOR_RESIZE_STEP: f32 = 50.0;
+pub const EDITOR_MIN_REACTOR_SIZE: f32 = 200.0;
+pub const EDITOR_PICK_DISTANCE: f32 = 200.0;
+pub const EDITOR_CONNECTION_RADIUS: f32 = 100.0;
+// Placed molecules are given a velocity of this many times
+// the distance the mouse is dragged
+pub const EDITOR_VELOCITY_SCALE: f32 = 2.0;
+pub const EDITOR_PANEL_WIDTH: f32 = 380.0;
+
 
 // STATES
 #[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
@@ -142,6 +244,7 @@ pub enum GameState {
 	Cutscene,
 	Lab,
 	Reactor,
+	Editor,
 }
 
 #[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
@@ -168,24 +271,23 @@ pub enum CutsceneState {
 	Ended,
 }
 
-#[derive(Eq, PartialEq, Clone, Copy, Debug)]
-pub enum WinCondition {
-	GreaterThan(usize, usize),
-	LessThan(usize, usize),
-}
-
 #[derive(Clone, Copy, Debug)]
 pub enum PopupType {
 	Settings,
 	Logbook,
 	LevelSelect,
 	LevelIntro(usize),
-	WinScreen(f32, f32, usize, usize),
+	// Previous best time, time, previous best cost, cost, stars
+	WinScreen(f32, f32, usize, usize, usize),
+	FailScreen(FailReason),
+	Profiles,
+	Controls,
 }
 
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
 pub enum MenuButton {
 	StartGame,
+	Profiles,
 	Settings,
 	ExitGame,
 }
@@ -195,7 +297,6 @@ pub enum CustomLabButton {
 	MonitorActivate,
 	LogbookOpen,
 	ExitLab,
-	Poster,
 }
 
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
@@ -205,10 +306,26 @@ pub enum PopupButton {
 	PaletteToggle,
 	ParticleTrails(bool),
 	Fullscreen(bool),
+	Glyphs(bool),
+	UiScale(usize),
+	TextScale(usize),
+	Language(usize),
 	LogbookPage(usize),
 	LevelSelect(usize),
+	ExportSave,
+	ImportSave,
+	OpenControls,
+	OpenSettings,
+	// Action and which of its two bindings to replace
+	Rebind(InputAction, usize),
+	ResetControls,
+	SelectProfile(usize),
+	RenameProfile(usize),
+	CopyProfile(usize),
+	DeleteProfile(usize),
 	ReturnToLab,
 	ReplayLevel,
+	WatchReplay,
 	CompleteLevel,
 	ExitPopup,
 }
@@ -226,49 +343,76 @@ pub enum CutsceneButton {
 	SkipCutscene,
 }
 
-#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
-pub enum Actor {
-	#[default]
-	Nobody,
-	You,
-	Guard,
-	Scientist,
+// Everything the player can do in the reactor or a cutscene,
+// which can be bound to any key or mouse button
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Copy, Debug)]
+pub enum InputAction {
+	MoveLeft,
+	MoveRight,
+	RotateCW,
+	RotateCCW,
+	Sprint,
+	LaunchOnce,
+	LaunchHold,
+	PlaceSpawner,
+	SelectReactor,
+	DragLever,
+	TrackMolecule,
+	Pan,
+	ZoomIn,
+	ZoomOut,
+	AdvanceCutscene,
+}
+
+// Moves or uses the focused button, from the gamepad
+// or keyboard rather than the mouse
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum FocusInput {
+	Up,
+	Down,
+	Left,
+	Right,
+	Next,
+	Previous,
+	Activate,
+	Back,
 }
 
-#[derive(Clone, Copy)]
-pub enum ReactorType {
-	Rectangle{
-		origin: Vec2,
-		dimensions: Dimensions,
-	},
-	Circle{
-		origin: Vec2,
-		radius: f32,
-	},
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Copy, Debug)]
+pub enum InputBinding {
+	Key(KeyCode),
+	Mouse(MouseButton),
 }
 
-pub enum ReactionInfo {
-	Reaction(Vec<usize>, Limits, Limits),
-	None,
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub enum EditorTool {
+	#[default]
+	Select,
+	Rectangle,
+	Circle,
+	Intake,
+	Outlet,
+	Molecule,
 }
 
-pub enum Lifetime {
-	Unstable(Timer, ReactionInfo),
-	Stable,
+// Reactors are referred to by their position in the level, and
+// connections and molecules by their position in their reactor
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum EditorSelection {
+	Reactor(usize),
+	Connection(usize, usize),
+	Molecule(usize, usize),
 }
 
-
-// STRUCTS
-#[derive(Clone, Copy)]
-pub struct Dimensions {
-	pub width: f32,
-	pub height: f32,
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub enum Actor {
+	#[default]
+	Nobody,
+	You,
+	Guard,
+	Scientist,
 }
 
-pub struct Limits(pub f32, pub f32);
-
-// SYSTEM SETS
-
 
 // COMPONENTS
 #[derive(Component)]
@@ -286,41 +430,11 @@ pub struct MoleculeButton(pub usize);
 
 #[derive(Component, PartialEq, Eq, Clone, Copy, Debug)]
 pub enum ButtonEffect {
-	MenuButton(MenuButton),
-	CustomLabButton(CustomLabButton),
-	PopupButton(PopupButton),
-	ReactorButton(ReactorButton),
-	CutsceneButton(CutsceneButton),
-}
-
-#[derive(Component)]
-pub struct ReactorConnections(pub Vec<(Vec2, Connection)>);
-
-#[derive(Component)]
-pub struct Connection {
-	pub reactor_id: usize, 
-	pub connection_id: usize,
-	pub intake: bool,
-	pub filter: [bool; TOTAL_MOLECULE_TYPES],
-}
-
-#[derive(Component, Clone, Copy)]
-pub struct ReactorInfo {
-	pub input_chamber: bool,
-	pub product_chamber: bool,
-	pub reactor_type: ReactorType,
-	pub reactor_id: usize,
-}
-
-#[derive(Component)]
-pub struct Molecule(pub Lifetime);
-
-#[derive(Component, Clone, Copy)]
-pub struct MoleculeInfo {
-	pub index: usize,
-	pub reacted: bool,
-	pub radius: f32,
-	pub mass: f32,
+	Menu(MenuButton),
+	CustomLab(CustomLabButton),
+	Popup(PopupButton),
+	Reactor(ReactorButton),
+	Cutscene(CutsceneButton),
 }
 
 #[derive(Component)]
@@ -334,9 +448,6 @@ pub struct Particle {
 	pub duration: Timer,
 }
 
-#[derive(Component, Clone, Copy)]
-pub struct Velocity(pub Vec2);
-
 #[derive(Component)]
 pub struct SelectedLever;
 
@@ -353,9 +464,6 @@ pub struct MainCamera;
 #[derive(Component)]
 pub struct ReactorCamera;
 
-#[derive(Component)]
-pub struct SelectedMolecule;
-
 #[derive(Component)]
 pub struct SelectedReactor;
 
@@ -365,18 +473,55 @@ pub struct SelectedParticle;
 #[derive(Component)]
 pub struct Highlight;
 
+// One of the four sides drawn around the focused button
+#[derive(Component)]
+pub struct FocusOutline(pub usize);
+
 #[derive(Component)]
 pub struct Tooltip;
 
 #[derive(Component)]
 pub struct WinCountdownText;
 
+#[derive(Component)]
+pub struct GoalText;
+
 #[derive(Component)]
 pub struct LogbookText(pub usize);
 
+#[derive(Component)]
+pub struct ProfileNameText;
+
+#[derive(Component)]
+pub struct SaveTransferText;
+
+// Text on the button for one of the bindings of an action
+#[derive(Component)]
+pub struct BindingText(pub InputAction, pub usize);
+
+// Text for one of the profile slots, or one of the
+// buttons beside it
+#[derive(Component)]
+pub struct ProfileSlotText(pub usize);
+
+#[derive(Component)]
+pub struct ProfileDeleteText(pub usize);
+
 #[derive(Component)]
 pub struct Palette(pub usize);
 
+#[derive(Component)]
+pub struct PaletteNameText;
+
+// Text looked up by its key, redrawn whenever the language changes
+#[derive(Component)]
+pub struct LocalizedText(pub &'static str);
+
+// The letter drawn over a molecule of this type, shown
+// while glyphs are turned on in the settings
+#[derive(Component)]
+pub struct MoleculeGlyph(pub usize);
+
 #[derive(Component)]
 pub struct LaunchTube{
 	pub id: usize,
@@ -390,11 +535,28 @@ pub struct ActorInfo {
 }
 
 #[derive(Component)]
-pub struct StopwatchText(pub Stopwatch);
+pub struct StopwatchText;
 
 #[derive(Component)]
 pub struct CostText;
 
+#[derive(Component)]
+pub struct CostBox;
+
+// A box sized by the UI scale, whose text child is
+// reflowed to fit whenever the scale changes
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub enum ScaledBox {
+	Goal,
+	Cost,
+	Stopwatch,
+	Tooltip,
+	TextBox,
+}
+
+#[derive(Component)]
+pub struct StopwatchBox;
+
 #[derive(Component)]
 pub struct TooltipText;
 
@@ -411,7 +573,7 @@ pub struct FadeScreen;
 pub struct BrightLab;
 
 #[derive(Component)]
-pub struct Logbook(pub usize);
+pub struct Logbook;
 
 #[derive(Component)]
 pub struct PopupInfo{
@@ -420,18 +582,6 @@ pub struct PopupInfo{
 	pub popup_type: PopupType,
 }
 
-#[derive(Component)]
-pub struct MoleculeSpawnerInfo{
-	pub spawner_index: usize,
-	pub spawner_timer: Timer,
-}
-
-#[derive(Component)]
-pub struct ReactorCondition{
-	pub temperature: f32,
-	pub pressure: f32,
-}
-
 #[derive(Component)]
 pub struct AnimationTimer(pub Timer);
 
@@ -441,6 +591,9 @@ pub struct AnimationIndices {
 	pub total: usize,
 }
 
+#[derive(Component)]
+pub struct EditorText;
+
 #[derive(Component)]
 pub struct DespawnOnExitGameState;
 
@@ -463,12 +616,6 @@ pub struct BgmHandle(pub Handle<AudioInstance>);
 #[derive(Resource)]
 pub struct SfxHandles(pub Vec<(Handle<AudioInstance>, f64)>);
 
-#[derive(Resource)]
-pub struct MoleculeCount {
-	pub total: usize,
-	pub cap: usize,
-}
-
 #[derive(Resource)]
 pub struct CurrentCost(pub usize);
 
@@ -478,27 +625,75 @@ pub struct BootTimer(pub Timer);
 #[derive(Resource)]
 pub struct LaunchTimer(pub Timer);
 
-#[derive(Resource)]
-pub struct WinCountdown(pub Timer);
-
 #[derive(Resource)]
 pub struct FadeTransitionTimer(pub Timer);
 
 #[derive(Resource)]
 pub struct TextSpeedTimer(pub Timer);
 
+#[derive(Resource)]
+pub struct SettingsSaveTimer(pub Timer);
+
 #[derive(Resource, Deref, DerefMut)]
 pub struct SelectedPalette(pub usize);
 
 #[derive(Resource, Deref, DerefMut)]
-pub struct FullscreenEnabled(pub usize);
+pub struct SelectedLevel(pub usize);
 
 #[derive(Resource, Deref, DerefMut)]
-pub struct SelectedLevel(pub usize);
+pub struct SelectedProfile(pub usize);
+
+// A profile being renamed, or waiting for its delete
+// button to be pressed a second time to confirm
+#[derive(Resource, Default, PartialEq, Clone, Copy)]
+pub enum PendingProfileAction {
+	#[default]
+	None,
+	Rename(usize),
+	Delete(usize),
+	// The profile's save could not be read, and will be
+	// started over if it is selected again
+	StartOver(usize),
+	// An exported save has been checked and will replace the
+	// selected profile if the import button is pressed again
+	Import,
+}
+
+// The button the gamepad or keyboard will press, cleared
+// once the mouse is moved
+#[derive(Resource, Default)]
+pub struct ButtonFocus(pub Option<ButtonEffect>);
+
+// The action and binding waiting for a key or mouse button
+// to be pressed on the controls popup
+#[derive(Resource, Default)]
+pub struct Rebinding(pub Option<(InputAction, usize)>);
+
+// Result of the last save export or import, shown in the settings
+#[derive(Resource, Default)]
+pub struct SaveTransferMessage(pub String);
+
+// An export which has been read and checked, kept until the import
+// is confirmed so the file or pasted text is only read once
+#[derive(Resource, Default)]
+pub struct PendingSaveImport(pub Option<SaveExport>);
 
 #[derive(Resource, Deref, DerefMut)]
 pub struct SelectedMoleculeType(pub usize);
 
+#[derive(Resource)]
+pub struct LevelHandles(pub Vec<Handle<LevelDefinition>>);
+
+#[derive(Resource)]
+pub struct MoleculeRegistryHandle(pub Handle<MoleculeRegistry>);
+
+#[derive(Resource)]
+pub struct ReactionTableHandle(pub Handle<ReactionTable>);
+
+// One for each language, in the same order as LANGUAGES
+#[derive(Resource)]
+pub struct StringTableHandles(pub Vec<Handle<StringTable>>);
+
 #[derive(Resource)]
 pub struct CutsceneTracker {
 	pub current_scene: usize,
@@ -509,6 +704,27 @@ pub struct CutsceneTracker {
 	pub cutscene_state: CutsceneState,
 }
 
+// Level open in the editor, edits are only written
+// to the level file when saved
+#[derive(Resource)]
+pub struct LevelEditor {
+	pub level_id: usize,
+	pub level: LevelDefinition,
+	pub tool: EditorTool,
+	pub selection: Option<EditorSelection>,
+	pub brush_molecule: usize,
+	pub connection_id: usize,
+	pub cursor: Option<Vec2>,
+	// Where the left mouse button was pressed, used for dragging
+	// and for giving placed molecules a velocity
+	pub drag_start: Option<Vec2>,
+	pub unsaved: bool,
+	// Set when Escape is pressed with unsaved changes, so
+	// a second press is needed to leave without saving
+	pub confirm_exit: bool,
+	pub message: String,
+}
+
 
 // EVENTS
 #[derive(Event)]
@@ -517,8 +733,10 @@ pub struct ButtonCall(pub ButtonEffect);
 #[derive(Event)]
 pub struct FadeTransitionEvent(pub GameState);
 
+// Restarts the current level, playing back
+// the given replay if there is one
 #[derive(Event)]
-pub struct ReplayLevelEvent;
+pub struct ReplayLevelEvent(pub Option<Replay>);
 
 #[derive(Event)]
 pub struct PopupEvent{
@@ -532,19 +750,7 @@ pub struct PopupEvent{
 pub struct PopupCompleteEvent;
 
 #[derive(Event)]
-pub struct ConnectionEvent{
-	pub connection_id: usize,
-	pub m_info: MoleculeInfo,
-	pub r_info: ReactorInfo,
-	pub velocity: Vec2,
-	pub selected: bool,
-}
-
-#[derive(Event)]
-pub struct SoundEffectEvent{
-	pub note: usize,
-	pub location: Vec2,
-}
+pub struct FocusEvent(pub FocusInput);
 
 
 // AUDIO HELPER FUNCTIONS
@@ -564,516 +770,7 @@ pub fn get_audio_path(
 }
 
 
-// MOLECULE HELPER FUNCTIONS
-pub fn get_available_molecules(
-	level: usize,
-) -> [bool; TOTAL_MOLECULE_TYPES] {
-	let mut available_molecules = [false; TOTAL_MOLECULE_TYPES];
-	match level {
-		0 => {
-			available_molecules[0] = true;
-			available_molecules
-		}
-		1 | 3 => {
-			available_molecules[0] = true;
-			available_molecules[1] = true;
-			available_molecules
-		}
-		2 => {
-			available_molecules[5] = true;
-			available_molecules
-		}
-		4 => {
-			available_molecules[2] = true;
-			available_molecules[3] = true;
-			available_molecules
-		}
-		5 => {
-			available_molecules[0] = true;
-			available_molecules[1] = true;
-			available_molecules[3] = true;
-			available_molecules[6] = true;
-			available_molecules
-		}
-		6 => {
-			available_molecules[6] = true;
-			available_molecules
-		}
-		_ => {
-			for i in 0..7 {
-				available_molecules[i] = true;
-			};
-			available_molecules
-		}
-	}
-}
-
-pub fn get_molecule_path(
-	index: usize,
-) -> String {
-	match index {
-		0 => "moles/smooth_triangle.png".to_string(),
-		1 => "moles/spikes_sparse.png".to_string(),
-		2 => "moles/spikes_dense.png".to_string(),
-		3 => "moles/cage_triangle.png".to_string(),
-		4 => "moles/smooth_triangle.png".to_string(),
-		5 => "moles/cage_square.png".to_string(),
-		6 => "moles/smooth_triangle.png".to_string(),
-		7 => "moles/spikes_sparse.png".to_string(),
-		8 => "moles/spikes_dense.png".to_string(),
-		9 => "moles/cage_triangle.png".to_string(),
-		10 => "moles/smooth_triangle.png".to_string(),
-		11 => "moles/cage_square.png".to_string(),
-		12 => "moles/spikes_dense.png".to_string(),
-		13 => "moles/cage_triangle.png".to_string(),
-		14 => "moles/smooth_triangle.png".to_string(),
-		15 => "moles/spikes_dense.png".to_string(),
-		16 => "moles/smooth_triangle.png".to_string(),
-		17 => "moles/spikes_dense.png".to_string(),
-		_ => "moles/smooth_triangle.png".to_string(),
-	}
-}
-
-pub fn get_molecule_color(
-	index: usize,
-	palette: usize,
-) -> Color {
-	match palette {
-		0 => match index {
-			0 => Color::RED,
-			1 => Color::BLUE,
-			2 => Color::GREEN,
-			3 => Color::WHITE,
-			4 => Color::DARK_GRAY,
-			5 => Color::ORANGE,
-			6 => Color::YELLOW,
-			7 => Color::RED,
-			8 => Color::BLUE,
-			9 => Color::GREEN,
-			10 => Color::WHITE,
-			11 => Color::DARK_GRAY,
-			12 => Color::ORANGE,
-			13 => Color::YELLOW,
-			14 => Color::RED,
-			15 => Color::BLUE,
-			16 => Color::GREEN,
-			17 => Color::WHITE,
-			_ => Color::RED,
-		}
-		1 => match index {
-			0 => Color::RED,
-			1 => Color::ORANGE,
-			2 => Color::YELLOW,
-			3 => Color::GREEN,
-			4 => Color::BLUE,
-			5 => Color::INDIGO,
-			6 => Color::VIOLET,
-			7 => Color::RED,
-			8 => Color::ORANGE,
-			9 => Color::YELLOW,
-			10 => Color::GREEN,
-			11 => Color::BLUE,
-			12 => Color::INDIGO,
-			13 => Color::VIOLET,
-			14 => Color::RED,
-			15 => Color::ORANGE,
-			16 => Color::YELLOW,
-			17 => Color::GREEN,
-			_ => Color::BLUE,
-		}
-		2 => match index {
-			_ => Color::VIOLET,
-		}
-		_ => match index {
-			0 => Color::rgb(0.8, 0.8, 0.8),
-			1 => Color::rgb(0.3, 0.3, 0.3),
-			2 => Color::rgb(1.0, 1.0, 1.0),
-			3 => Color::rgb(0.5, 0.5, 0.5),
-			4 => Color::rgb(0.0, 0.0, 0.0),
-			5 => Color::rgb(0.7, 0.7, 0.7),
-			6 => Color::rgb(0.2, 0.2, 0.2),
-			7 => Color::rgb(0.6, 0.6, 0.6),
-			8 => Color::rgb(0.1, 0.1, 0.1),
-			9 => Color::rgb(0.9, 0.9, 0.9),
-			10 => Color::rgb(0.4, 0.4, 0.4),
-			11 => Color::rgb(0.6, 0.6, 0.6),
-			12 => Color::rgb(0.8, 0.8, 0.8),
-			13 => Color::rgb(0.3, 0.3, 0.3),
-			14 => Color::rgb(1.0, 1.0, 1.0),
-			15 => Color::rgb(0.5, 0.5, 0.5),
-			16 => Color::rgb(0.0, 0.0, 0.0),
-			17 => Color::rgb(0.7, 0.7, 0.7),
-			_ => Color::rgb(0.2, 0.2, 0.2),
-		}
-	}
-}
-
-pub fn get_molecule_radius(
-	index: usize,
-) -> f32 {
-	match index {
-		0 => 48.0,
-		1 => 64.0,
-		2 => 80.0,
-		3 => 16.0,
-		4 => 32.0,
-		5 => 56.0,
-		6 => 72.0,
-		_ => 32.0,
-	}
-}
-
-pub fn get_molecule_mass(
-	index: usize,
-) -> f32 {
-	match index {
-		0 => 10.0,
-		1 => 20.0,
-		2 => 30.0,
-		3 => 40.0,
-		4 => 10000.0,
-		5 => 1000.0,
-		6 => 5.0,
-		_ => 100.0,
-	}
-}
-
-pub fn get_molecule_cost(
-	index: usize,
-) -> usize {
-	match index {
-		0 => 10,
-		1 => 20,
-		2 => 40,
-		3 => 50,
-		4 => 80,
-		5 => 2,
-		6 => 0,
-		_ => 1,
-	}
-}
-
-pub fn get_molecule_initial_velocity(
-	index: usize,
-) -> f32 {
-	match index {
-		0 => 1000.0,
-		1 => 2000.0,
-		2 => 1500.0,
-		3 => 2500.0,
-		4 => 50.0,
-		5 => 3000.0,
-		6 => 3000.0,
-		_ => 600.0,
-	}
-}
-
-pub fn get_molecule_lifetime(
-	index: usize,
-) -> Lifetime {
-	match index {
-		//2 => Lifetime::Unstable(Timer::from_seconds(rand::random::<f32>() * 5.0 + 5.0, TimerMode::Once), 
-		//ReactionInfo::None),
-		//4 => Lifetime::Unstable(Timer::from_seconds(rand::random::<f32>() * 3.0 + 0.2, TimerMode::Once), 
-		//ReactionInfo::Reaction(vec![], Limits(0.0, 0.1), Limits(0.0, 1.0))),
-		5 => Lifetime::Unstable(Timer::from_seconds(rand::random::<f32>() * 0.2 + 0.8, TimerMode::Once), 
-			ReactionInfo::Reaction(vec![], Limits(0.0, 1.0), Limits(0.0, 1.0))),
-		6 => Lifetime::Unstable(Timer::from_seconds(rand::random::<f32>() * 0.2 + 0.8, TimerMode::Once), 
-			ReactionInfo::Reaction(vec![], Limits(0.0, 1.0), Limits(0.0, 1.0))),
-		_ => Lifetime::Stable,
-	}
-}
-
-pub fn valid_molecule_combination(
-	mol_a: usize,
-	mol_b: usize,
-) -> ReactionInfo {
-	let (mol_a, mol_b) = (mol_a.min(mol_b), mol_a.max(mol_b));
-	match mol_a {
-		0 => match mol_b {
-			0 => ReactionInfo::Reaction(vec![1], Limits(0.9, 1.0), Limits(0.0, 1.0)),
-			1 => ReactionInfo::Reaction(vec![2], Limits(0.0, 0.9), Limits(0.0, 1.0)),
-			3 => ReactionInfo::Reaction(vec![5, 5, 5, 5, 5], Limits(0.0, 1.0), Limits(0.0, 1.0)),
-			5 => ReactionInfo::Reaction(vec![5], Limits(0.0, 1.0), Limits(0.0, 1.0)),
-			_ => ReactionInfo::None,
-		},
-		1 => match mol_b {
-			3 => ReactionInfo::Reaction(vec![5, 5, 5, 5, 5], Limits(0.0, 1.0), Limits(0.0, 1.0)),
-			5 => ReactionInfo::Reaction(vec![5], Limits(0.0, 1.0), Limits(0.0, 1.0)),
-			_ => ReactionInfo::None,
-		},
-		2 => match mol_b {
-			3 => ReactionInfo::Reaction(vec![4], Limits(0.0, 1.0), Limits(0.0, 1.0)),
-			5 => ReactionInfo::Reaction(vec![5], Limits(0.0, 1.0), Limits(0.0, 1.0)),
-			_ => ReactionInfo::None,
-		},
-		3 => match mol_b {
-			5 => ReactionInfo::Reaction(vec![5], Limits(0.0, 1.0), Limits(0.0, 1.0)),
-			_ => ReactionInfo::None,
-		},
-		4 => match mol_b {
-			5 => ReactionInfo::Reaction(vec![5], Limits(0.0, 1.0), Limits(0.0, 1.0)),
-			_ => ReactionInfo::None,
-		},
-		5 => match mol_b {
-			_ => ReactionInfo::None,
-		},
-		6 => match mol_b {
-			_ => ReactionInfo::None,
-		},
-		_ => ReactionInfo::None,
-	}
-}
-
-
 // REACTOR HELPER FUNCTIONS
-pub fn get_reactors(
-	level: usize,
-) -> Vec<ReactorInfo> {
-	let mut reactors = Vec::new();
-	match level {
-		0 => {
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Rectangle{origin: Vec2::new(0.0, 0.0), dimensions: Dimensions{width: 3000.0, height: 2000.0}}, reactor_id: 0, input_chamber: true, product_chamber: true});
-		}
-		1 => {
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Circle{origin: Vec2::new(0.0, 0.0), radius: 2000.0}, reactor_id: 0, input_chamber: true, product_chamber: true});
-		}
-		2 => {
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Rectangle{origin: Vec2::new(0.0, 0.0), dimensions: Dimensions {width: 2000.0, height: 1600.0}}, reactor_id: 0, input_chamber: true, product_chamber: true});
-		}
-		3 => {
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Rectangle{origin: Vec2::new(0.0, 1000.0), dimensions: Dimensions{width: 4000.0, height: 2000.0}}, reactor_id: 0, input_chamber: true, product_chamber: false});
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Circle{origin: Vec2::new(0.0, -1000.0), radius: 800.0}, reactor_id: 1, input_chamber: false, product_chamber: true});
-		}
-		4 => {
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Circle{origin: Vec2::new(-4500.0, 0.0), radius: 2000.0}, reactor_id: 0, input_chamber: true, product_chamber: false});
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Rectangle{origin: Vec2::new(0.0, 0.0), dimensions: Dimensions{width: 4000.0, height: 600.0}}, reactor_id: 1, input_chamber: false, product_chamber: true});
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Circle{origin: Vec2::new(4500.0, 0.0), radius: 2000.0}, reactor_id: 2, input_chamber: true, product_chamber: false});
-		}
-		5 => {
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Circle{origin: Vec2::new(0.0, 0.0), radius: 1500.0}, reactor_id: 0, input_chamber: true, product_chamber: true});
-		}
-		6 => {
-			{reactors.push(ReactorInfo{reactor_type: ReactorType::Circle{origin: Vec2::new(0.0, 0.0), radius: 1500.0}, reactor_id: 0, input_chamber: true, product_chamber: true});}
-		}
-		_ => {
-			{reactors.push(ReactorInfo{reactor_type: ReactorType::Circle{origin: Vec2::new(0.0, 0.0), radius: 4000.0}, reactor_id: 0, input_chamber: true, product_chamber: true});}
-		}
-		_ => {
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Rectangle{origin: Vec2::new(-3000.0, 100.0), dimensions: Dimensions{width: 3000.0, height: 3000.0}}, reactor_id: 0, input_chamber: true, product_chamber: false});
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Rectangle{origin: Vec2::new(3000.0, -2500.0), dimensions: Dimensions{width: 3000.0, height: 2000.0}}, reactor_id: 1, input_chamber: false, product_chamber: true});
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Circle{origin: Vec2::new(4000.0, 2000.0), radius: 2200.0}, reactor_id: 2, input_chamber: true, product_chamber: false});
-		},
-		_ => {reactors.push(ReactorInfo{reactor_type: ReactorType::Circle{origin: Vec2::new(0.0, 0.0), radius: 4500.0}, reactor_id: 0, input_chamber: true, product_chamber: true});}
-		_ => {
-			for j in 0..4 {
-				for i in 0..8 {
-					reactors.push(ReactorInfo{reactor_type: ReactorType::Circle{origin: Vec2::new(-6844.0 + 1955.5*i as f32, 3180.0 - 2120.0*j as f32), radius: 800.0}, reactor_id: i + 8*j, 
-					input_chamber: if i == 0 && j == 0 {true} else {false}, product_chamber: if i == 7 && j == 3 {true} else {false}});
-				}
-			}
-		}
-		_ => {
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Circle{origin: Vec2::new(-2000.0, 0.0), radius: 800.0}, reactor_id: 0, input_chamber: true, product_chamber: false});
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Circle{origin: Vec2::new(0.0, 0.0), radius: 800.0}, reactor_id: 1, input_chamber: true, product_chamber: false});
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Circle{origin: Vec2::new(2000.0, 0.0), radius: 800.0}, reactor_id: 2, input_chamber: true, product_chamber: true});
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Rectangle{origin: Vec2::new(-2000.0, -3000.0), dimensions: Dimensions{width: 800.0, height: 800.0}}, reactor_id: 3, input_chamber: true, product_chamber: false});
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Rectangle{origin: Vec2::new(0.0, -3000.0), dimensions: Dimensions{width: 800.0, height: 2000.0}}, reactor_id: 4, input_chamber: true, product_chamber: false});
-			reactors.push(ReactorInfo{reactor_type: ReactorType::Rectangle{origin: Vec2::new(2000.0, -3000.0), dimensions: Dimensions{width: 800.0, height: 3000.0}}, reactor_id: 5, input_chamber: true, product_chamber: false});
-		}
-		_ => (),
-	}
-	reactors
-}
-
-pub fn get_reactor_connections(
-	level: usize,
-	reactor_id: usize,
-) -> ReactorConnections {
-	let mut filter = [false; TOTAL_MOLECULE_TYPES];
-	let (mut filter_a, mut filter_b, mut filter_c, mut filter_d) = (filter, filter, filter, filter); 
-	filter_a[0] = true;
-	filter_b[1] = true;
-	filter_c[2] = true;
-	filter_d[3] = true;
-	let mut connections = Vec::new();
-	match level {
-		3 => match reactor_id {
-			0 => {
-				connections.push((Vec2::new(-0.8, -1.0), Connection{reactor_id: reactor_id, connection_id: 0, intake: true, filter: filter_a}));
-				connections.push((Vec2::new(0.8, -1.0), Connection{reactor_id: reactor_id, connection_id: 1, intake: true, filter: filter_b}));
-			},
-			1 => {
-				connections.push((Vec2::new(0.0, 1.0), Connection{reactor_id: reactor_id, connection_id: 0, intake: false, filter: filter}));
-				connections.push((Vec2::new(0.0, 1.0), Connection{reactor_id: reactor_id, connection_id: 1, intake: false, filter: filter}));
-			}
-			_ => (),
-		}
-		4 => match reactor_id {
-			0 => {
-				connections.push((Vec2::new(1.0, 0.0), Connection{reactor_id: reactor_id, connection_id: 2, intake: true, filter: filter_c}));
-			},
-			1 => {
-				connections.push((Vec2::new(-1.0, 0.0), Connection{reactor_id: reactor_id, connection_id: 2, intake: false, filter: filter}));
-				connections.push((Vec2::new(1.0, 0.0), Connection{reactor_id: reactor_id, connection_id: 3, intake: false, filter: filter}));
-			},
-			2 => {
-				connections.push((Vec2::new(-1.0, 0.0), Connection{reactor_id: reactor_id, connection_id: 3, intake: true, filter: filter_d}));
-			},
-			_ => (),
-		}
-		_ => (),
-		_ => match reactor_id {
-			_ => {
-				connections.push((Vec2::new(0.0, -1.0), Connection{reactor_id: reactor_id, connection_id: 0, intake: true, filter: filter}));
-				connections.push((Vec2::new(1.0, 1.0), Connection{reactor_id: reactor_id, connection_id: 0, intake: false, filter: filter}));
-				connections.push((Vec2::new(-1.0, -1.0), Connection{reactor_id: reactor_id, connection_id: 1, intake: true, filter: filter}));
-				connections.push((Vec2::new(-1.0, 1.0), Connection{reactor_id: reactor_id, connection_id: 1, intake: false, filter: filter}));
-				connections.push((Vec2::new(1.0, -1.0), Connection{reactor_id: reactor_id, connection_id: 2, intake: true, filter: filter}));
-				connections.push((Vec2::new(-1.0, 0.0), Connection{reactor_id: reactor_id, connection_id: 2, intake: false, filter: filter}));
-				connections.push((Vec2::new(1.0, 0.0), Connection{reactor_id: reactor_id, connection_id: 3, intake: true, filter: filter}));
-				connections.push((Vec2::new(0.0, 1.0), Connection{reactor_id: reactor_id, connection_id: 3, intake: false, filter: filter}));
-			}
-		}
-		_ => (),
-		_ => match reactor_id {
-			0 => {
-				connections.push((Vec2::new(-1.0, 0.0), Connection{reactor_id: reactor_id, connection_id: 1, intake: true, filter: filter}));
-				connections.push((Vec2::new(1.0, 0.0), Connection{reactor_id: reactor_id, connection_id: 1, intake: false, filter: filter}));
-				connections.push((Vec2::new(0.0, -1.0), Connection{reactor_id: reactor_id, connection_id: 0, intake: false, filter: filter}));
-			},
-			1 => {
-				connections.push((Vec2::new(0.0, 1.0), Connection{reactor_id: reactor_id, connection_id: 1, intake: false, filter: filter}));
-			}
-			2 => {
-				filter[0] = false;
-				connections.push((Vec2::new(1.0, 1.0), Connection{reactor_id: reactor_id, connection_id: 0, intake: true, filter: filter}));
-			}
-			_ => (),
-		}
-		_ => match reactor_id {
-			i => {
-				connections.push((Vec2::new(0.0, -1.0), Connection{reactor_id: reactor_id, connection_id: (i+1)%32, intake: true, filter: filter}));
-				connections.push((Vec2::new(0.0, 1.0), Connection{reactor_id: reactor_id, connection_id: i, intake: false, filter: filter}));
-			}
-		}
-	}
-	ReactorConnections(connections)
-}
-
-pub fn get_reactor_initialization(
-	level: usize,
-	reactor_id: usize,
-) -> Vec<(usize, Vec2, Vec2)> {
-	let mut molecules = Vec::new();
-	match level {
-		0 => match reactor_id {
-			0 => {
-				for i in 0..5 {
-					molecules.push((1, Vec2::new(-1000.0 + 500.0 * i as f32, 0.0), Vec2::ZERO));
-				}
-				molecules
-			},
-			_ => molecules,
-		}
-		1 => match reactor_id {
-			0 => {
-				for j in 0..9 {
-					for i in 0..9 {
-						molecules.push((4, Vec2::new(-600.0 + 150.0 * i as f32, -600.0 + 150.0 * j as f32), Vec2::ZERO));
-					}
-				}
-				molecules
-			},
-			_ => molecules,
-		}
-		2 => match reactor_id {
-			0 => {
-				for i in 0..18 {
-					molecules.push((0, Vec2::new(-800.0 + 100.0 * i as f32, 0.0), 
-					Vec2::new(0.0, if i % 2 == 0 {300.0} else {-300.0})));
-				}
-				molecules
-			},
-			_ => molecules,
-		}
-		6 => match reactor_id {
-			0 => {
-				for j in 0..5 {
-					for i in 0..5 {
-						molecules.push((0, Vec2::new(-400.0 + 200.0 * i as f32, -400.0 + 200.0 * j as f32), Vec2::ZERO));
-					}
-				}
-				molecules
-			},
-			_ => molecules,
-		}
-		_ => {
-			molecules
-		}
-	}
-}
-
-pub fn get_level_goal(
-	level: usize,
-) -> WinCondition {
-	match level {
-		0 => WinCondition::GreaterThan(5, 2),
-		1 => WinCondition::GreaterThan(5, 2),
-		2 => WinCondition::LessThan(1, 0),
-		3 => WinCondition::GreaterThan(5, 2),
-		4 => WinCondition::GreaterThan(5, 4),
-		5 => WinCondition::GreaterThan(15, 4),
-		6 => WinCondition::GreaterThan(5, 1),
-		_ => WinCondition::GreaterThan(1, 10),
-		_ => WinCondition::GreaterThan(1, 0),
-	}
-}
-
-pub fn get_level_goal_text(
-	level: usize,
-) -> String {
-	match level {
-		0 => format!("Have at least 5 Comba molecules in the output chamber"),
-		1 => format!("Have at least 5 Comba molecules in the output chamber"),
-		2 => format!("Remove all Funda molecules from the output chamber"),
-		3 => format!("Have at least 5 Comba molecules in the output chamber"),
-		4 => format!("Have at least 5 Densa molecules in the output chamber"),
-		5 => format!("Have at least 15 Densa molecules in the output chamber"),
-		6 => format!("Have at least 5 Supla molecules in the output chamber"),
-		_ => format!("Have fun!"),
-	}
-}
-
-pub fn get_tooltip_text(
-	molecule_index: usize,
-	molecule_unlocked: bool,
-) -> String {
-	if molecule_unlocked {
-		match molecule_index {
-			0 => format!("Funda is the simplest molecule, and the cheapest to produce. Reacts with Supla to produce Comba."),
-			1 => format!("Supla is a common reagent used in many reactions. Can be combined with Funda to produce Comba."),
-			2 => format!("Comba was the first compound discovered by ranchers, and is the first step in a long journey."),
-			3 => format!("Volla reacts strongly with Funda and Supla to produce destructive Morta, however, it can be stabilized by reacting with Comba."),
-			4 => format!("Densa is extremely heavy and is not easily moved by other molecules. It is also very stable, but will still be destroyed by Morta."),
-			5 => format!("Morta is a fast and dangerous molecule which eradicates most other molecules. Thankfully it decays quickly, and can be useful for clearing out a reactor."),
-			6 => format!("Inera is a short-lived molecule, and is not known to react with any other molecules. Skilled ranchers use these to push other molecules around."),
-			_ => format!("This molecule is unknown!"),
-		}
-	} else {
-		match molecule_index {
-			0 => format!("Funda, the simplest molecule. Unavailable for this level."),
-			1 => format!("Supla, the most common reagent. Unavailable for this level."),
-			2 => format!("Comba, the original compound. Unavailable for this level."),
-			3 => format!("Volla, the unstable molecule. Unavailable for this level."),
-			4 => format!("Densa, the compact compound. Unavailable for this level."),
-			5 => format!("Morta, the destructive molecule. Unavailable for this level."),
-			6 => format!("Inera, the harmless molecule. Unavailable for this level."),
-			_ => format!("This molecule is unknown!"),
-		}
-	}
-}
-
 pub fn get_reactor_color(
 	reactor_type: usize,
 ) -> Color {
@@ -1089,69 +786,35 @@ pub fn get_reactor_color(
 	}
 }
 
-// For rectangles, limits between 0.0 and 1.0 represent centre to edge
-// For circles, limits between 0.0 and 1.0 represent top, going anticlockwise, back to the top
-pub fn get_launch_tube_limits(
-	level: usize,
-	reactor_id: usize,
-) -> Limits {
-	match level {
-		4 => match reactor_id {
-			0 => Limits(0.625, 0.875),
-			2 => Limits(0.125, 0.375),
-			_ => Limits(1.0, 1.0),
-		}
-		_ => match reactor_id {
-			_ => Limits(1.0, 1.0),
-		},
-	}
-}
-
-pub fn get_initial_zoom(
-	level: usize,
-) -> f32 {
-	match level {
-		0 => 3.0,
-		1 => 5.0,
-		2 => 2.2,
-		3 => 9.0,
-		4 => 9.0,
-		5 => 4.0,
-		6 => 8.0,
-		_ => 10.0,
-	}
-}
-
-pub fn get_intro_text(
-	level: usize,
-) -> String {
-	match level {
-		0 => format!("Welcome to the reactor view! Select a chamber with Left Click and press Spacebar to launch molecules. You can move the launcher left and right with A and D. Try hit the molecules in the center to cause a reaction!"),
-		1 => format!("You are getting the hang of this! Use Left Click to select different molecules from the menu on the left. You can hover over them for more details! Also, you can hold W to continuously fire molecules. Remember to select a chamber with Left Click!"),
-		2 => format!("This chamber is filled with unwanted molecules! Use that new molecule in the menu to the left to clear them out. You can rotate the launcher using Q and E, and you move faster while holding down Shift."),
-		3 => format!("This level has two chambers. You can only launch molecules in the top chamber, and the pipes connecting the chambers only accept specific molecules. You can use the Mouse Wheel to zoom, and hold Right Click to pan around for a better view."),
-		4 => format!("TGIF! There are three chambers this time, but it should be no problem for you! Make sure you select each chamber with Left Click to control the launcher within it. Be aware that your movement is restricted due to the connections on the side of the chamber!"),
-		5 => format!("You thought Saturdays would be a holiday? No way! This will be your hardest challenge yet! Nothing new but this reaction requires two steps, though watch out for any unwanted reactions!"),
-		6 => format!("Hello, Arnie here, well done on passing your review! I have made you some popcorn to celebrate! Totally unrelated, the temperature lever has been fixed at the side of the reactor. Just select a reactor with Left Click, then click and drag the lever to change the temperature! If you find any kernels, do not tell Isa! Ha ha! Maybe Fundas will react differently at high temperatures like the popcorn!"),
-		_ => format!("I hope you are enjoying Mole Rancher Remastered! If you made it this far, leave me a comment letting me know what you think! Any feedback is appreciated! More levels will be added in future updates! This is currently a sandbox level. Use Middle Mouse Button on a mole to track it!"),
+// Colour of the cost and stopwatch boxes for the fraction
+// of the level's limit used up so far
+pub fn get_limit_color(
+	fraction: f32,
+) -> Color {
+	if fraction >= 1.0 {
+		Color::hex("E57373").unwrap()
+	} else if fraction >= LIMIT_WARNING_FRACTION {
+		Color::hex("F2C46D").unwrap()
+	} else {
+		Color::hex("F2F2F2").unwrap()
 	}
 }
 
-pub fn get_logbook_text(
+pub fn get_logbook_key(
 	page: usize,
 	side: usize,
-) -> String {
+) -> &'static str {
 	match side {
 		0 => match page {
-			0 => format!("Welcome to the logbook! Click the tabs to view other pages!"),
-			1 => format!("The Mole Ranch was first founded after the Molecular Shortages of 67, and is still in operation to this day."),
-			2 => format!("According to many ranchers, the spikier a molecule is the tastier it is."),
-			_ => format!("Maybe I will fill these pages myself once I have uncovered the secrets of the mole!"),
+			0 => "logbook.page_0.left",
+			1 => "logbook.page_1.left",
+			2 => "logbook.page_2.left",
+			_ => "logbook.blank.left",
 		}
 		_ => match page {
-			0 => format!("This feature is currently incomplete, but more information will be added in future updates!"),
-			2 => format!("However, they also require the most careful cooking techniques. A strong particle trail is indicative of a potent scent when cooking."),
-			_ => format!("Noone ever writes on the right page of notebooks... The ink would leak through!"),
+			0 => "logbook.page_0.right",
+			2 => "logbook.page_2.right",
+			_ => "logbook.blank.right",
 		}
 	}
 }
@@ -1159,348 +822,254 @@ pub fn get_logbook_text(
 
 // TEXT STYLES
 pub fn get_splash_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/Ronda.ttf"),
-		font_size: 30.0,
+		font_size: 30.0 * settings.text_scale,
 		color: Color::hex("EDD6AD").unwrap(),
 	}
 }
 
 pub fn get_title_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/Ronda.ttf"),
-		font_size: 50.0,
+		font_size: 50.0 * settings.text_scale,
 		color: Color::hex("EDD6AD").unwrap(),
 	}
 }
 
 pub fn get_settings_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/Ronda.ttf"),
-		font_size: 30.0,
+		font_size: 30.0 * settings.text_scale,
 		color: Color::hex("EDD6AD").unwrap(),
-		..Default::default()
 	}
 }
 
+// Glyphs are dark over light molecules and light over dark
+// ones, so they can be read whatever the palette
+pub fn get_glyph_text_style(
+	asset_server: &Res<AssetServer>,
+	font_size: f32,
+	background: Color,
+) -> TextStyle {
+	TextStyle {
+		font: asset_server.load("fonts/Ronda.ttf"),
+		font_size,
+		color: get_glyph_color(background),
+	}
+}
+
+pub fn get_glyph_color(
+	background: Color,
+) -> Color {
+	let luminance = 0.299 * background.r() + 0.587 * background.g() + 0.114 * background.b();
+	if luminance > 0.5 {Color::hex("2B2B29").unwrap()} else {Color::hex("EDD6AD").unwrap()}
+}
+
 pub fn get_intro_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/Ronda.ttf"),
-		font_size: 40.0,
+		font_size: 40.0 * settings.text_scale,
 		color: Color::hex("EDD6AD").unwrap(),
-		..Default::default()
 	}
 }
 
 pub fn get_logbook_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/RonysiswadiArchitect5.ttf"),
-		font_size: 40.0,
+		font_size: 40.0 * settings.text_scale,
 		color: Color::hex("2B2B29").unwrap(),
-		..Default::default()
 	}
 }
 
 pub fn get_win_countdown_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/Ronda.ttf"),
-		font_size: 50.0,
+		font_size: 50.0 * settings.text_scale,
 		color: *Color::hex("2B2B29").unwrap().set_a(0.95),
 	}
 }
 
 pub fn get_win_title_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/RonysiswadiArchitect5.ttf"),
-		font_size: 65.0,
+		font_size: 65.0 * settings.text_scale,
 		color: Color::hex("2B2B29").unwrap(),
 	}
 }
 
 pub fn get_win_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/RonysiswadiArchitect5.ttf"),
-		font_size: 50.0,
+		font_size: 50.0 * settings.text_scale,
 		color: Color::hex("2B2B29").unwrap(),
 	}
 }
 
 pub fn get_win_values_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/RonysiswadiArchitect5.ttf"),
-		font_size: 50.0,
+		font_size: 50.0 * settings.text_scale,
 		color: Color::hex("2B2B29").unwrap(),
 	}
 }
 
 pub fn get_tooltip_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/RonysiswadiArchitect5.ttf"),
-		font_size: 40.0,
+		font_size: 40.0 * settings.text_scale,
 		color: Color::rgba(0.1, 0.1, 0.1, 1.0),
-		..Default::default()
 	}
 }
 
 pub fn get_cutscene_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/PixelSplitter-Bold.ttf"),
-		font_size: 32.0,
+		font_size: 32.0 * settings.text_scale,
 		color: Color::rgba(0.1, 0.1, 0.1, 1.0),
-		..Default::default()
 	}
 }
 
 pub fn get_stopwatch_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/PixelSplitter-Bold.ttf"),
-		font_size: 48.0,
+		font_size: 48.0 * settings.text_scale,
 		color: Color::rgba(0.1, 0.3, 0.1, 1.0),
-		..Default::default()
 	}
 }
 
 pub fn get_cost_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/PixelSplitter-Bold.ttf"),
-		font_size: 48.0,
+		font_size: 48.0 * settings.text_scale,
 		color: Color::rgba(0.1, 0.3, 0.1, 1.0),
-		..Default::default()
 	}
 }
 
 pub fn get_goal_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/PixelSplitter-Bold.ttf"),
-		font_size: 28.0,
+		font_size: 28.0 * settings.text_scale,
 		color: Color::rgba(0.1, 0.3, 0.1, 1.0),
-		..Default::default()
+	}
+}
+
+// Sub-goals turn green while they are met
+pub fn get_goal_progress_text_style(
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
+	met: bool,
+) -> TextStyle {
+	TextStyle {
+		font: asset_server.load("fonts/PixelSplitter-Bold.ttf"),
+		font_size: 20.0 * settings.text_scale,
+		color: if met {Color::rgba(0.1, 0.6, 0.1, 1.0)} else {Color::rgba(0.4, 0.2, 0.1, 1.0)},
 	}
 }
 
 pub fn get_button_text_style(
-	asset_server: &Res<AssetServer>
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
 ) -> TextStyle {
 	TextStyle {
 		font: asset_server.load("fonts/Ronda.ttf"),
-		font_size: 25.0,
+		font_size: 25.0 * settings.text_scale,
 		color: Color::hex("2B2B29").unwrap(),
-		..Default::default()
+	}
+}
+
+pub fn get_editor_text_style(
+	asset_server: &Res<AssetServer>,
+	settings: &Settings,
+) -> TextStyle {
+	TextStyle {
+		font: asset_server.load("fonts/Ronda.ttf"),
+		font_size: 22.0 * settings.text_scale,
+		color: Color::hex("EDD6AD").unwrap(),
 	}
 }
 
 // CUTSCENE HELPER FUNCTIONS
+// The text of a line is looked up by its scene and line, with
+// lines past the end of a scene repeating its last line
 pub fn next_line(
 	current_scene: usize,
 	current_line: usize,
+	locale: &Locale,
 ) -> (String, ActorInfo) {
-	match current_scene {
+	let actor = match current_scene {
 		0 => match current_line {
-			0 =>
-			("August 1st - The Mole Ranch".to_string(),
-			ActorInfo{actor: Actor::Nobody}),
-			1 =>
-			("So you are the intern I have heard so much about?".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			2 =>
-			("Welcome! I will show you to your workstation.".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			3 =>
-			("There is much to learn but I am sure you will pick it up quickly!".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			4 =>
-			("I will pass you over to Isa, she will be able to explain better than I. But I am sure we will catch up again at some point!".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			5 =>
-			("Ah, you have arrived. You will find a logbook on your desk to fill with your findings, and the computer is already logged in.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			6 =>
-			("Progress through the training exercises I have laid out for you, they should only take a week to complete. I will check in occasionally to see how you are progressing.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			7 =>
-			("Make sure you read the notes I have left for you, they will be essential when performing reactions.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			_ =>
-			("I look forward to seeing you for your review on Sunday.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
+			0 => Actor::Nobody,
+			1..=4 => Actor::Guard,
+			_ => Actor::Scientist,
 		},
-		1 => match current_line {
-			0 =>
-			("August 2nd - The Mole Ranch".to_string(),
-			ActorInfo{actor: Actor::Nobody}),
-			1 =>
-			("Ah, so we did not scare you off! Good.".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			2 =>
-			("Isa is out today, I believe she is ranching a new mole type for you to use tomorrow!".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			3 =>
-			("She likes efficient workers, so try to use as few moles as possible to keep reaction costs down! Though getting your work done quickly can be its own reward too.".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			4 =>
-			("Do not worry about those moles in the center of the reactor today, I have never seen them do much of anything, and they are not easily budged.".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			_ =>
-			("Your desk should be the way you left it, good luck!".to_string(),
-			ActorInfo{actor: Actor::Guard}),
+		1 | 4 => match current_line {
+			0 => Actor::Nobody,
+			_ => Actor::Guard,
 		},
-		2 => match current_line {
-			0 =>
-			("August 3rd - The Mole Ranch".to_string(),
-			ActorInfo{actor: Actor::Nobody}),
-			1 =>
-			("Welcome back.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			2 =>
-			("The mole you will be working with today is very dangerous, and is capable of destroying most other moles.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			3 =>
-			("As such you will be using it to clean out an old reactor. With correct launcher positioning it should be pretty simple.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			_ =>
-			("Keep up the good work.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-		},
-		3 => match current_line {
-			0 =>
-			("August 4th - The Mole Ranch".to_string(),
-			ActorInfo{actor: Actor::Nobody}),
-			1 =>
-			("Ah, intern. You are picking things up quickly.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			2 =>
-			("You will soon be moving onto reactors with multiple chambers, so do not be afraid to revisit previous problems to get a strong grasp on the basics.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			3 =>
-			("The reactions you learn here will be vital to more complex problems you will face in the future.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			_ =>
-			("Keep this up and you will be a rancher in no time.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-		},
-		4 => match current_line {
-			0 =>
-			("August 5th - The Mole Ranch".to_string(),
-			ActorInfo{actor: Actor::Nobody}),
-			1 =>
-			("Friday already! This week has flown past. Almost as fast as you have flown through your training!".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			2 =>
-			("Even Isa seems to be impressed with the rate you are progressing!".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			_ =>
-			("Make sure you remember me once you are up in the big leagues, eh?".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-		},
-		5 => match current_line {
-			0 =>
-			("August 6th - The Mole Ranch".to_string(),
-			ActorInfo{actor: Actor::Nobody}),
-			1 =>
-			("Today will be tricky, make sure you are careful about which reactions you trigger.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			2 =>
-			("Do not get frustrated if you feel like you have lost progress, as even mistakes can be valuable experiences!".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			_ =>
-			("I will see you tomorrow for your review.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
+		2 | 3 | 5 => match current_line {
+			0 => Actor::Nobody,
+			_ => Actor::Scientist,
 		},
 		6 => match current_line {
-			0 =>
-			("August 7th - Outside The Main Entrance".to_string(),
-			ActorInfo{actor: Actor::Nobody}),
-			1 =>
-			("Well, this is it. Time to see if you have impressed Isa enough to stay.".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			2 =>
-			("Hopefully you decide to stick around if all things go well. But regardless, it has been nice seeing you.".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			3 =>
-			("Isa is waiting for you in her office, best not to keep her waiting too long.".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			4 =>
-			("My name is Arnie by the way. It has been a pleasure getting to see you uncover the wonders of mole ranching. So thanks.".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			5 =>
-			("Until next time.".to_string(),
-			ActorInfo{actor: Actor::Guard}),
-			6 =>
-			("August 7th - Inside the Office".to_string(),
-			ActorInfo{actor: Actor::Nobody}),
-			7 =>
-			("One week down. You have made great strides. It has been an honor seeing you grow and thrive.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			8 =>
-			("If you would be willing, it would be great if you would stay on as a full time rancher here.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			9 =>
-			("I will give you some time to think it over. Feel free to revisit any of the training exercises you have completed so far.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
-			_ =>
-			("If you do decide to stay, Arnie has been working hard on improving the reactor. I believe he has left a note for you on your computer.".to_string(),
-			ActorInfo{actor: Actor::Scientist}),
+			0 | 6 => Actor::Nobody,
+			1..=5 => Actor::Guard,
+			_ => Actor::Scientist,
 		},
 		7 => match current_line {
-			0 =>
-			("August 8th - Inside the Reactor".to_string(),
-			ActorInfo{actor: Actor::Nobody}),
-			1 =>
-			("You are the only person here.".to_string(),
-			ActorInfo{actor: Actor::You}),
-			2 =>
-			("The temperature is slowly rising.".to_string(),
-			ActorInfo{actor: Actor::You}),
-			3 =>
-			("Just as you begin to panic, you hear a screeching alarm echo around the reactor...".to_string(),
-			ActorInfo{actor: Actor::You}),
-			4 =>
-			("August 8th - The Reactor Control Bench".to_string(),
-			ActorInfo{actor: Actor::Nobody}),
-			5 =>
-			("You must have drifted off ranching the moles. It has been a busy week, and it can be so relaxing watching them bounce around inside the reactors.".to_string(),
-			ActorInfo{actor: Actor::You}),
-			_ =>
-			("Ah well. Time to get back to work.".to_string(),
-			ActorInfo{actor: Actor::You}),
-		}
+			0 | 4 => Actor::Nobody,
+			_ => Actor::You,
+		},
 		_ => match current_line {
-			0 =>
-			("August ??? - Please Report This".to_string(),
-			ActorInfo{actor: Actor::Nobody}),
-			_ =>
-			("You seem to have stumbled upon an unreachable cutscene, interesting. This should not have happened! Please let me know how you got here so I can fix it!".to_string(),
-			ActorInfo{actor: Actor::You}),
-		}
-	}
+			0 => Actor::Nobody,
+			_ => Actor::You,
+		},
+	};
+	let scene = if current_scene < 8 {current_scene.to_string()} else {"unknown".to_string()};
+	let line = current_line.min(lines_per_scene(current_scene));
+	(locale.get(&format!("cutscene.{}.{}", scene, line)), ActorInfo{actor})
 }
 
 pub fn lines_per_scene(
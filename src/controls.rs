@@ -0,0 +1,221 @@
+// Import Bevy game engine essentials
+use bevy::{prelude::*, ecs::system::SystemParam};
+// Import components, resources, and events
+use crate::components::*;
+
+// Plugin for reading the player's input as actions, and for
+// rebinding those actions from the controls popup
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+			.insert_resource(Rebinding::default())
+			.add_systems(Update, (
+				capture_rebinding,
+				update_binding_text.after(capture_rebinding),
+			).run_if(in_state(PauseState::Paused)))
+		;
+	}
+}
+
+impl InputAction {
+	// Key of the action's name in the string tables
+	pub fn key(&self) -> &'static str {
+		match self {
+			InputAction::MoveLeft => "action.move_left",
+			InputAction::MoveRight => "action.move_right",
+			InputAction::RotateCW => "action.rotate_cw",
+			InputAction::RotateCCW => "action.rotate_ccw",
+			InputAction::Sprint => "action.sprint",
+			InputAction::LaunchOnce => "action.launch_once",
+			InputAction::LaunchHold => "action.launch_hold",
+			InputAction::PlaceSpawner => "action.place_spawner",
+			InputAction::SelectReactor => "action.select_reactor",
+			InputAction::DragLever => "action.drag_lever",
+			InputAction::TrackMolecule => "action.track_molecule",
+			InputAction::Pan => "action.pan",
+			InputAction::ZoomIn => "action.zoom_in",
+			InputAction::ZoomOut => "action.zoom_out",
+			InputAction::AdvanceCutscene => "action.advance_cutscene",
+		}
+	}
+}
+
+impl InputBinding {
+	// Keys are shown by their key code, mouse buttons are
+	// named in the selected language
+	pub fn name(
+		&self,
+		locale: &Locale,
+	) -> String {
+		match self {
+			InputBinding::Key(key) => format!("{:?}", key),
+			InputBinding::Mouse(MouseButton::Left) => locale.get("binding.mouse_left"),
+			InputBinding::Mouse(MouseButton::Right) => locale.get("binding.mouse_right"),
+			InputBinding::Mouse(MouseButton::Middle) => locale.get("binding.mouse_middle"),
+			InputBinding::Mouse(MouseButton::Other(button)) => locale.format("binding.mouse_other", &[button.to_string()]),
+		}
+	}
+}
+
+// Gamepads are not rebound. Each action has a fixed button
+// or stick direction alongside its keys and mouse buttons
+enum GamepadBinding {
+	Button(GamepadButtonType),
+	Stick(GamepadAxisType, f32),
+}
+
+fn get_gamepad_binding(
+	action: InputAction,
+) -> Option<GamepadBinding> {
+	use GamepadBinding::*;
+	match action {
+		InputAction::MoveLeft => Some(Stick(GamepadAxisType::LeftStickX, -1.0)),
+		InputAction::MoveRight => Some(Stick(GamepadAxisType::LeftStickX, 1.0)),
+		InputAction::RotateCCW => Some(Stick(GamepadAxisType::RightStickX, -1.0)),
+		InputAction::RotateCW => Some(Stick(GamepadAxisType::RightStickX, 1.0)),
+		InputAction::Sprint => Some(Button(GamepadButtonType::LeftThumb)),
+		InputAction::LaunchOnce => Some(Button(GamepadButtonType::South)),
+		InputAction::LaunchHold => Some(Button(GamepadButtonType::West)),
+		InputAction::PlaceSpawner => Some(Button(GamepadButtonType::North)),
+		InputAction::ZoomIn => Some(Button(GamepadButtonType::DPadUp)),
+		InputAction::ZoomOut => Some(Button(GamepadButtonType::DPadDown)),
+		InputAction::AdvanceCutscene => Some(Button(GamepadButtonType::South)),
+		// Reactors and levers are handled by the gamepad module,
+		// and the camera follows molecules without panning
+		InputAction::SelectReactor
+		| InputAction::DragLever
+		| InputAction::TrackMolecule
+		| InputAction::Pan => None,
+	}
+}
+
+// Gives systems the state of each action, whichever keys,
+// mouse buttons or gamepad controls it is currently bound to
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+	keyboard: Res<'w, Input<KeyCode>>,
+	mouse: Res<'w, Input<MouseButton>>,
+	gamepads: Res<'w, Gamepads>,
+	gamepad_buttons: Res<'w, Input<GamepadButton>>,
+	gamepad_axes: Res<'w, Axis<GamepadAxis>>,
+	settings: Res<'w, Settings>,
+}
+
+impl<'w> ActionInput<'w> {
+	pub fn pressed(
+		&self,
+		action: InputAction,
+	) -> bool {
+		self.button_pressed(action) || self.stick(action) > 0.5
+	}
+
+	pub fn just_pressed(
+		&self,
+		action: InputAction,
+	) -> bool {
+		self.settings.controls.get(action).iter().flatten().any(|binding| match binding {
+			InputBinding::Key(key) => self.keyboard.just_pressed(*key),
+			InputBinding::Mouse(button) => self.mouse.just_pressed(*button),
+		}) || match get_gamepad_binding(action) {
+			Some(GamepadBinding::Button(button_type)) => self.gamepads.iter()
+				.any(|gamepad| self.gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type))),
+			_ => false,
+		}
+	}
+
+	// How far between -1 and 1 the player is asking to go, with
+	// keys and buttons going all the way and sticks partway
+	pub fn axis(
+		&self,
+		negative: InputAction,
+		positive: InputAction,
+	) -> f32 {
+		let strength = |action| if self.button_pressed(action) {1.0} else {self.stick(action)};
+		strength(positive) - strength(negative)
+	}
+
+	fn button_pressed(
+		&self,
+		action: InputAction,
+	) -> bool {
+		self.settings.controls.get(action).iter().flatten().any(|binding| match binding {
+			InputBinding::Key(key) => self.keyboard.pressed(*key),
+			InputBinding::Mouse(button) => self.mouse.pressed(*button),
+		}) || match get_gamepad_binding(action) {
+			Some(GamepadBinding::Button(button_type)) => self.gamepads.iter()
+				.any(|gamepad| self.gamepad_buttons.pressed(GamepadButton::new(gamepad, button_type))),
+			_ => false,
+		}
+	}
+
+	// How far a stick is pushed in the direction of an action,
+	// from 0 at the edge of the deadzone to 1
+	fn stick(
+		&self,
+		action: InputAction,
+	) -> f32 {
+		match get_gamepad_binding(action) {
+			Some(GamepadBinding::Stick(axis_type, direction)) => self.gamepads.iter()
+				.filter_map(|gamepad| self.gamepad_axes.get(GamepadAxis::new(gamepad, axis_type)))
+				.map(|value| ((value * direction - GAMEPAD_DEADZONE) / (1.0 - GAMEPAD_DEADZONE)).clamp(0.0, 1.0))
+				.fold(0.0, f32::max),
+			_ => 0.0,
+		}
+	}
+}
+
+// While a binding is waiting on the controls popup, the next key
+// or mouse button pressed replaces it. Escape cancels, and
+// Backspace leaves the binding empty
+fn capture_rebinding(
+	keyboard: Res<Input<KeyCode>>,
+	mouse: Res<Input<MouseButton>>,
+	mut rebinding: ResMut<Rebinding>,
+	mut settings: ResMut<Settings>,
+) {
+	// The click that started rebinding is not taken as the binding
+	if rebinding.is_changed() {
+		return;
+	}
+	if let Some((action, slot)) = rebinding.0 {
+		let pressed = keyboard.get_just_pressed().next().map(|key| InputBinding::Key(*key))
+			.or_else(|| mouse.get_just_pressed().next().map(|button| InputBinding::Mouse(*button)));
+		match pressed {
+			Some(InputBinding::Key(KeyCode::Escape)) => {
+				rebinding.0 = None;
+			},
+			Some(InputBinding::Key(KeyCode::Back)) => {
+				settings.controls.set(action, slot, None);
+				rebinding.0 = None;
+			},
+			Some(binding) => {
+				settings.controls.set(action, slot, Some(binding));
+				rebinding.0 = None;
+			},
+			None => (),
+		}
+	}
+}
+
+fn update_binding_text(
+	settings: Res<Settings>,
+	rebinding: Res<Rebinding>,
+	locale: Res<Locale>,
+	mut binding_text_query: Query<(&mut Text, &BindingText)>,
+) {
+	for (mut text, binding_text) in binding_text_query.iter_mut() {
+		let value = if rebinding.0 == Some((binding_text.0, binding_text.1)) {
+			locale.get("controls.press_key")
+		} else {
+			match settings.controls.get(binding_text.0)[binding_text.1] {
+				Some(binding) => binding.name(&locale),
+				None => "-".to_string(),
+			}
+		};
+		if text.sections[0].value != value {
+			text.sections[0].value = value;
+		}
+	}
+}
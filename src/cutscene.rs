@@ -1,7 +1,7 @@
 // Import Bevy game engine essentials
 use bevy::prelude::*;
 // Import components, resources, and events
-use crate::components::*;
+use crate::{components::*, controls::ActionInput};
 
 // Plugin for handling narrative and text
 // based elements of the game
@@ -26,10 +26,12 @@ impl Plugin for CutscenePlugin {
 fn spawn_cutscene(
 	mut commands: Commands,
 	asset_server: Res<AssetServer>,
+	settings: Res<Settings>,
 	cutscene_tracker: Res<CutsceneTracker>,
+	locale: Res<Locale>,
 	ortho_size: Res<OrthoSize>,
 ) {
-	let (initial_line, _) = next_line(cutscene_tracker.current_scene, 0);
+	let (initial_line, _) = next_line(cutscene_tracker.current_scene, 0, &locale);
 	for char in actors_in_scene(cutscene_tracker.current_scene) {
 		commands
 			.spawn((SpriteBundle {
@@ -98,7 +100,7 @@ fn spawn_cutscene(
 
 	commands
 		.spawn((SpriteBundle {
-			transform: Transform::from_xyz(150.0, -250.0, 600.0),
+			transform: Transform::from_xyz(TEXT_BOX_X, TEXT_BOX_Y, 600.0),
 			sprite: Sprite {
 				color: Color::hex("EDD6AD").unwrap(),
 				custom_size: Some(Vec2::new(TEXT_BOX_WIDTH, TEXT_BOX_HEIGHT)),
@@ -107,6 +109,7 @@ fn spawn_cutscene(
 			..Default::default()
 		},
 		DespawnOnExitGameState,
+		ScaledBox::TextBox,
 		Name::new("Text Box Sprite")
 	)).with_children(|parent| {
 		parent
@@ -121,7 +124,7 @@ fn spawn_cutscene(
 					10.0,
 				),
 				text_anchor: bevy::sprite::Anchor::TopLeft,
-				text: Text::from_section(initial_line, get_cutscene_text_style(&asset_server))
+				text: Text::from_section(initial_line, get_cutscene_text_style(&asset_server, &settings))
 				.with_alignment(TextAlignment::Left),
 				..Default::default()
 			},
@@ -150,7 +153,7 @@ fn spawn_cutscene(
 			},
 			..Default::default()
 		},
-		ButtonEffect::CutsceneButton(CutsceneButton::SkipCutscene),
+		ButtonEffect::Cutscene(CutsceneButton::SkipCutscene),
 		button,
 		DespawnOnExitGameState,
 		Name::new("Skip Cutscene Button")
@@ -158,10 +161,11 @@ fn spawn_cutscene(
 		parent
 			.spawn((Text2dBundle {
 				transform: Transform::from_xyz(0.0, -3.0, 10.0,),
-				text: Text::from_section(format!("Skip"), get_button_text_style(&asset_server))
+				text: Text::from_section(locale.get("button.skip"), get_button_text_style(&asset_server, &settings))
 					.with_alignment(TextAlignment::Center),
 				..Default::default()
 			},
+			LocalizedText("button.skip"),
 			Name::new("Skip Cutscene Button Text")
 		));
 	});
@@ -175,16 +179,17 @@ pub fn update_cutscene_text(
 	mut text_speed_timer: ResMut<TextSpeedTimer>,
 	mut ev_w_fade_transition: EventWriter<FadeTransitionEvent>,
 	asset_server: Res<AssetServer>,
-	mouse: Res<Input<MouseButton>>,
-	keyboard: Res<Input<KeyCode>>,
+	settings: Res<Settings>,
+	locale: Res<Locale>,
+	actions: ActionInput,
 	time: Res<Time>,
 ) {
-	if mouse.just_pressed(MouseButton::Left) || keyboard.just_pressed(KeyCode::Space){
+	if actions.just_pressed(InputAction::AdvanceCutscene) {
 		match cutscene_tracker.cutscene_state {
 			CutsceneState::Initialize => {
 				cutscene_tracker.current_character = 0;
 				cutscene_tracker.current_line = 1;
-				(cutscene_tracker.full_line, cutscene_tracker.actor_info) = next_line(cutscene_tracker.current_scene, cutscene_tracker.current_line);
+				(cutscene_tracker.full_line, cutscene_tracker.actor_info) = next_line(cutscene_tracker.current_scene, cutscene_tracker.current_line, &locale);
 				cutscene_tracker.cutscene_state = CutsceneState::Started;
 			},
 			CutsceneState::Started => {
@@ -194,11 +199,9 @@ pub fn update_cutscene_text(
 					if cutscene_tracker.current_line != lines_per_scene(cutscene_tracker.current_scene) {
 						cutscene_tracker.current_character = 0;
 						cutscene_tracker.current_line += 1;
-						(cutscene_tracker.full_line, cutscene_tracker.actor_info) = next_line(cutscene_tracker.current_scene, cutscene_tracker.current_line);
+						(cutscene_tracker.full_line, cutscene_tracker.actor_info) = next_line(cutscene_tracker.current_scene, cutscene_tracker.current_line, &locale);
 					} else {
-						match cutscene_tracker.current_scene {
-							_ => ev_w_fade_transition.send(FadeTransitionEvent(GameState::Lab)),
-						}
+						ev_w_fade_transition.send(FadeTransitionEvent(GameState::Lab));
 						cutscene_tracker.current_scene = 0;
 						cutscene_tracker.current_line = 0;
 						cutscene_tracker.current_character = 0;
@@ -213,19 +216,21 @@ pub fn update_cutscene_text(
 		}
 	}
 	text_speed_timer.0.tick(time.delta());
-	if cutscene_tracker.cutscene_state == CutsceneState::Started {
-		if text_speed_timer.0.just_finished() {
-			if cutscene_tracker.current_character + 1 <= cutscene_tracker.full_line.len() {
-				cutscene_tracker.current_character += 1;
-				for (mut text, _) in cutscene_text_query.iter_mut() {
-					text.sections = vec![
-						TextSection::new(
-							&cutscene_tracker.full_line[0..cutscene_tracker.current_character],
-							get_cutscene_text_style(&asset_server),
-						)
-					];
-				}
-			}
+	if cutscene_tracker.cutscene_state == CutsceneState::Started && text_speed_timer.0.just_finished()
+	&& cutscene_tracker.current_character < cutscene_tracker.full_line.len() {
+		cutscene_tracker.current_character += 1;
+		// Lines are counted in bytes, so letters written with
+		// more than one byte are shown all at once
+		while !cutscene_tracker.full_line.is_char_boundary(cutscene_tracker.current_character) {
+			cutscene_tracker.current_character += 1;
+		}
+		for (mut text, _) in cutscene_text_query.iter_mut() {
+			text.sections = vec![
+				TextSection::new(
+					&cutscene_tracker.full_line[0..cutscene_tracker.current_character],
+					get_cutscene_text_style(&asset_server, &settings),
+				)
+			];
 		}
 	}
 }
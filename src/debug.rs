@@ -1,5 +1,5 @@
 // Import Bevy game engine essentials
-use bevy::{prelude::*, render::view::RenderLayers, math::Vec3Swizzles};
+use bevy::{prelude::*, utils::{Duration, Instant}};
 // Import random number generation for adding variation
 use rand::Rng;
 // Import components, resources, and events
@@ -15,10 +15,19 @@ impl Plugin for DebugPlugin {
 			.add_systems( Update, (
 				change_game_state,
 				change_pause_state,
-				//debug_popup,
-				//debug_molecule,
 				vent_reactor,
 			))
+			.init_resource::<CollisionBenchmark>()
+			.add_systems(Update, (
+				spawn_benchmark_molecules,
+				toggle_broad_phase,
+			).run_if(in_state(GameState::Reactor)))
+			.add_systems(FixedUpdate, (
+				start_collision_benchmark.before(MoleculeMovementSet),
+				report_collision_benchmark.after(MoleculeMovementSet),
+			).run_if(in_state(GameState::Reactor))
+			.run_if(not(in_state(PauseState::Paused)))
+			)
 		;
 	}
 }
@@ -47,6 +56,10 @@ fn change_game_state(
 	if keyboard.just_pressed(KeyCode::B) {
 		ev_w_fade_transition.send(FadeTransitionEvent(GameState::Reactor));
 	}
+
+	if keyboard.just_pressed(KeyCode::N) {
+		ev_w_fade_transition.send(FadeTransitionEvent(GameState::Editor));
+	}
 }
 
 // Allows the user to freely change the pause state
@@ -64,159 +77,108 @@ fn change_pause_state(
 	}
 }
 
-// Allows the user to create a popup to appear at the cursor's current location
-fn debug_popup(
-	window_query: Query<&Window>,
-	ortho_size: Res<OrthoSize>,
-	mouse: Res<Input<MouseButton>>,
-	asset_server: Res<AssetServer>,
-	mut ev_w_popup: EventWriter<PopupEvent>,
+// Allows the user to remove all molecules from the selected reactor
+fn vent_reactor(
+	mut commands: Commands,
+	keyboard: Res<Input<KeyCode>>,
+	selected_reactor_query: Query<(&ReactorInfo, With<SelectedReactor>)>,
+	molecule_query: Query<(Entity, &ReactorInfo, With<Molecule>)>,
 ) {
-	// Get the current window, and the cursor position scaled 
-	// to the window size
-	let w = window_query.single();
-	if let Some(p) = w.cursor_position() {
-		let p = Vec2::new(
-			ortho_size.width * (p.x / w.width() - 0.5), 
-			-ortho_size.height * (p.y / w.height() - 0.5)
-		);
-		if mouse.just_pressed(MouseButton::Right) {
-			ev_w_popup.send(PopupEvent{
-				origin: p,
-				image: asset_server.load("sprites/popup/level_select.png"),
-				alpha: 1.0,
-				popup_type: PopupType::LevelSelect,
-			});
+	if keyboard.just_pressed(KeyCode::L) {
+		for (r_info, _) in selected_reactor_query.iter() {
+			for (entity, m_r_info, _) in molecule_query.iter() {
+				if m_r_info.reactor_id == r_info.reactor_id {
+					commands.entity(entity).despawn_recursive();
+				}
+			}
 		}
 	}
 }
 
+// Number of collision steps averaged for each benchmark report
+const BENCHMARK_STEPS: u32 = 120;
 
+// Wall clock time spent in the molecule collision step
+#[derive(Resource, Default)]
+struct CollisionBenchmark {
+	step_start: Option<Instant>,
+	total: Duration,
+	steps: u32,
+}
 
-// Allows the user to spawn molecules and spawners
-fn debug_molecule(
+// Allows the user to fill the selected reactor with as many molecules
+// as the cap allows, to benchmark the collision step under load
+fn spawn_benchmark_molecules(
 	mut commands: Commands,
-	mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-	mut launch_timer: ResMut<LaunchTimer>,
-	mut current_cost: ResMut<CurrentCost>,
-	selected_palette: Res<SelectedPalette>,
-	selected_molecule_type: Res<SelectedMoleculeType>,
 	selected_reactor_query: Query<(&ReactorInfo, With<SelectedReactor>)>,
-	launch_tube_query: Query<(&Transform, &LaunchTube)>,
-	asset_server: Res<AssetServer>,
+	registry: Res<MoleculeRegistry>,
+	mut sim_rng: ResMut<SimRng>,
 	keyboard: Res<Input<KeyCode>>,
-	time: Res<Time>,
 ) {
-	launch_timer.0.tick(time.delta());
-	// Space for selected, R for random, T for true random, S for spawner
-	if keyboard.just_pressed(KeyCode::S) || keyboard.just_pressed(KeyCode::Space) || keyboard.pressed(KeyCode::R) || keyboard.pressed(KeyCode::T) || keyboard.pressed(KeyCode::W) {
+	if keyboard.just_pressed(KeyCode::K) {
 		let mut rng = rand::thread_rng();
-		let molecule_index = if keyboard.just_pressed(KeyCode::Space) || keyboard.pressed(KeyCode::W) {selected_molecule_type.0} else {rng.gen_range(0..5)};
-		let radius = if keyboard.pressed(KeyCode::T) {rand::random::<f32>() * 128.0 + 16.0} else {get_molecule_radius(molecule_index)};
-		let mass = if keyboard.pressed(KeyCode::T) {rand::random::<f32>() * 3000.0 + 10.0} else {get_molecule_mass(molecule_index)};
-
-		let texture_handle = asset_server.load(get_molecule_path(molecule_index));
-		let texture_atlas = TextureAtlas::from_grid(texture_handle, Vec2::new(32.0, 32.0), 4, 2, None, None);
-		let texture_atlas_handle = texture_atlases.add(texture_atlas);
-
 		for (info, _) in selected_reactor_query.iter() {
-			for (transform, launch_tube) in launch_tube_query.iter() {
-				if launch_tube.id == info.reactor_id {
-					if keyboard.just_pressed(KeyCode::Space) || keyboard.pressed(KeyCode::R) || keyboard.pressed(KeyCode::T) || keyboard.pressed(KeyCode::W) {
-						if launch_timer.0.finished() {
-							launch_timer.0.reset();
-							current_cost.0 += get_molecule_cost(molecule_index);
-							let (target, distance) = match info.reactor_type {
-								ReactorType::Rectangle{dimensions, ..} => (Vec2::new(transform.translation.x, transform.translation.y - dimensions.height / 2.0), dimensions.height / 2.0), 
-								ReactorType::Circle{origin, radius} => (origin, radius),
-							};
-							let direction = -transform.local_y().xy();
-							let velocity = get_molecule_initial_velocity(molecule_index);
-							commands
-								.spawn((SpriteSheetBundle {
-									transform: Transform::from_translation(((Vec2::new(transform.translation.x, transform.translation.y) - target)
-										.clamp_length_max(distance - get_molecule_radius(molecule_index)) + target).extend(500.0)),
-									texture_atlas: texture_atlas_handle.clone(),
-									sprite: TextureAtlasSprite{
-										color: get_molecule_color(molecule_index, selected_palette.0),
-										index: 0,
-										custom_size: Some(Vec2::new(radius * 2.0, radius * 2.0)),
-										..Default::default()
-									},
-									..Default::default()
-								},
-								*info,
-								Molecule(get_molecule_lifetime(molecule_index)),
-								MoleculeInfo {
-									index: molecule_index,
-									reacted: false,
-									radius: radius,
-									mass: mass,
-								},
-								ParticleTrail{
-									spawn_timer: Timer::from_seconds(PARTICLE_SPAWN_DELAY, TimerMode::Repeating),
-									duration: PARTICLE_DURATION,
-								},
-								Velocity(Vec2::new(velocity, velocity) * direction),
-								AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-								AnimationIndices{ 
-									first: 0, 
-									total: 8,
-								},
-								RenderLayers::layer(1),
-								DespawnOnExitGameState,
-								Name::new("Debug Molecule")
-							));
-						}
-					}
-					// S for Spawner
-					if keyboard.just_pressed(KeyCode::S) {
-						let (target, distance) = match info.reactor_type {
-							ReactorType::Rectangle{dimensions, ..} => (Vec2::new(transform.translation.x, transform.translation.y - dimensions.height / 2.0), dimensions.height / 2.0), 
-							ReactorType::Circle{origin, radius} => (origin, radius),
-						};
-						commands
-							.spawn((SpriteBundle {
-								transform: Transform::from_translation(((Vec2::new(transform.translation.x, transform.translation.y) - target)
-									.clamp_length_max(distance - 64.0) + target).extend(400.0))
-									.with_rotation(Quat::from_rotation_arc(Vec3::Y, (transform.translation.xy() - target).normalize().extend(0.0))),
-								sprite: Sprite{
-									color: Color::BLACK,
-									custom_size: Some(Vec2::new(64.0, 128.0)),
-									..Default::default()
-								},
-								..Default::default()
-							},
-							*info,
-							MoleculeSpawnerInfo{
-								spawner_index: selected_molecule_type.0,
-								spawner_timer: Timer::from_seconds(3.0, TimerMode::Repeating),
-							},
-							RenderLayers::layer(1),
-							DespawnOnExitGameState,
-							Name::new("Debug Molecule Spawner"),
-						));
-					};
-				}
+			for _ in 0..MOLECULE_CAP {
+				let molecule_index = rng.gen_range(0..5);
+				let radius = registry.radius(molecule_index);
+				let location = match info.reactor_type {
+					ReactorType::Rectangle{origin, dimensions} => origin + Vec2::new(
+						(rng.gen::<f32>() - 0.5) * (dimensions.width - radius * 2.0),
+						(rng.gen::<f32>() - 0.5) * (dimensions.height - radius * 2.0),
+					),
+					ReactorType::Circle{origin, radius: reactor_radius} => origin
+						+ Vec2::from_angle(rng.gen::<f32>() * std::f32::consts::TAU) * rng.gen::<f32>().sqrt() * (reactor_radius - radius),
+				};
+				let velocity = registry.launch_speed(molecule_index);
+				let direction = Vec2::from_angle(rng.gen::<f32>() * std::f32::consts::TAU);
+				commands.spawn(MoleculeBundle{
+					name: Name::new("Benchmark Molecule"),
+					..MoleculeBundle::new(&registry, &mut sim_rng, molecule_index, *info, location, direction * velocity)
+				});
 			}
 		}
 	}
 }
 
-// Allows the user to remove all molecules from the selected reactor
-fn vent_reactor(
-	mut commands: Commands,
+// Allows the user to switch between the spatial hash and checking
+// every pair of molecules, to compare the collision step before and after
+fn toggle_broad_phase(
 	keyboard: Res<Input<KeyCode>>,
-	selected_reactor_query: Query<(&ReactorInfo, With<SelectedReactor>)>,
-	molecule_query: Query<(Entity, &ReactorInfo, With<Molecule>)>,
+	mut spatial_hash: ResMut<SpatialHash>,
+	mut benchmark: ResMut<CollisionBenchmark>,
 ) {
-	if keyboard.just_pressed(KeyCode::L) {
-		for (r_info, _) in selected_reactor_query.iter() {
-			for (entity, m_r_info, _) in molecule_query.iter() {
-				if m_r_info.reactor_id == r_info.reactor_id {
-					commands.entity(entity).despawn_recursive();
-				}
-			}
-		}
+	if keyboard.just_pressed(KeyCode::J) {
+		spatial_hash.enabled = !spatial_hash.enabled;
+		benchmark.total = Duration::ZERO;
+		benchmark.steps = 0;
+		info!("Collision broad phase: {}", if spatial_hash.enabled {"spatial hash"} else {"brute force"});
 	}
-}
\ No newline at end of file
+}
+
+fn start_collision_benchmark(
+	mut benchmark: ResMut<CollisionBenchmark>,
+) {
+	benchmark.step_start = Some(Instant::now());
+}
+
+// Logs the average collision step time every BENCHMARK_STEPS steps
+fn report_collision_benchmark(
+	mut benchmark: ResMut<CollisionBenchmark>,
+	spatial_hash: Res<SpatialHash>,
+	molecule_count: Res<MoleculeCount>,
+) {
+	if let Some(start) = benchmark.step_start.take() {
+		benchmark.total += start.elapsed();
+		benchmark.steps += 1;
+	}
+	if benchmark.steps >= BENCHMARK_STEPS {
+		info!("Collision step ({}): {:.3} ms average over {} steps with {} molecules",
+			if spatial_hash.enabled {"spatial hash"} else {"brute force"},
+			benchmark.total.as_secs_f64() * 1000.0 / benchmark.steps as f64,
+			benchmark.steps,
+			molecule_count.total,
+		);
+		benchmark.total = Duration::ZERO;
+		benchmark.steps = 0;
+	}
+}
@@ -0,0 +1,802 @@
+// Import Bevy game engine essentials
+use bevy::{prelude::*, gizmos::GizmoConfig, render::view::RenderLayers};
+// Import the simulation's level checks
+use mole_sim::levels::validate_level;
+// Import components, resources, and events
+use crate::{components::*, levels::Levels};
+
+// Plugin for the level editor, which edits the reactors, connections,
+// starting molecules and goal of a level in the reactor view and
+// writes them back to the level's file
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app
+			.add_systems(OnEnter(GameState::Editor), (
+				start_editor,
+				spawn_editor_visuals,
+			))
+			.add_systems(Update, (
+				switch_editor_tool,
+				edit_with_mouse,
+				edit_with_keyboard,
+				load_and_save_level,
+				draw_edited_level,
+				update_editor_text,
+			).chain()
+			.run_if(in_state(GameState::Editor)))
+			.add_systems(OnExit(GameState::Editor), (
+				stop_editor,
+			))
+		;
+	}
+}
+
+const EDITOR_HELP_TEXT: &str = "Left Click: use tool  Right Click: pan  Mouse Wheel: zoom\n\
+1-6: tool  Q/E: brush molecule  [ ]: connection  Arrows: resize  Delete: remove\n\
+I: input chamber  O: product chamber  F: intake filter  A: available molecule\n\
+G: goal type  H: goal molecule  +/-: goal quantity  PgUp/PgDn: level  Ctrl+S: save  Esc: exit";
+
+impl LevelEditor {
+	pub fn new(
+		level_id: usize,
+		level: LevelDefinition,
+	) -> Self {
+		LevelEditor{
+			level_id,
+			level,
+			tool: EditorTool::Select,
+			selection: None,
+			brush_molecule: 0,
+			connection_id: 0,
+			cursor: None,
+			drag_start: None,
+			unsaved: false,
+			confirm_exit: false,
+			message: String::new(),
+		}
+	}
+
+	// The reactor drawn on top at the given point, later
+	// reactors in the level are drawn over earlier ones
+	fn reactor_at(
+		&self,
+		point: Vec2,
+		margin: f32,
+	) -> Option<usize> {
+		self.level.reactors.iter().rposition(|reactor| reactor_contains(&reactor.reactor_type, point, margin))
+	}
+
+	// Molecules are picked before connections, and connections
+	// before the reactors they sit on
+	fn pick(
+		&self,
+		point: Vec2,
+		registry: &MoleculeRegistry,
+	) -> Option<EditorSelection> {
+		for (reactor_id, reactor) in self.level.reactors.iter().enumerate().rev() {
+			if let Some(i) = reactor.molecules.iter().rposition(|molecule| molecule.location.distance(point) <= registry.radius(molecule.index)) {
+				return Some(EditorSelection::Molecule(reactor_id, i));
+			}
+		}
+		for (reactor_id, reactor) in self.level.reactors.iter().enumerate().rev() {
+			if let Some(i) = reactor.connections.iter().rposition(|connection| reactor.reactor_type.wall_point(connection.direction).distance(point) <= EDITOR_PICK_DISTANCE) {
+				return Some(EditorSelection::Connection(reactor_id, i));
+			}
+		}
+		self.reactor_at(point, 0.0).map(EditorSelection::Reactor)
+	}
+
+	fn add_reactor(
+		&mut self,
+		reactor_type: ReactorType,
+	) {
+		self.level.reactors.push(ReactorDefinition{
+			reactor_type,
+			input_chamber: false,
+			product_chamber: false,
+			launch_tube_limits: Limits(1.0, 1.0),
+			connections: Vec::new(),
+			molecules: Vec::new(),
+		});
+		self.selection = Some(EditorSelection::Reactor(self.level.reactors.len() - 1));
+		self.unsaved = true;
+	}
+
+	// Connections sit on the wall closest to where they are placed,
+	// intakes start off accepting the brush molecule
+	fn add_connection(
+		&mut self,
+		point: Vec2,
+		intake: bool,
+	) {
+		let Some(reactor_id) = self.reactor_at(point, EDITOR_PICK_DISTANCE) else {
+			self.message = "Connections must be placed on a chamber wall".to_string();
+			return;
+		};
+		let reactor = &mut self.level.reactors[reactor_id];
+		let direction = wall_direction(&reactor.reactor_type, point);
+		if direction == Vec2::ZERO {
+			self.message = "Connections must be placed on a chamber wall".to_string();
+			return;
+		}
+		reactor.connections.push(ConnectionDefinition{
+			direction,
+			connection_id: self.connection_id,
+			intake,
+			filter: if intake {vec![self.brush_molecule]} else {Vec::new()},
+		});
+		self.selection = Some(EditorSelection::Connection(reactor_id, reactor.connections.len() - 1));
+		self.unsaved = true;
+	}
+
+	fn add_molecule(
+		&mut self,
+		location: Vec2,
+		velocity: Vec2,
+	) {
+		let Some(reactor_id) = self.reactor_at(location, 0.0) else {
+			self.message = "Molecules must be placed inside a chamber".to_string();
+			return;
+		};
+		let molecules = &mut self.level.reactors[reactor_id].molecules;
+		molecules.push(InitialMolecule{
+			index: self.brush_molecule,
+			location,
+			velocity,
+		});
+		self.selection = Some(EditorSelection::Molecule(reactor_id, molecules.len() - 1));
+		self.unsaved = true;
+	}
+
+	// Reactors carry their starting molecules with them and
+	// stay on the grid, molecules move freely
+	fn move_selection(
+		&mut self,
+		offset: Vec2,
+	) {
+		match self.selection {
+			Some(EditorSelection::Reactor(reactor_id)) => {
+				let reactor = &mut self.level.reactors[reactor_id];
+				let origin = reactor.reactor_type.origin();
+				let offset = snap_to_grid(origin + offset) - origin;
+				match reactor.reactor_type {
+					ReactorType::Rectangle{ref mut origin, ..} => *origin += offset,
+					ReactorType::Circle{ref mut origin, ..} => *origin += offset,
+				}
+				for molecule in reactor.molecules.iter_mut() {
+					molecule.location += offset;
+				}
+			},
+			Some(EditorSelection::Molecule(reactor_id, i)) => {
+				self.level.reactors[reactor_id].molecules[i].location += offset;
+			},
+			_ => return,
+		}
+		self.unsaved = true;
+	}
+
+	fn resize_selection(
+		&mut self,
+		change: Vec2,
+	) {
+		let Some(EditorSelection::Reactor(reactor_id)) = self.selection else {
+			return;
+		};
+		match self.level.reactors[reactor_id].reactor_type {
+			ReactorType::Rectangle{ref mut dimensions, ..} => {
+				dimensions.width = (dimensions.width + change.x).max(EDITOR_MIN_REACTOR_SIZE);
+				dimensions.height = (dimensions.height + change.y).max(EDITOR_MIN_REACTOR_SIZE);
+			},
+			ReactorType::Circle{ref mut radius, ..} => {
+				*radius = (*radius + change.x + change.y).max(EDITOR_MIN_REACTOR_SIZE / 2.0);
+			},
+		}
+		self.unsaved = true;
+	}
+
+	// Reactors the goal is limited to are kept, and goals limited
+	// to later reactors follow them down the list
+	fn delete_selection(&mut self) {
+		match self.selection.take() {
+			Some(EditorSelection::Reactor(reactor_id)) => {
+				if self.level.goal.sub_goals().iter().any(|(chamber, _)| *chamber == Some(reactor_id)) {
+					self.message = format!("Reactor {} is counted by the goal, change the goal in the level file first", reactor_id);
+					self.selection = Some(EditorSelection::Reactor(reactor_id));
+					return;
+				}
+				self.level.reactors.remove(reactor_id);
+				renumber_goal_chambers(&mut self.level.goal, reactor_id);
+			},
+			Some(EditorSelection::Connection(reactor_id, i)) => {
+				self.level.reactors[reactor_id].connections.remove(i);
+			},
+			Some(EditorSelection::Molecule(reactor_id, i)) => {
+				self.level.reactors[reactor_id].molecules.remove(i);
+			},
+			None => return,
+		}
+		self.unsaved = true;
+	}
+
+	fn set_goal(
+		&mut self,
+		goal: WinCondition,
+		registry: &MoleculeRegistry,
+	) {
+		self.level.goal_text = get_goal_text(&goal, registry);
+		self.level.goal = goal;
+		self.unsaved = true;
+	}
+
+	// Catches the mistakes validate_level lets through but
+	// which would leave a level unplayable
+	fn check_layout(&self) -> Result<(), String> {
+		validate_level(&self.level)?;
+		for reactor in self.level.reactors.iter() {
+			for intake in reactor.connections.iter().filter(|connection| connection.intake) {
+				let outlets = self.level.reactors.iter()
+					.flat_map(|reactor| reactor.connections.iter())
+					.filter(|connection| !connection.intake && connection.connection_id == intake.connection_id)
+					.count();
+				if outlets != 1 {
+					return Err(format!("intake {} has {} outlets", intake.connection_id, outlets));
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+fn reactor_contains(
+	reactor_type: &ReactorType,
+	point: Vec2,
+	margin: f32,
+) -> bool {
+	match *reactor_type {
+		ReactorType::Rectangle{origin, dimensions} => (point.x - origin.x).abs() <= dimensions.width / 2.0 + margin
+			&& (point.y - origin.y).abs() <= dimensions.height / 2.0 + margin,
+		ReactorType::Circle{origin, radius} => point.distance(origin) <= radius + margin,
+	}
+}
+
+// Direction from the centre of a reactor to the wall nearest the point,
+// written the same way as the directions in the level files
+fn wall_direction(
+	reactor_type: &ReactorType,
+	point: Vec2,
+) -> Vec2 {
+	let round = |value: f32| (value * 10.0).round() / 10.0;
+	match *reactor_type {
+		ReactorType::Rectangle{origin, dimensions} => {
+			let offset = (point - origin) / Vec2::new(dimensions.width / 2.0, dimensions.height / 2.0);
+			if offset == Vec2::ZERO {
+				Vec2::ZERO
+			} else if offset.x.abs() > offset.y.abs() {
+				Vec2::new(offset.x.signum(), round(offset.y.clamp(-1.0, 1.0)))
+			} else {
+				Vec2::new(round(offset.x.clamp(-1.0, 1.0)), offset.y.signum())
+			}
+		},
+		ReactorType::Circle{origin, ..} => {
+			let direction = (point - origin).normalize_or_zero();
+			Vec2::new((direction.x * 100.0).round() / 100.0, (direction.y * 100.0).round() / 100.0)
+		},
+	}
+}
+
+fn renumber_goal_chambers(
+	goal: &mut WinCondition,
+	removed: usize,
+) {
+	match goal {
+		WinCondition::InChamber(reactor_id, goal) => {
+			if *reactor_id > removed {
+				*reactor_id -= 1;
+			}
+			renumber_goal_chambers(goal, removed);
+		},
+		WinCondition::Sustain(_, goal) => renumber_goal_chambers(goal, removed),
+		WinCondition::All(goals) | WinCondition::Any(goals) => {
+			for goal in goals.iter_mut() {
+				renumber_goal_chambers(goal, removed);
+			}
+		},
+		_ => (),
+	}
+}
+
+fn snap_to_grid(
+	point: Vec2,
+) -> Vec2 {
+	(point / EDITOR_GRID_SIZE).round() * EDITOR_GRID_SIZE
+}
+
+// Goal text in the same wording as the level files, compound
+// goals are described by whoever writes them
+fn get_goal_text(
+	goal: &WinCondition,
+	registry: &MoleculeRegistry,
+) -> String {
+	match *goal {
+		WinCondition::GreaterThan(quantity, index) => format!("Have at least {} {} molecules in the output chamber", quantity, registry.get(index).name),
+		WinCondition::LessThan(1, index) => format!("Remove all {} molecules from the output chamber", registry.get(index).name),
+		WinCondition::LessThan(quantity, index) => format!("Have fewer than {} {} molecules in the output chamber", quantity, registry.get(index).name),
+		WinCondition::Exactly(quantity, index) => format!("Have exactly {} {} molecules in the output chamber", quantity, registry.get(index).name),
+		_ => String::new(),
+	}
+}
+
+// Opens the selected level in the editor, and has gizmos
+// drawn by the reactor camera only
+fn start_editor(
+	mut commands: Commands,
+	mut gizmo_config: ResMut<GizmoConfig>,
+	level: Res<SelectedLevel>,
+	levels: Levels,
+) {
+	gizmo_config.render_layers = RenderLayers::layer(1);
+	commands.insert_resource(LevelEditor::new(level.0, levels.get(level.0).clone()));
+}
+
+fn stop_editor(
+	mut commands: Commands,
+) {
+	commands.remove_resource::<LevelEditor>();
+}
+
+// Spawns the reactor frame and background, and the
+// text showing the editor's state and controls
+fn spawn_editor_visuals(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+	settings: Res<Settings>,
+	ortho_size: Res<OrthoSize>,
+) {
+	commands
+		.spawn((SpriteBundle {
+			texture: asset_server.load("sprites/background/reactor_controls.png"),
+			transform: Transform::from_xyz(0.0, 0.0, 0.0),
+			sprite: Sprite {
+				custom_size: Some(Vec2::new(ortho_size.width, ortho_size.height)),
+				..Default::default()
+			},
+			..Default::default()
+		},
+		DespawnOnExitGameState,
+		Name::new("Editor Controls"),
+	));
+
+	commands
+		.spawn((SpriteBundle {
+			texture: asset_server.load("sprites/background/reactor_background.png"),
+			transform: Transform::from_xyz(0.0, 0.0, 0.0),
+			sprite: Sprite {
+				custom_size: Some(Vec2::new(ortho_size.width * 10.0, ortho_size.height * 10.0)),
+				..Default::default()
+			},
+			..Default::default()
+		},
+		RenderLayers::layer(1),
+		DespawnOnExitGameState,
+		Name::new("Editor Background"),
+	));
+
+	commands
+		.spawn((SpriteBundle {
+			transform: Transform::from_xyz(-ortho_size.width / 2.0 + EDITOR_PANEL_WIDTH / 2.0, 0.0, 700.0),
+			sprite: Sprite {
+				color: Color::hex("2B2B29").unwrap(),
+				custom_size: Some(Vec2::new(EDITOR_PANEL_WIDTH, ortho_size.height)),
+				..Default::default()
+			},
+			..Default::default()
+		},
+		DespawnOnExitGameState,
+		Name::new("Editor Panel"),
+	)).with_children(|parent| {
+		parent
+			.spawn((Text2dBundle {
+				text_2d_bounds: bevy::text::Text2dBounds{ size: Vec2::new(
+					EDITOR_PANEL_WIDTH - TOOLTIP_MARGINS * 2.0,
+					ortho_size.height - TOOLTIP_MARGINS * 2.0,
+				)},
+				transform: Transform::from_xyz(-EDITOR_PANEL_WIDTH / 2.0 + TOOLTIP_MARGINS, ortho_size.height / 2.0 - TOOLTIP_MARGINS, 10.0),
+				text_anchor: bevy::sprite::Anchor::TopLeft,
+				text: Text::from_section("", get_editor_text_style(&asset_server, &settings))
+					.with_alignment(TextAlignment::Left),
+				..Default::default()
+			},
+			EditorText,
+			Name::new("Editor Text"),
+		));
+	});
+
+	commands
+		.spawn((Text2dBundle {
+			transform: Transform::from_xyz(REACTOR_VIEWPORT_CENTER.x, REACTOR_VIEWPORT_CENTER.y - REACTOR_VIEWPORT_HEIGHT / 2.0 - 20.0, 710.0),
+			text_anchor: bevy::sprite::Anchor::TopCenter,
+			text: Text::from_section(EDITOR_HELP_TEXT, get_editor_text_style(&asset_server, &settings))
+				.with_alignment(TextAlignment::Center),
+			..Default::default()
+		},
+		DespawnOnExitGameState,
+		Name::new("Editor Help Text"),
+	));
+}
+
+// Number keys choose the tool, while the brush molecule and
+// connection are used by the tools which place things
+fn switch_editor_tool(
+	keyboard: Res<Input<KeyCode>>,
+	mut editor: ResMut<LevelEditor>,
+) {
+	let tools = [
+		(KeyCode::Key1, EditorTool::Select),
+		(KeyCode::Key2, EditorTool::Rectangle),
+		(KeyCode::Key3, EditorTool::Circle),
+		(KeyCode::Key4, EditorTool::Intake),
+		(KeyCode::Key5, EditorTool::Outlet),
+		(KeyCode::Key6, EditorTool::Molecule),
+	];
+	for (key, tool) in tools {
+		if keyboard.just_pressed(key) {
+			editor.tool = tool;
+		}
+	}
+	if keyboard.just_pressed(KeyCode::Q) {
+		editor.brush_molecule = (editor.brush_molecule + TOTAL_MOLECULE_TYPES - 1) % TOTAL_MOLECULE_TYPES;
+	}
+	if keyboard.just_pressed(KeyCode::E) {
+		editor.brush_molecule = (editor.brush_molecule + 1) % TOTAL_MOLECULE_TYPES;
+	}
+	// Connections are coloured by the molecule with the same index
+	if keyboard.just_pressed(KeyCode::BracketLeft) {
+		editor.connection_id = (editor.connection_id + TOTAL_MOLECULE_TYPES - 1) % TOTAL_MOLECULE_TYPES;
+	}
+	if keyboard.just_pressed(KeyCode::BracketRight) {
+		editor.connection_id = (editor.connection_id + 1) % TOTAL_MOLECULE_TYPES;
+	}
+}
+
+// Places, selects and drags things in the reactor view, molecules
+// are given the velocity they are dragged out with
+fn edit_with_mouse(
+	window_query: Query<&Window>,
+	ortho_size: Res<OrthoSize>,
+	mouse: Res<Input<MouseButton>>,
+	reactor_camera_query: Query<(&Transform, &OrthographicProjection), With<ReactorCamera>>,
+	registry: Res<MoleculeRegistry>,
+	mut editor: ResMut<LevelEditor>,
+) {
+	editor.cursor = None;
+	// Get the current window, and the cursor position scaled
+	// to the window size
+	let w = window_query.single();
+	if let Some(p) = w.cursor_position() {
+		let mut p = Vec2::new(
+			ortho_size.width * (p.x / w.width() - 0.5),
+			-ortho_size.height * (p.y / w.height() - 0.5)
+		);
+		if (p.x - REACTOR_VIEWPORT_CENTER.x).abs() <= REACTOR_VIEWPORT_WIDTH / 2.0 &&
+		(p.y - REACTOR_VIEWPORT_CENTER.y).abs() <= REACTOR_VIEWPORT_HEIGHT / 2.0 {
+			// Scale the cursor position from ortho coords to viewport coords to reactor coords
+			p = (p - REACTOR_VIEWPORT_CENTER) / Vec2::new(REACTOR_VIEWPORT_WIDTH, REACTOR_VIEWPORT_HEIGHT) * Vec2::new(ortho_size.width, ortho_size.height);
+			let (cam_transform, ortho_proj) = reactor_camera_query.single();
+			p = p * ortho_proj.scale + cam_transform.translation.truncate();
+			editor.cursor = Some(p);
+		}
+	}
+	let Some(p) = editor.cursor else {
+		return;
+	};
+
+	if mouse.just_pressed(MouseButton::Left) {
+		editor.message.clear();
+		editor.confirm_exit = false;
+		editor.drag_start = Some(p);
+		match editor.tool {
+			EditorTool::Select => editor.selection = editor.pick(p, &registry),
+			EditorTool::Rectangle => editor.add_reactor(ReactorType::Rectangle{
+				origin: snap_to_grid(p),
+				dimensions: Dimensions{width: 2000.0, height: 1200.0},
+			}),
+			EditorTool::Circle => editor.add_reactor(ReactorType::Circle{
+				origin: snap_to_grid(p),
+				radius: 1000.0,
+			}),
+			EditorTool::Intake => editor.add_connection(p, true),
+			EditorTool::Outlet => editor.add_connection(p, false),
+			EditorTool::Molecule => (),
+		}
+	}
+	if mouse.pressed(MouseButton::Left) && editor.tool == EditorTool::Select {
+		if let Some(start) = editor.drag_start {
+			editor.move_selection(p - start);
+			// Reactors only move a whole grid square at a time, so
+			// the rest of the drag is kept for the next frame
+			let moved = match editor.selection {
+				Some(EditorSelection::Reactor(_)) => snap_to_grid(p - start),
+				_ => p - start,
+			};
+			editor.drag_start = Some(start + moved);
+		}
+	}
+	if mouse.just_released(MouseButton::Left) {
+		if let Some(start) = editor.drag_start.take() {
+			if editor.tool == EditorTool::Molecule {
+				editor.add_molecule(start, (p - start) * EDITOR_VELOCITY_SCALE);
+			}
+		}
+	}
+}
+
+// Changes the selected reactor, connection or molecule, and
+// the level's goal and available molecules
+fn edit_with_keyboard(
+	keyboard: Res<Input<KeyCode>>,
+	registry: Res<MoleculeRegistry>,
+	mut editor: ResMut<LevelEditor>,
+	mut ev_w_fade_transition: EventWriter<FadeTransitionEvent>,
+) {
+	let step = if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {EDITOR_RESIZE_STEP * 10.0} else {EDITOR_RESIZE_STEP};
+	let resizes = [
+		(KeyCode::Left, Vec2::new(-step, 0.0)),
+		(KeyCode::Right, Vec2::new(step, 0.0)),
+		(KeyCode::Down, Vec2::new(0.0, -step)),
+		(KeyCode::Up, Vec2::new(0.0, step)),
+	];
+	for (key, change) in resizes {
+		if keyboard.just_pressed(key) {
+			editor.resize_selection(change);
+		}
+	}
+	if keyboard.any_just_pressed([KeyCode::Delete, KeyCode::Back]) {
+		editor.delete_selection();
+	}
+	if keyboard.just_pressed(KeyCode::I) {
+		if let Some(EditorSelection::Reactor(reactor_id)) = editor.selection {
+			let reactor = &mut editor.level.reactors[reactor_id];
+			reactor.input_chamber = !reactor.input_chamber;
+			editor.unsaved = true;
+		}
+	}
+	if keyboard.just_pressed(KeyCode::O) {
+		if let Some(EditorSelection::Reactor(reactor_id)) = editor.selection {
+			let reactor = &mut editor.level.reactors[reactor_id];
+			reactor.product_chamber = !reactor.product_chamber;
+			editor.unsaved = true;
+		}
+	}
+	if keyboard.just_pressed(KeyCode::F) {
+		if let Some(EditorSelection::Connection(reactor_id, i)) = editor.selection {
+			let brush_molecule = editor.brush_molecule;
+			let connection = &mut editor.level.reactors[reactor_id].connections[i];
+			if connection.intake {
+				match connection.filter.iter().position(|index| *index == brush_molecule) {
+					Some(position) => {connection.filter.remove(position);},
+					None => {connection.filter.push(brush_molecule); connection.filter.sort();},
+				}
+				editor.unsaved = true;
+			}
+		}
+	}
+	if keyboard.just_pressed(KeyCode::A) {
+		let brush_molecule = editor.brush_molecule;
+		let available = &mut editor.level.available_molecules;
+		match available.iter().position(|index| *index == brush_molecule) {
+			Some(position) => {available.remove(position);},
+			None => {available.push(brush_molecule); available.sort();},
+		}
+		editor.unsaved = true;
+	}
+	if keyboard.just_pressed(KeyCode::Escape) {
+		if editor.unsaved && !editor.confirm_exit {
+			editor.confirm_exit = true;
+			editor.message = "Unsaved changes, press Esc again to leave without saving or Ctrl+S to save".to_string();
+		} else {
+			ev_w_fade_transition.send(FadeTransitionEvent(GameState::Lab));
+		}
+	}
+	// Only goals counting a single molecule are changed here,
+	// compound goals are written in the level file
+	let (quantity, index) = match editor.level.goal {
+		WinCondition::GreaterThan(quantity, index) => (quantity, index),
+		WinCondition::LessThan(quantity, index) => (quantity, index),
+		WinCondition::Exactly(quantity, index) => (quantity, index),
+		_ => {
+			let goal_keys = [KeyCode::G, KeyCode::H, KeyCode::Equals, KeyCode::NumpadAdd, KeyCode::Minus, KeyCode::NumpadSubtract];
+			if keyboard.any_just_pressed(goal_keys) {
+				editor.message = "Compound goals can only be changed in the level file".to_string();
+			}
+			return;
+		},
+	};
+	if keyboard.just_pressed(KeyCode::G) {
+		let goal = match editor.level.goal {
+			WinCondition::GreaterThan(..) => WinCondition::LessThan(quantity.max(1), index),
+			WinCondition::LessThan(..) => WinCondition::Exactly(quantity, index),
+			_ => WinCondition::GreaterThan(quantity, index),
+		};
+		editor.set_goal(goal, &registry);
+	}
+	let new_goal = |quantity: usize, index: usize| match editor.level.goal {
+		WinCondition::LessThan(..) => WinCondition::LessThan(quantity.max(1), index),
+		WinCondition::Exactly(..) => WinCondition::Exactly(quantity, index),
+		_ => WinCondition::GreaterThan(quantity, index),
+	};
+	let mut goal = None;
+	if keyboard.just_pressed(KeyCode::H) {
+		goal = Some(new_goal(quantity, editor.brush_molecule));
+	}
+	if keyboard.any_just_pressed([KeyCode::Equals, KeyCode::NumpadAdd]) {
+		goal = Some(new_goal(quantity + 1, index));
+	}
+	if keyboard.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+		goal = Some(new_goal(quantity.saturating_sub(1), index));
+	}
+	if let Some(goal) = goal {
+		editor.set_goal(goal, &registry);
+	}
+}
+
+// Writes the level to its file with Ctrl+S, and opens the previous
+// or next level with Page Up and Page Down. Saved levels replace the
+// loaded copy too, so they can be played straight away
+fn load_and_save_level(
+	keyboard: Res<Input<KeyCode>>,
+	handles: Res<LevelHandles>,
+	mut definitions: ResMut<Assets<LevelDefinition>>,
+	mut selected_level: ResMut<SelectedLevel>,
+	mut editor: ResMut<LevelEditor>,
+) {
+	let control = keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+	if control && keyboard.just_pressed(KeyCode::S) {
+		if let Err(error) = editor.check_layout() {
+			editor.message = format!("Not saved, {}", error);
+			return;
+		}
+		let text = match editor.level.to_ron() {
+			Ok(text) => text,
+			Err(error) => {
+				editor.message = format!("Not saved, {}", error);
+				return;
+			},
+		};
+		#[cfg(not(target_arch = "wasm32"))]
+		if let Err(error) = std::fs::write(format!("assets/{}", get_level_path(editor.level_id)), text) {
+			editor.message = format!("Not saved, {}", error);
+			return;
+		}
+		#[cfg(target_arch = "wasm32")]
+		let _ = text;
+		if let Some(definition) = definitions.get_mut(&handles.0[editor.level_id]) {
+			*definition = editor.level.clone();
+		}
+		editor.unsaved = false;
+		editor.message = format!("Saved {}", get_level_path(editor.level_id));
+	}
+
+	let level_id = if keyboard.just_pressed(KeyCode::PageUp) {
+		(editor.level_id + NUMBER_OF_LEVELS - 1) % NUMBER_OF_LEVELS
+	} else if keyboard.just_pressed(KeyCode::PageDown) {
+		(editor.level_id + 1) % NUMBER_OF_LEVELS
+	} else {
+		return;
+	};
+	if let Some(definition) = definitions.get(&handles.0[level_id]) {
+		let discarded = editor.unsaved;
+		*editor = LevelEditor{
+			tool: editor.tool,
+			brush_molecule: editor.brush_molecule,
+			connection_id: editor.connection_id,
+			..LevelEditor::new(level_id, definition.clone())
+		};
+		if discarded {
+			editor.message = "Unsaved changes were discarded".to_string();
+		}
+		selected_level.0 = level_id;
+	}
+}
+
+// Draws the level being edited with gizmos, so edits
+// show up without spawning or updating any sprites
+fn draw_edited_level(
+	mut gizmos: Gizmos,
+	editor: Res<LevelEditor>,
+	registry: Res<MoleculeRegistry>,
+	selected_palette: Res<SelectedPalette>,
+) {
+	let selected_color = Color::WHITE;
+	for (reactor_id, reactor) in editor.level.reactors.iter().enumerate() {
+		let color = if editor.selection == Some(EditorSelection::Reactor(reactor_id)) {
+			selected_color
+		} else if reactor.product_chamber {
+			get_reactor_color(1)
+		} else if reactor.input_chamber {
+			get_reactor_color(0)
+		} else {
+			get_reactor_color(3)
+		};
+		match reactor.reactor_type {
+			ReactorType::Rectangle{origin, dimensions} => {
+				gizmos.rect_2d(origin, 0.0, Vec2::new(dimensions.width, dimensions.height), color);
+			},
+			ReactorType::Circle{origin, radius} => {
+				gizmos.circle_2d(origin, radius, color).segments(128);
+			},
+		}
+		// Intakes point out of the reactor, outlets point into it
+		for (i, connection) in reactor.connections.iter().enumerate() {
+			let location = reactor.reactor_type.wall_point(connection.direction);
+			let outwards = (location - reactor.reactor_type.origin()).normalize_or_zero();
+			let color = if editor.selection == Some(EditorSelection::Connection(reactor_id, i)) {selected_color} else {registry.color(connection.connection_id, selected_palette.0)};
+			let arrow = (if connection.intake {outwards} else {-outwards}) * EDITOR_CONNECTION_RADIUS * 2.0;
+			gizmos.circle_2d(location, EDITOR_CONNECTION_RADIUS, color);
+			gizmos.line_2d(location, location + arrow, color);
+			for index in connection.filter.iter() {
+				gizmos.circle_2d(location + arrow, registry.radius(*index), registry.color(*index, selected_palette.0));
+			}
+		}
+		for (i, molecule) in reactor.molecules.iter().enumerate() {
+			let color = if editor.selection == Some(EditorSelection::Molecule(reactor_id, i)) {selected_color} else {registry.color(molecule.index, selected_palette.0)};
+			gizmos.circle_2d(molecule.location, registry.radius(molecule.index), color);
+			gizmos.line_2d(molecule.location, molecule.location + molecule.velocity / EDITOR_VELOCITY_SCALE, color);
+		}
+	}
+	// Shows the velocity a molecule will be placed with
+	if let (EditorTool::Molecule, Some(start), Some(cursor)) = (editor.tool, editor.drag_start, editor.cursor) {
+		let color = registry.color(editor.brush_molecule, selected_palette.0);
+		gizmos.circle_2d(start, registry.radius(editor.brush_molecule), color);
+		gizmos.line_2d(start, cursor, color);
+	}
+}
+
+fn update_editor_text(
+	editor: Res<LevelEditor>,
+	registry: Res<MoleculeRegistry>,
+	mut editor_text_query: Query<&mut Text, With<EditorText>>,
+) {
+	let name = |index: usize| registry.get(index).name.clone();
+	let selection = match editor.selection {
+		Some(EditorSelection::Reactor(reactor_id)) => {
+			let reactor = &editor.level.reactors[reactor_id];
+			let size = match reactor.reactor_type {
+				ReactorType::Rectangle{dimensions, ..} => format!("{} x {}", dimensions.width, dimensions.height),
+				ReactorType::Circle{radius, ..} => format!("radius {}", radius),
+			};
+			format!("Reactor {}, {}{}{}", reactor_id, size,
+				if reactor.input_chamber {", input"} else {""},
+				if reactor.product_chamber {", product"} else {""})
+		},
+		Some(EditorSelection::Connection(reactor_id, i)) => {
+			let connection = &editor.level.reactors[reactor_id].connections[i];
+			if connection.intake {
+				format!("Intake {} on reactor {}, accepts {}", connection.connection_id, reactor_id,
+					connection.filter.iter().map(|index| name(*index)).collect::<Vec<_>>().join(", "))
+			} else {
+				format!("Outlet {} on reactor {}", connection.connection_id, reactor_id)
+			}
+		},
+		Some(EditorSelection::Molecule(reactor_id, i)) => {
+			let molecule = &editor.level.reactors[reactor_id].molecules[i];
+			format!("{} in reactor {}, moving at {:.0}", name(molecule.index), reactor_id, molecule.velocity.length())
+		},
+		None => "Nothing".to_string(),
+	};
+	for mut text in editor_text_query.iter_mut() {
+		text.sections[0].value = format!(
+			"LEVEL EDITOR\nLevel {}{}\n\nTool: {:?}\nBrush: {}\nConnection: {}\n\nSelected: {}\n\nGoal: {}\nAvailable: {}\n\n{}",
+			editor.level_id,
+			if editor.unsaved {" (unsaved)"} else {""},
+			editor.tool,
+			name(editor.brush_molecule),
+			editor.connection_id,
+			selection,
+			editor.level.goal_text,
+			editor.level.available_molecules.iter().map(|index| name(*index)).collect::<Vec<_>>().join(", "),
+			editor.message,
+		);
+	}
+}
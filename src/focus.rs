@@ -0,0 +1,299 @@
+// Import Bevy game engine essentials
+use bevy::prelude::*;
+// Import Kira audio for Bevy to handle loading sound files
+use bevy_kira_audio::{Audio, AudioControl};
+// Import components, resources, and events
+use crate::{buttons::get_lab_button_shapes, components::*};
+
+// Plugin for moving focus between buttons and pressing
+// them with the keyboard or a gamepad
+pub struct FocusPlugin;
+
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        app
+			.insert_resource(ButtonFocus::default())
+			.add_systems(Startup, (
+				spawn_focus_outline,
+			))
+			.add_systems(Update, (
+				navigate_with_keyboard.run_if(menus_in_use),
+				clear_focus_on_mouse,
+				move_focus.after(clear_focus_on_mouse).after(navigate_with_keyboard),
+				show_focus_outline.after(move_focus),
+			))
+		;
+	}
+}
+
+// Buttons are moved between without the mouse everywhere but the
+// reactor and editor, where the same keys and sticks control the
+// level until it is paused
+pub fn menus_in_use(
+	game_state: Res<State<GameState>>,
+	pause_state: Res<State<PauseState>>,
+) -> bool {
+	*pause_state.get() == PauseState::Paused || !matches!(game_state.get(), GameState::Reactor | GameState::Editor)
+}
+
+// A button that can be focused, and where it is drawn
+struct FocusTarget {
+	effect: ButtonEffect,
+	location: Vec3,
+	size: Vec2,
+}
+
+// The buttons that could be clicked right now, which are only
+// those on the popup while the game is paused
+fn get_focus_targets(
+	game_state: &GameState,
+	pause_state: &PauseState,
+	button_query: &Query<(&StandardButton, &ButtonEffect)>,
+) -> Vec<FocusTarget> {
+	let mut targets: Vec<FocusTarget> = Vec::new();
+	for (button, effect) in button_query.iter() {
+		if !button.enabled {
+			continue;
+		}
+		if *pause_state == PauseState::Paused && !matches!(effect, ButtonEffect::Popup(_)) {
+			continue;
+		}
+		targets.push(FocusTarget{
+			effect: *effect,
+			location: button.location,
+			size: Vec2::new(button.dimensions.width, button.dimensions.height),
+		});
+	}
+	// The lab has no buttons of its own, so the shapes clicked
+	// on are focused instead, once each
+	if *game_state == GameState::Lab && *pause_state == PauseState::Unpaused {
+		for (shape, effect) in get_lab_button_shapes() {
+			if targets.iter().any(|target| target.effect == effect) {
+				continue;
+			}
+			let min = shape.iter().fold(Vec2::MAX, |min, point| min.min(*point));
+			let max = shape.iter().fold(Vec2::MIN, |max, point| max.max(*point));
+			targets.push(FocusTarget{
+				effect,
+				location: ((min + max) / 2.0).extend(50.0),
+				size: max - min,
+			});
+		}
+	}
+	targets
+}
+
+// Picks the button focus moves to from the one at index current,
+// or the first button if nothing is focused yet
+fn get_next_focus(
+	current: Option<usize>,
+	input: FocusInput,
+	locations: &[Vec2],
+) -> Option<usize> {
+	// Buttons in reading order, top to bottom then left to right
+	let mut order: Vec<usize> = (0..locations.len()).collect();
+	order.sort_by(|a, b| locations[*b].y.total_cmp(&locations[*a].y)
+		.then(locations[*a].x.total_cmp(&locations[*b].x)));
+	let current = match current {
+		Some(current) => current,
+		None if input == FocusInput::Previous => return order.last().copied(),
+		None => return order.first().copied(),
+	};
+	let direction = match input {
+		FocusInput::Up => Vec2::Y,
+		FocusInput::Down => Vec2::NEG_Y,
+		FocusInput::Left => Vec2::NEG_X,
+		FocusInput::Right => Vec2::X,
+		FocusInput::Next | FocusInput::Previous => {
+			let position = order.iter().position(|i| *i == current)?;
+			let step = if input == FocusInput::Next {1} else {order.len() - 1};
+			return Some(order[(position + step) % order.len()]);
+		},
+		FocusInput::Activate | FocusInput::Back => return Some(current),
+	};
+	// The nearest button in the direction pressed, preferring ones
+	// in line with the current button over ones off to the side
+	(0..locations.len())
+		.filter(|i| *i != current)
+		.filter_map(|i| {
+			let offset = locations[i] - locations[current];
+			let along = offset.dot(direction);
+			(along > 1.0).then(|| (i, along + 2.0 * offset.perp_dot(direction).abs()))
+		})
+		.min_by(|a, b| a.1.total_cmp(&b.1))
+		.map(|(i, _)| i)
+		.or(Some(current))
+}
+
+// Spawns the four sides of the outline, hidden until
+// a button is focused
+fn spawn_focus_outline(
+	mut commands: Commands,
+) {
+	for side in 0..4 {
+		commands.spawn((SpriteBundle {
+				sprite: Sprite {
+					color: Color::WHITE,
+					..Default::default()
+				},
+				visibility: Visibility::Hidden,
+				..Default::default()
+			},
+			FocusOutline(side),
+			Name::new("Focus Outline")
+		));
+	}
+}
+
+// The arrow keys and tab move focus, enter presses the focused
+// button, and escape closes the popup. Keys being typed into a
+// profile name or bound to an action are left to those instead
+fn navigate_with_keyboard(
+	keyboard: Res<Input<KeyCode>>,
+	rebinding: Res<Rebinding>,
+	pending_profile_action: Res<PendingProfileAction>,
+	mut ev_w_focus: EventWriter<FocusEvent>,
+) {
+	if rebinding.0.is_some() || rebinding.is_changed()
+	|| matches!(*pending_profile_action, PendingProfileAction::Rename(_)) || pending_profile_action.is_changed() {
+		return;
+	}
+	let tab = if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {FocusInput::Previous} else {FocusInput::Next};
+	for (key, input) in [
+		(KeyCode::Up, FocusInput::Up),
+		(KeyCode::Down, FocusInput::Down),
+		(KeyCode::Left, FocusInput::Left),
+		(KeyCode::Right, FocusInput::Right),
+		(KeyCode::Tab, tab),
+		(KeyCode::Return, FocusInput::Activate),
+		(KeyCode::NumpadEnter, FocusInput::Activate),
+		(KeyCode::Escape, FocusInput::Back),
+	] {
+		if keyboard.just_pressed(key) {
+			ev_w_focus.send(FocusEvent(input));
+		}
+	}
+}
+
+// The mouse takes over as soon as it is moved
+fn clear_focus_on_mouse(
+	mut focus: ResMut<ButtonFocus>,
+	mut ev_r_cursor_moved: EventReader<CursorMoved>,
+) {
+	if ev_r_cursor_moved.iter().count() > 0 && focus.0.is_some() {
+		focus.0 = None;
+	}
+}
+
+// Moves focus between the buttons that can be pressed, and presses
+// the focused one the same way a click would. Back presses the
+// button that closes the popup, if there is one
+fn move_focus(
+	game_state: Res<State<GameState>>,
+	pause_state: Res<State<PauseState>>,
+	settings: Res<Settings>,
+	audio: Res<Audio>,
+	asset_server: Res<AssetServer>,
+	button_query: Query<(&StandardButton, &ButtonEffect)>,
+	mut focus: ResMut<ButtonFocus>,
+	mut ev_r_focus: EventReader<FocusEvent>,
+	mut ev_w_button_call: EventWriter<ButtonCall>,
+) {
+	let targets = get_focus_targets(game_state.get(), pause_state.get(), &button_query);
+	let locations: Vec<Vec2> = targets.iter().map(|target| target.location.truncate()).collect();
+	let mut current = targets.iter().position(|target| Some(target.effect) == focus.0);
+	for ev in ev_r_focus.iter() {
+		let pressed = match ev.0 {
+			FocusInput::Activate => current.map(|i| targets[i].effect),
+			FocusInput::Back => targets.iter()
+				.find(|target| matches!(target.effect, ButtonEffect::Popup(PopupButton::ExitPopup | PopupButton::OpenSettings)))
+				.map(|target| target.effect),
+			input => {
+				current = get_next_focus(current, input, &locations);
+				None
+			},
+		};
+		if let Some(effect) = pressed {
+			audio
+				.play(asset_server.load("audio/haptics/click.wav"))
+				.with_volume(settings.sfx_volume);
+			ev_w_button_call.send(ButtonCall(effect));
+		}
+	}
+	// Focus is dropped once its button is gone or disabled
+	let focused = current.map(|i| targets[i].effect);
+	if focus.0 != focused {
+		focus.0 = focused;
+	}
+}
+
+fn show_focus_outline(
+	game_state: Res<State<GameState>>,
+	pause_state: Res<State<PauseState>>,
+	focus: Res<ButtonFocus>,
+	button_query: Query<(&StandardButton, &ButtonEffect)>,
+	mut outline_query: Query<(&mut Transform, &mut Sprite, &mut Visibility, &FocusOutline)>,
+) {
+	let target = focus.0.and_then(|effect| get_focus_targets(game_state.get(), pause_state.get(), &button_query)
+		.into_iter()
+		.find(|target| target.effect == effect));
+	for (mut transform, mut sprite, mut visibility, side) in outline_query.iter_mut() {
+		match target {
+			Some(ref target) => {
+				let half = (target.size + FOCUS_OUTLINE_WIDTH) / 2.0;
+				let (offset, size) = match side.0 {
+					0 => (Vec2::new(0.0, half.y), Vec2::new(half.x * 2.0 + FOCUS_OUTLINE_WIDTH, FOCUS_OUTLINE_WIDTH)),
+					1 => (Vec2::new(0.0, -half.y), Vec2::new(half.x * 2.0 + FOCUS_OUTLINE_WIDTH, FOCUS_OUTLINE_WIDTH)),
+					2 => (Vec2::new(-half.x, 0.0), Vec2::new(FOCUS_OUTLINE_WIDTH, half.y * 2.0 + FOCUS_OUTLINE_WIDTH)),
+					_ => (Vec2::new(half.x, 0.0), Vec2::new(FOCUS_OUTLINE_WIDTH, half.y * 2.0 + FOCUS_OUTLINE_WIDTH)),
+				};
+				transform.translation = (target.location.truncate() + offset).extend(target.location.z + 5.0);
+				sprite.custom_size = Some(size);
+				*visibility = Visibility::Visible;
+			},
+			None => {
+				if *visibility != Visibility::Hidden {
+					*visibility = Visibility::Hidden;
+				}
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Two rows of two, then a wide button centered below
+	const LOCATIONS: [Vec2; 5] = [
+		Vec2::new(-100.0, 100.0),
+		Vec2::new(100.0, 100.0),
+		Vec2::new(-100.0, 0.0),
+		Vec2::new(100.0, 0.0),
+		Vec2::new(0.0, -100.0),
+	];
+
+	#[test]
+	fn focus_starts_at_the_first_button() {
+		assert_eq!(get_next_focus(None, FocusInput::Down, &LOCATIONS), Some(0));
+		assert_eq!(get_next_focus(None, FocusInput::Previous, &LOCATIONS), Some(4));
+		assert_eq!(get_next_focus(None, FocusInput::Next, &[]), None);
+	}
+
+	#[test]
+	fn focus_moves_to_the_nearest_button_in_line() {
+		assert_eq!(get_next_focus(Some(0), FocusInput::Right, &LOCATIONS), Some(1));
+		assert_eq!(get_next_focus(Some(1), FocusInput::Down, &LOCATIONS), Some(3));
+		assert_eq!(get_next_focus(Some(3), FocusInput::Down, &LOCATIONS), Some(4));
+		assert_eq!(get_next_focus(Some(4), FocusInput::Up, &LOCATIONS), Some(2));
+		// Nothing further in that direction keeps the focus where it is
+		assert_eq!(get_next_focus(Some(1), FocusInput::Right, &LOCATIONS), Some(1));
+	}
+
+	#[test]
+	fn focus_cycles_in_reading_order() {
+		assert_eq!(get_next_focus(Some(1), FocusInput::Next, &LOCATIONS), Some(2));
+		assert_eq!(get_next_focus(Some(4), FocusInput::Next, &LOCATIONS), Some(0));
+		assert_eq!(get_next_focus(Some(0), FocusInput::Previous, &LOCATIONS), Some(4));
+	}
+}
@@ -0,0 +1,185 @@
+// Import Bevy game engine essentials
+use bevy::prelude::*;
+// Import components, resources, and events
+use crate::{components::*, focus::menus_in_use, molecules::get_lever_height};
+
+// Plugin for the gamepad controls that are not actions,
+// choosing reactors, molecules and levers in the reactor
+// and moving between buttons everywhere else
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app
+			.add_systems(Update, (
+				navigate_with_gamepad.run_if(menus_in_use),
+			))
+			.add_systems(Update, (
+				pause_with_start,
+			).run_if(in_state(GameState::Reactor))
+			.run_if(not(in_state(PauseState::Paused)))
+			)
+			.add_systems(Update, (
+				cycle_with_bumpers,
+				move_levers_with_triggers,
+			).run_if(in_state(GameState::Reactor))
+			.run_if(not(in_state(PauseState::Paused)))
+			.run_if(not(resource_exists::<ReplayPlayback>()))
+			)
+		;
+	}
+}
+
+// The D-pad or left stick moves focus, south presses the focused
+// button, and east or start closes the popup
+fn navigate_with_gamepad(
+	time: Res<Time>,
+	gamepads: Res<Gamepads>,
+	gamepad_buttons: Res<Input<GamepadButton>>,
+	gamepad_axes: Res<Axis<GamepadAxis>>,
+	mut stick_cooldown: Local<f32>,
+	mut ev_w_focus: EventWriter<FocusEvent>,
+) {
+	for gamepad in gamepads.iter() {
+		for (button_type, input) in [
+			(GamepadButtonType::DPadUp, FocusInput::Up),
+			(GamepadButtonType::DPadDown, FocusInput::Down),
+			(GamepadButtonType::DPadLeft, FocusInput::Left),
+			(GamepadButtonType::DPadRight, FocusInput::Right),
+			(GamepadButtonType::South, FocusInput::Activate),
+			(GamepadButtonType::East, FocusInput::Back),
+			(GamepadButtonType::Start, FocusInput::Back),
+		] {
+			if gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type)) {
+				ev_w_focus.send(FocusEvent(input));
+			}
+		}
+	}
+	// Holding the stick over keeps moving focus after a delay
+	let stick = gamepads.iter()
+		.map(|gamepad| Vec2::new(
+			gamepad_axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX)).unwrap_or(0.0),
+			gamepad_axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY)).unwrap_or(0.0),
+		))
+		.find(|stick| stick.length() > 0.5);
+	match stick {
+		Some(stick) => {
+			*stick_cooldown -= time.delta_seconds();
+			if *stick_cooldown <= 0.0 {
+				*stick_cooldown = GAMEPAD_REPEAT_DELAY;
+				let input = if stick.x.abs() > stick.y.abs() {
+					if stick.x > 0.0 {FocusInput::Right} else {FocusInput::Left}
+				} else if stick.y > 0.0 {
+					FocusInput::Up
+				} else {
+					FocusInput::Down
+				};
+				ev_w_focus.send(FocusEvent(input));
+			}
+		},
+		None => *stick_cooldown = 0.0,
+	}
+}
+
+fn pause_with_start(
+	gamepads: Res<Gamepads>,
+	gamepad_buttons: Res<Input<GamepadButton>>,
+	mut ev_w_button_call: EventWriter<ButtonCall>,
+) {
+	if gamepads.iter().any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Start))) {
+		ev_w_button_call.send(ButtonCall(ButtonEffect::Reactor(ReactorButton::PauseLevel)));
+	}
+}
+
+// The left bumper selects the next reactor, and the right bumper
+// the next molecule that can be launched
+fn cycle_with_bumpers(
+	gamepads: Res<Gamepads>,
+	gamepad_buttons: Res<Input<GamepadButton>>,
+	selected_molecule_type: Res<SelectedMoleculeType>,
+	button_query: Query<(&StandardButton, &ButtonEffect)>,
+	reactor_query: Query<(Entity, &ReactorInfo, &ReactorCondition, Option<&SelectedReactor>)>,
+	mut lever_query: Query<(&mut Transform, &LeverInfo)>,
+	mut input_queue: ResMut<InputQueue>,
+	mut commands: Commands,
+	mut ev_w_button_call: EventWriter<ButtonCall>,
+) {
+	let just_pressed = |button_type| gamepads.iter()
+		.any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type)));
+	if just_pressed(GamepadButtonType::LeftTrigger) {
+		let mut reactors: Vec<_> = reactor_query.iter().collect();
+		reactors.sort_by_key(|(_, info, _, _)| info.reactor_id);
+		let next = match reactors.iter().position(|(_, _, _, selected)| selected.is_some()) {
+			Some(i) => (i + 1) % reactors.len(),
+			None => 0,
+		};
+		if let Some((entity, info, condition, _)) = reactors.get(next) {
+			for (selected_entity, _, _, selected) in reactors.iter() {
+				if selected.is_some() {
+					commands.entity(*selected_entity).remove::<SelectedReactor>();
+				}
+			}
+			for (mut transform, lever) in lever_query.iter_mut() {
+				transform.translation.y = get_lever_height(lever, condition);
+			}
+			commands.entity(*entity).insert(SelectedReactor);
+			input_queue.0.push(ReactorInput::SelectReactor(info.reactor_id));
+		}
+	}
+	if just_pressed(GamepadButtonType::RightTrigger) {
+		let mut molecules: Vec<usize> = button_query.iter()
+			.filter_map(|(button, effect)| match effect {
+				ButtonEffect::Reactor(ReactorButton::SelectMolecule(index)) if button.enabled => Some(*index),
+				_ => None,
+			})
+			.collect();
+		molecules.sort();
+		let next = molecules.iter().find(|index| **index > selected_molecule_type.0).or(molecules.first());
+		if let Some(index) = next {
+			ev_w_button_call.send(ButtonCall(ButtonEffect::Reactor(ReactorButton::SelectMolecule(*index))));
+		}
+	}
+}
+
+// The left trigger raises the temperature lever of the selected
+// reactor and the right trigger its pressure lever, faster the
+// further they are pulled. Holding east lowers them instead
+fn move_levers_with_triggers(
+	time: Res<Time>,
+	gamepads: Res<Gamepads>,
+	gamepad_buttons: Res<Input<GamepadButton>>,
+	gamepad_triggers: Res<Axis<GamepadButton>>,
+	reactor_query: Query<(&ReactorInfo, &ReactorCondition), With<SelectedReactor>>,
+	mut lever_query: Query<(&mut Transform, &LeverInfo)>,
+	mut input_queue: ResMut<InputQueue>,
+) {
+	let pull = |button_type| gamepads.iter()
+		.filter_map(|gamepad| gamepad_triggers.get(GamepadButton::new(gamepad, button_type)))
+		.fold(0.0, f32::max);
+	let direction = if gamepads.iter().any(|gamepad| gamepad_buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::East))) {-1.0} else {1.0};
+	for (r_info, condition) in reactor_query.iter() {
+		let (mut temperature, mut pressure) = (condition.temperature, condition.pressure);
+		for (mut transform, info) in lever_query.iter_mut() {
+			let trigger = pull(if info.lever_type == 0 {GamepadButtonType::LeftTrigger2} else {GamepadButtonType::RightTrigger2});
+			if trigger > GAMEPAD_DEADZONE {
+				// Levers move on from where they are drawn, as the conditions
+				// only change once the simulation next steps
+				let percent = ((transform.translation.y - info.min_height) / (info.max_height - info.min_height)
+					+ direction * trigger * GAMEPAD_LEVER_SPEED * time.delta_seconds()).clamp(0.0, 1.0);
+				transform.translation.y = info.min_height + percent * (info.max_height - info.min_height);
+				if info.lever_type == 0 {
+					temperature = percent;
+				} else {
+					pressure = percent;
+				}
+			}
+		}
+		if temperature != condition.temperature || pressure != condition.pressure {
+			input_queue.0.push(ReactorInput::SetConditions{
+				reactor_id: r_info.reactor_id,
+				temperature,
+				pressure,
+			});
+		}
+	}
+}
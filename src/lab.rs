@@ -72,7 +72,7 @@ fn spawn_lab(
 				visibility: Visibility::Hidden,
 				..Default::default()
 			},
-			Logbook(i),
+			Logbook,
 			DespawnOnExitGameState,
 			Name::new(format!("Logbook Page {}", i))
 		));
@@ -80,10 +80,10 @@ fn spawn_lab(
 
 	// Name, Path, Z-Level, Alpha, Visibility, Interaction
 	let lab_sprite_info = [
-		("Monitor", "sprites/ui/monitor.png", 11.0, 1.0, Visibility::Hidden, ButtonEffect::CustomLabButton(CustomLabButton::MonitorActivate)),
-		("Logbook", "sprites/ui/logbook.png", 11.0, 1.0, Visibility::Hidden, ButtonEffect::CustomLabButton(CustomLabButton::LogbookOpen)),
-		("Exit", "sprites/ui/exit.png", 11.0, 1.0, Visibility::Hidden, ButtonEffect::CustomLabButton(CustomLabButton::ExitLab)),
-		//("Exit Glow", "sprites/ui/glow.png", 11.0, 0.05, Visibility::Hidden, ButtonEffect::CustomLabButton(CustomLabButton::ExitLab)),
+		("Monitor", "sprites/ui/monitor.png", 11.0, 1.0, Visibility::Hidden, ButtonEffect::CustomLab(CustomLabButton::MonitorActivate)),
+		("Logbook", "sprites/ui/logbook.png", 11.0, 1.0, Visibility::Hidden, ButtonEffect::CustomLab(CustomLabButton::LogbookOpen)),
+		("Exit", "sprites/ui/exit.png", 11.0, 1.0, Visibility::Hidden, ButtonEffect::CustomLab(CustomLabButton::ExitLab)),
+		//("Exit Glow", "sprites/ui/glow.png", 11.0, 0.05, Visibility::Hidden, ButtonEffect::CustomLab(CustomLabButton::ExitLab)),
 	];
 
 	for (name, path, z, a, visibility, interaction) in lab_sprite_info {
@@ -96,7 +96,7 @@ fn spawn_lab(
 					custom_size: Some(Vec2::new(ortho_size.width, ortho_size.height)),
 					..Default::default()
 				},
-				visibility: visibility,
+				visibility,
 				..Default::default()
 			},
 			DespawnOnExitGameState,
@@ -0,0 +1,135 @@
+// Import Bevy game engine essentials
+use bevy::{prelude::*, text::Text2dBounds};
+// Import components, resources, and events
+use crate::components::*;
+
+// Plugin for fitting the HUD, tooltip and text boxes to the
+// UI scale, and the text on screen to the text size
+pub struct LayoutPlugin;
+
+impl Plugin for LayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app
+			.add_systems(Update, (
+				rescale_text,
+				layout_scaled_boxes,
+			))
+		;
+	}
+}
+
+// The size a box is drawn at for the UI scale. Boxes grow
+// downwards or upwards, but only the tooltip grows wider,
+// as the HUD already fills the top of the reactor and the
+// cutscene text box sits beside the portrait
+pub fn get_scaled_box_size(
+	scaled_box: ScaledBox,
+	ui_scale: f32,
+) -> Vec2 {
+	let (width, height) = match scaled_box {
+		ScaledBox::Goal => (GOAL_BOX_WIDTH, GOAL_BOX_HEIGHT),
+		ScaledBox::Cost => (COST_BOX_WIDTH, COST_BOX_HEIGHT),
+		ScaledBox::Stopwatch => (STOPWATCH_BOX_WIDTH, STOPWATCH_BOX_HEIGHT),
+		ScaledBox::Tooltip => (TOOLTIP_WIDTH, TOOLTIP_HEIGHT),
+		ScaledBox::TextBox => (TEXT_BOX_WIDTH, TEXT_BOX_HEIGHT),
+	};
+	let width_scale = if scaled_box == ScaledBox::Tooltip {ui_scale} else {ui_scale.min(1.0)};
+	Vec2::new(width * width_scale, height * ui_scale)
+}
+
+// Where a box's text starts and the bounds it wraps within
+fn get_scaled_text_layout(
+	scaled_box: ScaledBox,
+	size: Vec2,
+) -> (Vec2, Vec2) {
+	match scaled_box {
+		ScaledBox::Goal => (Vec2::ZERO, size - GOAL_BOX_MARGINS * 2.0),
+		ScaledBox::Cost => (
+			Vec2::new(-size.x / 2.0 + COST_BOX_MARGINS, 0.0),
+			Vec2::new(size.x - COST_BOX_MARGINS * 2.0, size.y - COST_BOX_MARGINS),
+		),
+		ScaledBox::Stopwatch => (
+			Vec2::new(-size.x / 2.0 + STOPWATCH_BOX_MARGINS, 0.0),
+			Vec2::new(size.x - STOPWATCH_BOX_MARGINS * 2.0, size.y - STOPWATCH_BOX_MARGINS),
+		),
+		ScaledBox::Tooltip => (
+			Vec2::new(-size.x / 2.0 + TOOLTIP_MARGINS, size.y / 2.0 - TOOLTIP_MARGINS),
+			size - TOOLTIP_MARGINS * 2.0,
+		),
+		ScaledBox::TextBox => (
+			Vec2::new(-size.x / 2.0 + TEXT_BOX_MARGINS, size.y / 2.0 - TEXT_BOX_MARGINS),
+			size - TEXT_BOX_MARGINS * 2.0,
+		),
+	}
+}
+
+// Text is spawned at the text size through the text style
+// helpers, so only text already on screen needs resizing
+// when the setting changes. Glyphs keep to their molecules
+fn rescale_text(
+	settings: Res<Settings>,
+	mut text_scale: Local<Option<f32>>,
+	mut text_query: Query<&mut Text, Without<MoleculeGlyph>>,
+) {
+	let previous = *text_scale.get_or_insert(settings.text_scale);
+	if previous == settings.text_scale {
+		return;
+	}
+	*text_scale = Some(settings.text_scale);
+	for mut text in text_query.iter_mut() {
+		for section in text.sections.iter_mut() {
+			section.style.font_size *= settings.text_scale / previous;
+		}
+	}
+}
+
+// Sizes and places boxes as they are spawned and whenever the
+// UI scale changes, keeping the edge each is anchored by, and
+// reflows their text within the new bounds
+fn layout_scaled_boxes(
+	settings: Res<Settings>,
+	mut box_query: Query<(Ref<ScaledBox>, &mut Transform, &mut Sprite, &Children)>,
+	mut text_query: Query<(&mut Transform, &mut Text2dBounds), Without<ScaledBox>>,
+) {
+	for (scaled_box, mut transform, mut sprite, children) in box_query.iter_mut() {
+		if !settings.is_changed() && !scaled_box.is_added() {
+			continue;
+		}
+		let size = get_scaled_box_size(*scaled_box, settings.ui_scale);
+		if sprite.custom_size == Some(size) && !scaled_box.is_added() {
+			continue;
+		}
+		sprite.custom_size = Some(size);
+		let viewport_left = REACTOR_VIEWPORT_CENTER.x - REACTOR_VIEWPORT_WIDTH / 2.0;
+		let location = match *scaled_box {
+			ScaledBox::Goal => Some(Vec2::new(
+				viewport_left + size.x / 2.0,
+				GOAL_BOX_Y + GOAL_BOX_HEIGHT / 2.0 - size.y / 2.0,
+			)),
+			ScaledBox::Cost => Some(Vec2::new(
+				viewport_left + get_scaled_box_size(ScaledBox::Goal, settings.ui_scale).x + REACTION_UI_SPACING + size.x / 2.0,
+				COST_BOX_Y + COST_BOX_HEIGHT / 2.0 - size.y / 2.0,
+			)),
+			ScaledBox::Stopwatch => Some(Vec2::new(
+				REACTOR_VIEWPORT_CENTER.x + REACTOR_VIEWPORT_WIDTH / 2.0 - size.x / 2.0,
+				STOPWATCH_BOX_Y + STOPWATCH_BOX_HEIGHT / 2.0 - size.y / 2.0,
+			)),
+			ScaledBox::TextBox => Some(Vec2::new(
+				TEXT_BOX_X - TEXT_BOX_WIDTH / 2.0 + size.x / 2.0,
+				TEXT_BOX_Y - TEXT_BOX_HEIGHT / 2.0 + size.y / 2.0,
+			)),
+			// The tooltip follows the cursor instead
+			ScaledBox::Tooltip => None,
+		};
+		if let Some(location) = location {
+			transform.translation = location.extend(transform.translation.z);
+		}
+		let (offset, bounds) = get_scaled_text_layout(*scaled_box, size);
+		for child in children.iter() {
+			if let Ok((mut text_transform, mut text_bounds)) = text_query.get_mut(*child) {
+				text_transform.translation = offset.extend(text_transform.translation.z);
+				text_bounds.size = bounds;
+			}
+		}
+	}
+}
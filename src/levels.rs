@@ -0,0 +1,102 @@
+// Import Bevy game engine essentials
+use bevy::{prelude::*, asset::{AssetLoader, LoadContext, LoadedAsset, LoadState}, ecs::system::SystemParam, utils::BoxedFuture};
+// Import components, resources, and events
+use crate::components::*;
+
+// Plugin for loading level definitions from the
+// assets/levels folder through the asset server
+pub struct LevelsPlugin;
+
+impl Plugin for LevelsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+			.add_asset::<LevelDefinition>()
+			.init_asset_loader::<LevelDefinitionLoader>()
+			.add_systems(Startup, (
+				load_levels,
+			))
+		;
+	}
+}
+
+// Reads level files written in RON, for example
+// assets/levels/level_00.level.ron
+#[derive(Default)]
+pub struct LevelDefinitionLoader;
+
+impl AssetLoader for LevelDefinitionLoader {
+	fn load<'a>(
+		&'a self,
+		bytes: &'a [u8],
+		load_context: &'a mut LoadContext,
+	) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+		Box::pin(async move {
+			let level = LevelDefinition::from_ron(bytes)
+				.map_err(|error| bevy::asset::Error::msg(format!("{}: {}", load_context.path().display(), error)))?;
+			load_context.set_default_asset(LoadedAsset::new(level));
+			Ok(())
+		})
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["level.ron"]
+	}
+}
+
+// Starts loading every level file, the splash screen waits
+// for these to finish before moving on to the menu
+fn load_levels(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+) {
+	let mut handles = Vec::new();
+	for level in 0..NUMBER_OF_LEVELS {
+		handles.push(asset_server.load(get_level_path(level)));
+	}
+	commands.insert_resource(LevelHandles(handles));
+}
+
+// Gives systems access to the definition of any level
+// without having to look up the handles themselves
+#[derive(SystemParam)]
+pub struct Levels<'w> {
+	asset_server: Res<'w, AssetServer>,
+	handles: Res<'w, LevelHandles>,
+	definitions: Res<'w, Assets<LevelDefinition>>,
+}
+
+impl<'w> Levels<'w> {
+	// The definition of the selected level, which can only be
+	// selected once its file has loaded
+	pub fn get(
+		&self,
+		level: usize,
+	) -> &LevelDefinition {
+		self.try_get(level)
+			.unwrap_or_else(|| panic!("Level file {} was not loaded", get_level_path(level)))
+	}
+
+	// None if the level's file is missing or failed to load
+	pub fn try_get(
+		&self,
+		level: usize,
+	) -> Option<&LevelDefinition> {
+		self.handles.0.get(level).and_then(|handle| self.definitions.get(handle))
+	}
+
+	pub fn is_loaded(
+		&self,
+		level: usize,
+	) -> bool {
+		self.try_get(level).is_some()
+	}
+
+	// True once every level has either loaded or failed, failures
+	// are reported by the asset server
+	pub fn finished_loading(&self) -> bool {
+		!matches!(
+			self.asset_server.get_group_load_state(self.handles.0.iter().map(|handle| handle.id())),
+			LoadState::NotLoaded | LoadState::Loading,
+		)
+	}
+}
@@ -0,0 +1,107 @@
+// Import Bevy game engine essentials
+use bevy::{prelude::*, asset::{AssetLoader, LoadContext, LoadedAsset}, utils::{BoxedFuture, HashMap}};
+// Import components, resources, and events
+use crate::components::*;
+
+// Plugin for loading the string table of each language and
+// looking up the text shown in the selected language
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app
+			.add_asset::<StringTable>()
+			.init_asset_loader::<StringTableLoader>()
+			.init_resource::<Locale>()
+			.add_systems(Startup, (
+				load_string_tables,
+			))
+			.add_systems(Update, (
+				update_locale,
+				update_localized_text.after(update_locale),
+			))
+		;
+	}
+}
+
+// Reads a string table written in RON, found at
+// assets/locale/<language>.strings.ron
+#[derive(Default)]
+pub struct StringTableLoader;
+
+impl AssetLoader for StringTableLoader {
+	fn load<'a>(
+		&'a self,
+		bytes: &'a [u8],
+		load_context: &'a mut LoadContext,
+	) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+		Box::pin(async move {
+			let table = StringTable::from_ron(bytes)
+				.map_err(|error| bevy::asset::Error::msg(format!("{}: {}", load_context.path().display(), error)))?;
+			load_context.set_default_asset(LoadedAsset::new(table));
+			Ok(())
+		})
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["strings.ron"]
+	}
+}
+
+// Every language is loaded up front, so switching
+// between them in the settings is instant
+fn load_string_tables(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+) {
+	commands.insert_resource(StringTableHandles(LANGUAGES.iter()
+		.map(|(language, _)| asset_server.load(get_string_table_path(language)))
+		.collect()
+	));
+}
+
+// Rebuilds the text from the fallback and selected tables once
+// they are loaded, whenever one is hot reloaded, and whenever a
+// different language is picked in the settings
+fn update_locale(
+	handles: Res<StringTableHandles>,
+	tables: Res<Assets<StringTable>>,
+	settings: Res<Settings>,
+	mut locale: ResMut<Locale>,
+	mut ev_r_asset: EventReader<AssetEvent<StringTable>>,
+) {
+	let reloaded = ev_r_asset.iter().any(|ev| matches!(ev, AssetEvent::Created { .. } | AssetEvent::Modified { .. }));
+	if !reloaded && locale.language == settings.language {
+		return;
+	}
+	let table = |language: &str| LANGUAGES.iter()
+		.position(|(code, _)| *code == language)
+		.and_then(|i| tables.get(&handles.0[i]));
+	let Some(fallback) = table(FALLBACK_LANGUAGE) else {
+		return;
+	};
+	let mut strings: HashMap<String, String> = fallback.strings.clone().into_iter().collect();
+	if let Some(selected) = table(&settings.language) {
+		strings.extend(selected.strings.clone());
+	}
+	*locale = Locale{
+		language: settings.language.clone(),
+		strings,
+	};
+}
+
+// Text spawned before the string tables have loaded is
+// left blank until they are
+fn update_localized_text(
+	locale: Res<Locale>,
+	mut text_query: Query<(&mut Text, Ref<LocalizedText>)>,
+) {
+	if !locale.is_loaded() {
+		return;
+	}
+	for (mut text, localized) in text_query.iter_mut() {
+		if locale.is_changed() || localized.is_added() {
+			text.sections[0].value = locale.get(localized.0);
+		}
+	}
+}
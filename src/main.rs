@@ -1,26 +1,38 @@
+// Bevy systems routinely take many parameters and nested query types
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
 // Import Bevy game engine essentials
-use bevy::{prelude::*, asset::ChangeWatcher};
-// Import duration for the asset hot reloading change watcher
-use std::time::Duration;
+use bevy::prelude::*;
 
 // MODULES
 mod audio;
 mod buttons;
 mod camera;
 mod components;
+mod controls;
 mod cutscene;
+mod focus;
+mod gamepad;
 mod lab;
+mod layout;
+mod levels;
+mod locale;
 mod menu;
 mod molecules;
 mod particles;
 mod popup;
 mod reactor;
+mod registry;
+mod replay;
+mod save;
 mod setup;
 mod states;
 
 // Only include in debug builds
 #[cfg(debug_assertions)]
 mod debug;
+#[cfg(debug_assertions)]
+mod editor;
 
 // Can't forget main!
 fn main() {
@@ -44,7 +56,7 @@ fn main() {
 			})
 			.set(AssetPlugin {
 				// Enables asset hot reloading
-				//watch_for_changes: ChangeWatcher::with_delay(Duration::from_millis(200)),
+				//watch_for_changes: bevy::asset::ChangeWatcher::with_delay(std::time::Duration::from_millis(200)),
 				..Default::default()
 			})
 			// Prevents pixel art sprites from becoming blurry
@@ -65,21 +77,39 @@ fn main() {
 			cutscene::CutscenePlugin,
 			// Spawns sprites for lab which acts as a hub menu
 			lab::LabPlugin,
+			// Loads level definitions from the assets folder
+			levels::LevelsPlugin,
 			// Spawns title and menu buttons
 			menu::MenuPlugin,
-			// Molecule spawning and collision logic
+			// Molecule launching and sprites
 			molecules::MoleculesPlugin,
 			// Spawn and fade particle trails
 			particles::ParticlesPlugin,
 			// Spawns popup menus and buttons such as level select
 			popup::PopupPlugin,
-			// Reactor sprite spawning and logic
+			// Reactor sprites and controls
 			reactor::ReactorPlugin,
+			// Loads the molecule catalogue and reaction table from the assets folder
+			registry::RegistryPlugin,
 			// Camera spawn, save file loading, and resource initialization
 			setup::SetupPlugin,
 			// Handles screen transistion events
 			states::StatesPlugin,
 		))
+		// Headless molecule simulation shared with the level tooling
+		.add_plugins(mole_sim::SimPlugin)
+		// Saves the inputs of completed levels and plays them back
+		.add_plugins(replay::ReplayPlugin)
+		// Reads input as rebindable actions
+		.add_plugins(controls::ControlsPlugin)
+		// Moves between and presses buttons without the mouse
+		.add_plugins(focus::FocusPlugin)
+		// Gamepad controls for reactors, levers and menus
+		.add_plugins(gamepad::GamepadPlugin)
+		// Sizes the HUD and text to the UI scale and text size
+		.add_plugins(layout::LayoutPlugin)
+		// Loads the string tables and shows text in the selected language
+		.add_plugins(locale::LocalePlugin)
 		;
 
 	{
@@ -88,6 +118,8 @@ fn main() {
 		app
 			// Debug module for dev tools
 			.add_plugins(debug::DebugPlugin)
+			// Level editor for laying out reactors
+			.add_plugins(editor::EditorPlugin)
 		;
 	}
 
@@ -1,7 +1,7 @@
 // Import Bevy game engine essentials
 use bevy::prelude::*;
 // Import components, resources, and events
-use crate::components::*;
+use crate::{components::*, save::get_display_name};
 
 // Plugin for generating the main menu
 pub struct MenuPlugin;
@@ -12,6 +12,10 @@ impl Plugin for MenuPlugin {
 			.add_systems( OnEnter(GameState::Menu), (
 				spawn_menu,
 			))
+			.add_systems(Update, (
+				type_profile_name,
+				update_profile_text.after(type_profile_name),
+			).run_if(in_state(GameState::Menu)))
 		;
 	}
 }
@@ -21,6 +25,8 @@ impl Plugin for MenuPlugin {
 fn spawn_menu(
 	mut commands: Commands,
 	asset_server: Res<AssetServer>,
+	settings: Res<Settings>,
+	locale: Res<Locale>,
 	ortho_size: Res<OrthoSize>,
 ) {
 	// Menu Background
@@ -51,20 +57,34 @@ fn spawn_menu(
 		Name::new("Title Logo")
 	));
 
+	// Name of the profile being played
+	commands.spawn((Text2dBundle {
+			transform: Transform::from_xyz(0.0, -145.0, 100.0),
+			text: Text::from_section("", get_settings_text_style(&asset_server, &settings))
+				.with_alignment(TextAlignment::Center),
+			..Default::default()
+		},
+		ProfileNameText,
+		DespawnOnExitGameState,
+		Name::new("Profile Name Text")
+	));
+
 	// Buttons
 	let effect = [
-		ButtonEffect::MenuButton(MenuButton::StartGame),
-		ButtonEffect::MenuButton(MenuButton::Settings),
-		ButtonEffect::MenuButton(MenuButton::ExitGame),
+		ButtonEffect::Menu(MenuButton::StartGame),
+		ButtonEffect::Menu(MenuButton::Profiles),
+		ButtonEffect::Menu(MenuButton::Settings),
+		ButtonEffect::Menu(MenuButton::ExitGame),
 	];
 	// Button text
 	let text = [
-		"Play".to_string(), 
-		"Settings".to_string(), 
-		"Quit".to_string() 
+		"menu.play",
+		"menu.profiles",
+		"menu.settings",
+		"menu.quit",
 	];
 
-	for i in 0..3 {
+	for i in 0..4 {
 		let button = StandardButton {
 			location: Vec3::new(0.0, -200.0 -45.0 * i as f32, 100.0),
 			dimensions: Dimensions {
@@ -93,13 +113,93 @@ fn spawn_menu(
 			parent
 				.spawn((Text2dBundle {
 					transform: Transform::from_xyz(0.0, -2.5, 10.0,),
-					text: Text::from_section(format!("{}", text[i]), get_button_text_style(&asset_server))
+					text: Text::from_section(locale.get(text[i]), get_button_text_style(&asset_server, &settings))
 						.with_alignment(TextAlignment::Center),
 					..Default::default()
 				},
+				LocalizedText(text[i]),
 				Name::new("Menu Button Text")
 			));
 		});
 	}
 
-}
\ No newline at end of file
+}
+
+// While a profile is being renamed, typed characters are added to
+// its name, and Enter or Escape finishes renaming it
+fn type_profile_name(
+	mut profiles: ResMut<Profiles>,
+	mut pending_profile_action: ResMut<PendingProfileAction>,
+	mut ev_r_received_character: EventReader<ReceivedCharacter>,
+	keyboard: Res<Input<KeyCode>>,
+) {
+	if let PendingProfileAction::Rename(slot) = *pending_profile_action {
+		let mut renamed = profiles.clone();
+		for ev in ev_r_received_character.iter() {
+			renamed.type_name(slot, ev.char);
+		}
+		if keyboard.just_pressed(KeyCode::Back) {
+			renamed.erase_name(slot);
+		}
+		if keyboard.any_just_pressed([KeyCode::Return, KeyCode::Escape]) {
+			renamed.finish_name(slot);
+			*pending_profile_action = PendingProfileAction::None;
+		}
+		// Only a changed name marks the profiles to be saved
+		profiles.set_if_neq(renamed);
+	} else {
+		ev_r_received_character.clear();
+	}
+}
+
+// Keeps the profile names, the delete confirmation, and which
+// profile buttons can be pressed in step with the profiles, only
+// refreshing them when something they show has changed
+fn update_profile_text(
+	profiles: Res<Profiles>,
+	selected_profile: Res<SelectedProfile>,
+	pending_profile_action: Res<PendingProfileAction>,
+	locale: Res<Locale>,
+	added_query: Query<(), Or<(Added<ProfileNameText>, Added<ProfileSlotText>, Added<ProfileDeleteText>)>>,
+	mut name_text_query: Query<&mut Text, With<ProfileNameText>>,
+	mut slot_text_query: Query<(&mut Text, &ProfileSlotText), Without<ProfileNameText>>,
+	mut delete_text_query: Query<(&mut Text, &ProfileDeleteText), (Without<ProfileNameText>, Without<ProfileSlotText>)>,
+	mut button_query: Query<(&mut StandardButton, &ButtonEffect)>,
+) {
+	if !profiles.is_changed() && !selected_profile.is_changed() && !pending_profile_action.is_changed()
+	&& !locale.is_changed() && added_query.is_empty() {
+		return;
+	}
+	for mut text in name_text_query.iter_mut() {
+		text.sections[0].value = locale.format("menu.profile", &[profiles.display_name(selected_profile.0, &locale).unwrap_or_default()]);
+	}
+	for (mut text, slot) in slot_text_query.iter_mut() {
+		text.sections[0].value = match profiles.name(slot.0) {
+			Some(name) if *pending_profile_action == PendingProfileAction::Rename(slot.0) => format!("{}_", name),
+			Some(_) if *pending_profile_action == PendingProfileAction::StartOver(slot.0) => locale.get("profiles.start_over"),
+			Some(name) => get_display_name(name, slot.0, &locale),
+			None => locale.get("profiles.new"),
+		};
+	}
+	for (mut text, slot) in delete_text_query.iter_mut() {
+		text.sections[0].value = if *pending_profile_action == PendingProfileAction::Delete(slot.0) {
+			locale.get("profiles.confirm")
+		} else {
+			locale.get("profiles.delete")
+		};
+	}
+	for (mut button, effect) in button_query.iter_mut() {
+		match effect {
+			ButtonEffect::Popup(PopupButton::RenameProfile(slot)) => {
+				button.enabled = profiles.name(*slot).is_some();
+			},
+			ButtonEffect::Popup(PopupButton::CopyProfile(slot)) => {
+				button.enabled = profiles.name(*slot).is_some() && profiles.first_empty().is_some();
+			},
+			ButtonEffect::Popup(PopupButton::DeleteProfile(slot)) => {
+				button.enabled = profiles.name(*slot).is_some() && profiles.count() > 1;
+			},
+			_ => (),
+		}
+	}
+}
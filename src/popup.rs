@@ -2,7 +2,7 @@
 use bevy::prelude::*;
 use bevy_pkv::PkvStore;
 // Import components, resources, and events
-use crate::components::*;
+use crate::{components::*, levels::Levels, replay::load_replay, save::get_profile_key};
 
 // Plugin for generating popup visuals and 
 // menus which do not change the GameState
@@ -15,6 +15,8 @@ impl Plugin for PopupPlugin {
 				spawn_popup,
 				expand_popup,
 				spawn_popup_buttons.run_if(in_state(PauseState::Paused)),
+				update_save_transfer_text.run_if(in_state(PauseState::Paused)),
+				update_palette_preview.run_if(in_state(PauseState::Paused)),
 			))
 		;
 	}
@@ -76,12 +78,18 @@ fn expand_popup(
 // Spawns all the buttons for popups once they are
 // fully scaled
 fn spawn_popup_buttons(
-	popup_query: Query<(&PopupInfo)>,
+	popup_query: Query<&PopupInfo>,
 	asset_server: Res<AssetServer>,
+	registry: Res<MoleculeRegistry>,
 	selected_palette: Res<SelectedPalette>,
 	selected_level: Res<SelectedLevel>,
+	selected_profile: Res<SelectedProfile>,
+	levels: Levels,
 	pkv: Res<PkvStore>,
 	ortho_size: Res<OrthoSize>,
+	settings: Res<Settings>,
+	locale: Res<Locale>,
+	mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 	mut commands: Commands,
 	mut ev_r_popup_complete: EventReader<PopupCompleteEvent>,
 ) {
@@ -90,48 +98,69 @@ fn spawn_popup_buttons(
 			match info.popup_type {
 				PopupType::Settings => {
 					commands.spawn((Text2dBundle{
-						transform: Transform::from_xyz(0.0, 300.0, 810.0),
-						text: Text::from_section(format!("Settings"), get_title_text_style(&asset_server)),
-						..Default::default()
-						},
-						DespawnOnExitPauseState,
-						Name::new("Settings Text")
-					));
-					commands.spawn((Text2dBundle{
-						transform: Transform::from_xyz(-25.0, 0.0, 810.0),
-						text: Text::from_section(format!("BGM Volume:\n\nSFX Volume:\n\nToggle Palette:\n\nParticle Trails:\n\n Fullscreen Mode:"), get_settings_text_style(&asset_server))
-							.with_alignment(TextAlignment::Right),
-						text_anchor: bevy::sprite::Anchor::CenterRight,
+						transform: Transform::from_xyz(0.0, 340.0, 810.0),
+						text: Text::from_section(locale.get("settings.title"), get_title_text_style(&asset_server, &settings)),
 						..Default::default()
 						},
 						DespawnOnExitPauseState,
 						Name::new("Settings Text")
 					));
+					// Each label is placed on its own row, so the rows
+					// stay lined up with their buttons at any text size
+					for (y, label) in [
+						(260.0, "settings.bgm_volume"),
+						(205.0, "settings.sfx_volume"),
+						(150.0, "settings.palette"),
+						(95.0, "settings.glyphs"),
+						(40.0, "settings.particle_trails"),
+						(-15.0, "settings.fullscreen"),
+						(-70.0, "settings.text_size"),
+						(-125.0, "settings.ui_scale"),
+						(-180.0, "settings.language"),
+					] {
+						commands.spawn((Text2dBundle{
+							transform: Transform::from_xyz(-25.0, y, 810.0),
+							text: Text::from_section(locale.get(label), get_settings_text_style(&asset_server, &settings))
+								.with_alignment(TextAlignment::Right),
+							text_anchor: bevy::sprite::Anchor::CenterRight,
+							..Default::default()
+							},
+							DespawnOnExitPauseState,
+							Name::new("Settings Text")
+						));
+					}
 
 					let mut buttons  = Vec::new();
-					buttons.push((StandardButton {
-						location: Vec3::new(0.0, -310.0, 810.0),
-						dimensions: Dimensions {
-							width: 400.0,
-							height: 40.0,
-						},
-						enabled: true,
-						idle_color: Color::hex("EDD6AD").unwrap(),
-						hovered_color: Color::hex("CDB68D").unwrap(),
-						disabled_color: Color::hex("9D865D").unwrap(),
-					}, ButtonEffect::PopupButton(PopupButton::ExitPopup)));
-					commands.spawn((Text2dBundle {
-							transform: Transform::from_xyz(0.0, -312.5, 820.0),
-							text: Text::from_section(format!("Back"), get_button_text_style(&asset_server))
-								.with_alignment(TextAlignment::Center),
-							..Default::default()
-						},
-						DespawnOnExitPauseState,
-						Name::new("Settings Quit Button")
-					));
+					for (x, label, effect) in [
+						(-540.0, "settings.controls", PopupButton::OpenControls),
+						(-180.0, "settings.export_save", PopupButton::ExportSave),
+						(180.0, "settings.import_save", PopupButton::ImportSave),
+						(540.0, "button.back", PopupButton::ExitPopup),
+					] {
+						buttons.push((StandardButton {
+							location: Vec3::new(x, -310.0, 810.0),
+							dimensions: Dimensions {
+								width: 320.0,
+								height: 40.0,
+							},
+							enabled: true,
+							idle_color: Color::hex("EDD6AD").unwrap(),
+							hovered_color: Color::hex("CDB68D").unwrap(),
+							disabled_color: Color::hex("9D865D").unwrap(),
+						}, ButtonEffect::Popup(effect)));
+						commands.spawn((Text2dBundle {
+								transform: Transform::from_xyz(x, -312.5, 820.0),
+								text: Text::from_section(locale.get(label), get_button_text_style(&asset_server, &settings))
+									.with_alignment(TextAlignment::Center),
+								..Default::default()
+							},
+							DespawnOnExitPauseState,
+							Name::new("Settings Quit Button")
+						));
+					}
 					for i in 0..=10 {
 						buttons.push((StandardButton {
-							location: Vec3::new(25.0 + 30.0 * i as f32, 125.0, 810.0),
+							location: Vec3::new(25.0 + 30.0 * i as f32, 262.5, 810.0),
 							dimensions: Dimensions {
 								width: 25.0,
 								height: 50.0,
@@ -140,9 +169,9 @@ fn spawn_popup_buttons(
 							idle_color: Color::hex("EDD6AD").unwrap(),
 							hovered_color: Color::hex("CDB68D").unwrap(),
 							disabled_color: Color::hex("9D865D").unwrap(),
-						}, ButtonEffect::PopupButton(PopupButton::BgmVolume(i))));
+						}, ButtonEffect::Popup(PopupButton::BgmVolume(i))));
 						buttons.push((StandardButton {
-							location: Vec3::new(25.0 + 30.0 * i as f32, 62.5, 810.0),
+							location: Vec3::new(25.0 + 30.0 * i as f32, 207.5, 810.0),
 							dimensions: Dimensions {
 								width: 25.0,
 								height: 50.0,
@@ -151,10 +180,10 @@ fn spawn_popup_buttons(
 							idle_color: Color::hex("EDD6AD").unwrap(),
 							hovered_color: Color::hex("CDB68D").unwrap(),
 							disabled_color: Color::hex("9D865D").unwrap(),
-						}, ButtonEffect::PopupButton(PopupButton::SfxVolume(i))));
+						}, ButtonEffect::Popup(PopupButton::SfxVolume(i))));
 					}
 					buttons.push((StandardButton {
-						location: Vec3::new(37.5, 2.5, 810.0),
+						location: Vec3::new(37.5, 152.5, 810.0),
 						dimensions: Dimensions {
 							width: 50.0,
 							height: 50.0,
@@ -163,91 +192,61 @@ fn spawn_popup_buttons(
 						idle_color: Color::hex("EDD6AD").unwrap(),
 						hovered_color: Color::hex("CDB68D").unwrap(),
 						disabled_color: Color::hex("9D865D").unwrap(),
-					}, ButtonEffect::PopupButton(PopupButton::PaletteToggle)));
-					commands.spawn((Text2dBundle{
-						transform: Transform::from_xyz(62.5, -60.0, 820.0),
-						text: Text::from_section(format!("On"), get_settings_text_style(&asset_server))
-							.with_alignment(TextAlignment::Center),
-						text_anchor: bevy::sprite::Anchor::Center,
-						..Default::default()
-						},
-						DespawnOnExitPauseState,
-						Name::new("Particle Trail Enable Text")
-					));
-					buttons.push((StandardButton {
-						location: Vec3::new(62.5, -57.5, 810.0),
-						dimensions: Dimensions {
-							width: 100.0,
-							height: 50.0,
-						},
-						enabled: true,
-						idle_color: Color::hex("EDD6AD").unwrap(),
-						hovered_color: Color::hex("CDB68D").unwrap(),
-						disabled_color: Color::hex("9D865D").unwrap(),
-					}, ButtonEffect::PopupButton(PopupButton::ParticleTrails(true))));
-					commands.spawn((Text2dBundle{
-						transform: Transform::from_xyz(175.0, -60.0, 820.0),
-						text: Text::from_section(format!("Off"), get_settings_text_style(&asset_server))
-							.with_alignment(TextAlignment::Center),
-						text_anchor: bevy::sprite::Anchor::Center,
-						..Default::default()
-						},
-						DespawnOnExitPauseState,
-						Name::new("Particle Trail Disable Text")
-					));
-					buttons.push((StandardButton {
-						location: Vec3::new(175.0, -57.5, 810.0),
-						dimensions: Dimensions {
-							width: 100.0,
-							height: 50.0,
-						},
-						enabled: true,
-						idle_color: Color::hex("EDD6AD").unwrap(),
-						hovered_color: Color::hex("CDB68D").unwrap(),
-						disabled_color: Color::hex("9D865D").unwrap(),
-					}, ButtonEffect::PopupButton(PopupButton::ParticleTrails(false))));
-					commands.spawn((Text2dBundle{
-						transform: Transform::from_xyz(62.5, -120.0, 820.0),
-						text: Text::from_section(format!("On"), get_settings_text_style(&asset_server))
-							.with_alignment(TextAlignment::Center),
-						text_anchor: bevy::sprite::Anchor::Center,
-						..Default::default()
-						},
-						DespawnOnExitPauseState,
-						Name::new("Fullscreen Enable Text")
-					));
-					buttons.push((StandardButton {
-						location: Vec3::new(62.5, -117.5, 810.0),
-						dimensions: Dimensions {
-							width: 100.0,
-							height: 50.0,
-						},
-						enabled: true,
-						idle_color: Color::hex("EDD6AD").unwrap(),
-						hovered_color: Color::hex("CDB68D").unwrap(),
-						disabled_color: Color::hex("9D865D").unwrap(),
-					}, ButtonEffect::PopupButton(PopupButton::Fullscreen(true))));
+					}, ButtonEffect::Popup(PopupButton::PaletteToggle)));
+					// On and off for the letters, particle trails and fullscreen,
+					// then a choice of sizes for the text and the UI, and the
+					// languages, each named in its own language
+					let toggle = |enable: fn(bool) -> PopupButton| vec![(locale.get("settings.on"), enable(true)), (locale.get("settings.off"), enable(false))];
+					let sizes = |size: fn(usize) -> PopupButton| SCALE_STEPS.iter().enumerate()
+						.map(|(i, scale)| (format!("{:.0}%", scale * 100.0), size(i)))
+						.collect::<Vec<_>>();
+					let languages = LANGUAGES.iter().enumerate()
+						.map(|(i, (_, name))| (name.to_string(), PopupButton::Language(i)))
+						.collect::<Vec<_>>();
+					for (y, row) in [
+						(95.0, toggle(PopupButton::Glyphs)),
+						(40.0, toggle(PopupButton::ParticleTrails)),
+						(-15.0, toggle(PopupButton::Fullscreen)),
+						(-70.0, sizes(PopupButton::TextScale)),
+						(-125.0, sizes(PopupButton::UiScale)),
+						(-180.0, languages),
+					] {
+						for (column, (label, effect)) in row.into_iter().enumerate() {
+							let x = 62.5 + 112.5 * column as f32;
+							commands.spawn((Text2dBundle{
+								transform: Transform::from_xyz(x, y, 820.0),
+								text: Text::from_section(label, get_settings_text_style(&asset_server, &settings))
+									.with_alignment(TextAlignment::Center),
+								text_anchor: bevy::sprite::Anchor::Center,
+								..Default::default()
+								},
+								DespawnOnExitPauseState,
+								Name::new("Settings Choice Text")
+							));
+							buttons.push((StandardButton {
+								location: Vec3::new(x, y + 2.5, 810.0),
+								dimensions: Dimensions {
+									width: 100.0,
+									height: 50.0,
+								},
+								enabled: true,
+								idle_color: Color::hex("EDD6AD").unwrap(),
+								hovered_color: Color::hex("CDB68D").unwrap(),
+								disabled_color: Color::hex("9D865D").unwrap(),
+							}, ButtonEffect::Popup(effect)));
+						}
+					}
 					commands.spawn((Text2dBundle{
-						transform: Transform::from_xyz(175.0, -120.0, 820.0),
-						text: Text::from_section(format!("Off"), get_settings_text_style(&asset_server))
+						transform: Transform::from_xyz(0.0, -245.0, 810.0),
+						text: Text::from_section("", get_settings_text_style(&asset_server, &settings))
 							.with_alignment(TextAlignment::Center),
 						text_anchor: bevy::sprite::Anchor::Center,
 						..Default::default()
 						},
+						SaveTransferText,
 						DespawnOnExitPauseState,
-						Name::new("Fullscreen Disable Text")
+						Name::new("Save Transfer Text")
 					));
-					buttons.push((StandardButton {
-						location: Vec3::new(175.0, -117.5, 810.0),
-						dimensions: Dimensions {
-							width: 100.0,
-							height: 50.0,
-						},
-						enabled: true,
-						idle_color: Color::hex("EDD6AD").unwrap(),
-						hovered_color: Color::hex("CDB68D").unwrap(),
-						disabled_color: Color::hex("9D865D").unwrap(),
-					}, ButtonEffect::PopupButton(PopupButton::Fullscreen(false))));
 					for (button, effect) in buttons {
 						commands
 							.spawn((SpriteBundle {
@@ -265,13 +264,19 @@ fn spawn_popup_buttons(
 							Name::new("Settings Button")
 						));
 					}
-					for i in 0..15 {
+					// Preview of the designed molecules in the selected
+					// palette, with their glyphs if those are turned on
+					for i in 0..7 {
+						let texture_atlas = TextureAtlas::from_grid(asset_server.load(registry.path(i)), Vec2::new(32.0, 32.0), 4, 2, None, None);
+						let color = registry.color(i, selected_palette.0);
 						commands
-							.spawn((SpriteBundle{
-								transform: Transform::from_xyz(85.0 + 17.5 * i as f32, 2.5, 810.0),
-								sprite: Sprite {
-									color: get_molecule_color(i, selected_palette.0),
-									custom_size: Some(Vec2::new(12.5, 50.0)), 
+							.spawn((SpriteSheetBundle{
+								texture_atlas: texture_atlases.add(texture_atlas),
+								transform: Transform::from_xyz(90.0 + 45.0 * i as f32, 152.5, 810.0),
+								sprite: TextureAtlasSprite {
+									color,
+									index: 1,
+									custom_size: Some(Vec2::new(45.0, 45.0)), 
 									..Default::default()
 								},
 								..Default::default()
@@ -280,7 +285,29 @@ fn spawn_popup_buttons(
 							DespawnOnExitPauseState,
 							Name::new("Palette")
 						));
+						commands.spawn((Text2dBundle{
+							transform: Transform::from_xyz(90.0 + 45.0 * i as f32, 152.5, 815.0),
+							text: Text::from_section(registry.glyph(i), get_glyph_text_style(&asset_server, 22.0, color))
+								.with_alignment(TextAlignment::Center),
+							visibility: if settings.glyphs_enabled {Visibility::Inherited} else {Visibility::Hidden},
+							..Default::default()
+							},
+							MoleculeGlyph(i),
+							DespawnOnExitPauseState,
+							Name::new("Palette Glyph")
+						));
 					}
+					commands.spawn((Text2dBundle{
+						transform: Transform::from_xyz(405.0, 150.0, 810.0),
+						text: Text::from_section(locale.get(PALETTE_KEYS[selected_palette.0 % NUMBER_OF_PALETTES]), get_settings_text_style(&asset_server, &settings))
+							.with_alignment(TextAlignment::Left),
+						text_anchor: bevy::sprite::Anchor::CenterLeft,
+						..Default::default()
+						},
+						PaletteNameText,
+						DespawnOnExitPauseState,
+						Name::new("Palette Name Text")
+					));
 				},
 				
 				PopupType::Logbook => {
@@ -316,7 +343,7 @@ fn spawn_popup_buttons(
 							POPUP_WIDTH/2.0 - LOGBOOK_MARGINS * 2.0,
 							POPUP_HEIGHT - LOGBOOK_MARGINS * 2.0,
 						)},
-						text: Text::from_section(get_logbook_text(0, 0), get_logbook_text_style(&asset_server))
+						text: Text::from_section(locale.get(get_logbook_key(0, 0)), get_logbook_text_style(&asset_server, &settings))
 							.with_alignment(TextAlignment::Left),
 						text_anchor: bevy::sprite::Anchor::TopLeft,
 						..Default::default()
@@ -331,7 +358,7 @@ fn spawn_popup_buttons(
 							POPUP_WIDTH/2.0 - LOGBOOK_MARGINS * 2.0,
 							POPUP_HEIGHT - LOGBOOK_MARGINS * 2.0,
 						)},
-						text: Text::from_section(get_logbook_text(0, 1), get_logbook_text_style(&asset_server))
+						text: Text::from_section(locale.get(get_logbook_key(0, 1)), get_logbook_text_style(&asset_server, &settings))
 							.with_alignment(TextAlignment::Left),
 						text_anchor: bevy::sprite::Anchor::TopLeft,
 						..Default::default()
@@ -342,7 +369,7 @@ fn spawn_popup_buttons(
 					));
 					let mut tabs = Vec::new();
 					for i in 0..20 {
-						let color = get_molecule_color(i, selected_palette.0);
+						let color = registry.color(i, selected_palette.0);
 						tabs.push((StandardButton {
 							location: if i == 0 {Vec3::new(-600.0 + 60.0 * i as f32 + (i as f32 * 7.0).sin() * 8.0, 390.0 + (i as f32 * 9.0).cos() * 5.0, 810.0)}
 								else if i < 10 {Vec3::new(-600.0 + 60.0 * i as f32 + (i as f32 * 7.0).sin() * 8.0, 390.0 + (i as f32 * 9.0).cos() * 5.0, 801.0)}
@@ -355,9 +382,9 @@ fn spawn_popup_buttons(
 							idle_color: color,
 							hovered_color: Color::rgb((color.r() - 0.3).clamp(0.0, 1.0), (color.g() - 0.3).clamp(0.0, 1.0), (color.b() - 0.3).clamp(0.0, 1.0)),
 							disabled_color: Color::hex("9D865D").unwrap(),
-						}, ButtonEffect::PopupButton(PopupButton::LogbookPage(i)),
+						}, ButtonEffect::Popup(PopupButton::LogbookPage(i)),
 						0.0_f32,
-						if i < 10 {true} else {false}));
+						i < 10));
 					};
 					/*for i in 0..7 {
 						tabs.push((StandardButton {
@@ -367,8 +394,8 @@ fn spawn_popup_buttons(
 								height: 40.0,
 							},
 							enabled: true,
-						}, ButtonEffect::PopupButton(PopupButton::LogbookPage(i+15)),
-						get_molecule_color(i, selected_palette.0),
+						}, ButtonEffect::Popup(PopupButton::LogbookPage(i+15)),
+						registry.color(i, selected_palette.0),
 						90.0_f32));
 					}*/
 					for (button, effect, rotation, left) in tabs {
@@ -413,14 +440,14 @@ fn spawn_popup_buttons(
 							},
 							..Default::default()
 						},
-						ButtonEffect::PopupButton(PopupButton::ExitPopup),
+						ButtonEffect::Popup(PopupButton::ExitPopup),
 						button,
 						DespawnOnExitPauseState,
 						Name::new("Logbook Button")
 					));
 					commands.spawn((Text2dBundle {
 						transform: Transform::from_xyz(-500.0, -350.0, 820.0),
-						text: Text::from_section(format!("Back"), get_button_text_style(&asset_server))
+						text: Text::from_section(locale.get("button.back"), get_button_text_style(&asset_server, &settings))
 							.with_alignment(TextAlignment::Center),
 						..Default::default()
 					},
@@ -429,7 +456,7 @@ fn spawn_popup_buttons(
 					));
 				},
 				PopupType::LevelSelect => {
-					if let Ok(save_data) = pkv.get::<SaveData>("save_data") {
+					if let Ok(save_data) = pkv.get::<SaveData>(get_profile_key(selected_profile.0)) {
 						// Spawn level select buttons
 						for j in 0..=4 {
 							for i in 0..7 {
@@ -440,7 +467,7 @@ fn spawn_popup_buttons(
 										width: 85.0,
 										height: 85.0,
 									},
-									enabled: save_data.levels_unlocked[i+7*j],
+									enabled: save_data.levels_unlocked[i+7*j] && levels.is_loaded(i+7*j),
 									idle_color: Color::hex("EDD6AD").unwrap(),
 									hovered_color: Color::hex("CDB68D").unwrap(),
 									disabled_color: Color::hex("9D865D").unwrap(),
@@ -454,20 +481,24 @@ fn spawn_popup_buttons(
 										},
 										..Default::default()
 									},
-									ButtonEffect::PopupButton(PopupButton::LevelSelect(i + 7*j)),
+									ButtonEffect::Popup(PopupButton::LevelSelect(i + 7*j)),
 									button,
 									DespawnOnExitPauseState,
 									Name::new(format!("Level Select Button {}", i + 7*j))
 								)).with_children(|parent| {
 									parent
 										.spawn((Text2dBundle {
-											transform: Transform::from_xyz(0.0, -5.0, 10.0,),
-											text: Text::from_section(format!("{}", i+7*j+1), get_button_text_style(&asset_server))
+											transform: Transform::from_xyz(0.0, 5.0, 10.0,),
+											text: Text::from_section(format!("{}", i+7*j+1), get_button_text_style(&asset_server, &settings))
 												.with_alignment(TextAlignment::Center),
 											..Default::default()
 										},
 										Name::new("Level Select Button Text")
 									));
+									let stars = save_data.stars.get(i + 7*j).copied().unwrap_or(0);
+									for star in get_star_sprites(stars, Vec3::new(0.0, -28.0, 10.0), 12.0) {
+										parent.spawn((star, Name::new("Level Select Star")));
+									}
 								});
 							}
 						}
@@ -493,14 +524,14 @@ fn spawn_popup_buttons(
 							},
 							..Default::default()
 						},
-						ButtonEffect::PopupButton(PopupButton::ExitPopup),
+						ButtonEffect::Popup(PopupButton::ExitPopup),
 						button,
 						DespawnOnExitPauseState,
 						Name::new("Exit Level Select Button")
 					));
 					commands.spawn((Text2dBundle {
 						transform: Transform::from_xyz(-525.0, -337.5, 820.0),
-						text: Text::from_section(format!("Back"), get_button_text_style(&asset_server))
+						text: Text::from_section(locale.get("button.back"), get_button_text_style(&asset_server, &settings))
 							.with_alignment(TextAlignment::Center),
 						..Default::default()
 					},
@@ -511,7 +542,7 @@ fn spawn_popup_buttons(
 				PopupType::LevelIntro(level) => {
 					commands.spawn((Text2dBundle{
 						transform: Transform::from_xyz(0.0, 300.0, 810.0),
-						text: Text::from_section(format!("Hints for day {}", level + 1), get_title_text_style(&asset_server))
+						text: Text::from_section(locale.format("level_intro.title", &[(level + 1).to_string()]), get_title_text_style(&asset_server, &settings))
 							.with_alignment(TextAlignment::Center),
 						text_anchor: bevy::sprite::Anchor::Center,
 						..Default::default()
@@ -525,7 +556,7 @@ fn spawn_popup_buttons(
 							POPUP_WIDTH - TEXT_BOX_MARGINS * 2.0,
 							POPUP_HEIGHT - TEXT_BOX_MARGINS,
 						)},
-						text: Text::from_section(get_intro_text(level), get_intro_text_style(&asset_server))
+						text: Text::from_section(locale.get_or(&get_level_intro_key(level), &levels.get(level).intro_text), get_intro_text_style(&asset_server, &settings))
 							.with_alignment(TextAlignment::Left),
 						text_anchor: bevy::sprite::Anchor::Center,
 						..Default::default()
@@ -554,7 +585,7 @@ fn spawn_popup_buttons(
 							},
 							..Default::default()
 						},
-						ButtonEffect::PopupButton(PopupButton::ExitPopup),
+						ButtonEffect::Popup(PopupButton::ExitPopup),
 						button,
 						DespawnOnExitPauseState,
 						Name::new("Exit Intro Button")
@@ -562,7 +593,7 @@ fn spawn_popup_buttons(
 					commands
 						.spawn((Text2dBundle {
 							transform: Transform::from_xyz(400.0, -300.0, 820.0),
-							text: Text::from_section(format!("Continue"), get_button_text_style(&asset_server))
+							text: Text::from_section(locale.get("button.continue"), get_button_text_style(&asset_server, &settings))
 								.with_alignment(TextAlignment::Center),
 							..Default::default()
 						},
@@ -590,14 +621,14 @@ fn spawn_popup_buttons(
 							},
 							..Default::default()
 						},
-						ButtonEffect::PopupButton(PopupButton::ReturnToLab),
+						ButtonEffect::Popup(PopupButton::ReturnToLab),
 						button,
 						DespawnOnExitPauseState,
 						Name::new("Return to Lab Button")
 					));
 					commands.spawn((Text2dBundle {
 						transform: Transform::from_xyz(-400.0, -300.0, 820.0),
-						text: Text::from_section(format!("Exit"), get_button_text_style(&asset_server))
+						text: Text::from_section(locale.get("button.exit"), get_button_text_style(&asset_server, &settings))
 							.with_alignment(TextAlignment::Center),
 						..Default::default()
 					},
@@ -605,10 +636,10 @@ fn spawn_popup_buttons(
 					Name::new("Return to Lab Button")
 					));
 				}
-				PopupType::WinScreen(prev_best_time, current_time, prev_best_cost, current_cost) => {
+				PopupType::WinScreen(prev_best_time, current_time, prev_best_cost, current_cost, stars) => {
 					commands.spawn((Text2dBundle{
 						transform: Transform::from_xyz(0.0, 300.0, 810.0),
-						text: Text::from_section(format!("Reaction Successful!"), get_win_title_text_style(&asset_server))
+						text: Text::from_section(locale.get("win.title"), get_win_title_text_style(&asset_server, &settings))
 							.with_alignment(TextAlignment::Center),
 						text_anchor: bevy::sprite::Anchor::Center,
 						..Default::default()
@@ -616,18 +647,34 @@ fn spawn_popup_buttons(
 						DespawnOnExitPauseState,
 						Name::new("Win Text")
 					));
-					let prev_best_time_text = if prev_best_time < 60.0 {format!("{:.2} s", prev_best_time)}
-						else if prev_best_time < 6000.0 {format!("{:.0} m {:.0} s", (prev_best_time / 60.0).floor(), prev_best_time % 60.0)}
-						else if prev_best_time < 999999.0 {format!("{:.0} m", (prev_best_time / 60.0).floor())}
-						else {format!("None")};
-					let current_time_text = if current_time < 60.0 {format!("{:.2} s", current_time)}
-						else if current_time < 6000.0 {format!("{:.0} m {:.0} s", (current_time / 60.0).floor(), current_time % 60.0)}
-						else if current_time < 999999.0 {format!("{:.0} m", (current_time / 60.0).floor())}
-						else {format!("A While")};
-					let new_best_time = if current_time < prev_best_time {format!("New Best Time: ")} else {format!("Reaction Time: ")};
-					let new_best_cost = if current_cost < prev_best_cost {format!("New Best Cost: ")} else {format!("Reaction Cost: ")};
-					let prev_best_cost_text = if prev_best_cost < 999999 {format!("{} c", prev_best_cost)} else {format!("None")};
-					let current_cost_text = format!("{} c", current_cost);
+					for star in get_star_sprites(stars, Vec3::new(0.0, 190.0, 810.0), 40.0) {
+						commands.spawn((star, DespawnOnExitPauseState, Name::new("Win Star")));
+					}
+					let level = levels.get(selected_level.0);
+					let par_time_text = level.par_time.map_or(locale.get("win.none"), |par_time| locale.format("unit.seconds", &[format!("{:.0}", par_time)]));
+					let par_cost_text = level.par_cost.map_or(locale.get("win.none"), |par_cost| locale.format("unit.cost", &[par_cost.to_string()]));
+					commands.spawn((Text2dBundle{
+						transform: Transform::from_xyz(0.0, -180.0, 810.0),
+						text: Text::from_section(locale.format("win.par", &[par_time_text, par_cost_text]), get_win_text_style(&asset_server, &settings))
+							.with_alignment(TextAlignment::Center),
+						text_anchor: bevy::sprite::Anchor::Center,
+						..Default::default()
+						},
+						DespawnOnExitPauseState,
+						Name::new("Par Text")
+					));
+					let prev_best_time_text = if prev_best_time < 60.0 {locale.format("unit.seconds", &[format!("{:.2}", prev_best_time)])}
+						else if prev_best_time < 6000.0 {locale.format("unit.minutes_seconds", &[format!("{:.0}", (prev_best_time / 60.0).floor()), format!("{:.0}", prev_best_time % 60.0)])}
+						else if prev_best_time < 999999.0 {locale.format("unit.minutes", &[format!("{:.0}", (prev_best_time / 60.0).floor())])}
+						else {locale.get("win.none")};
+					let current_time_text = if current_time < 60.0 {locale.format("unit.seconds", &[format!("{:.2}", current_time)])}
+						else if current_time < 6000.0 {locale.format("unit.minutes_seconds", &[format!("{:.0}", (current_time / 60.0).floor()), format!("{:.0}", current_time % 60.0)])}
+						else if current_time < 999999.0 {locale.format("unit.minutes", &[format!("{:.0}", (current_time / 60.0).floor())])}
+						else {locale.get("win.a_while")};
+					let new_best_time = if current_time < prev_best_time {locale.get("win.new_best_time")} else {locale.get("win.reaction_time")};
+					let new_best_cost = if current_cost < prev_best_cost {locale.get("win.new_best_cost")} else {locale.get("win.reaction_cost")};
+					let prev_best_cost_text = if prev_best_cost < 999999 {locale.format("unit.cost", &[prev_best_cost.to_string()])} else {locale.get("win.none")};
+					let current_cost_text = locale.format("unit.cost", &[current_cost.to_string()]);
 					let win_text = [
 						prev_best_time_text,
 						current_time_text,
@@ -640,7 +687,7 @@ fn spawn_popup_buttons(
 					let z = 810.0;
 					commands.spawn((Text2dBundle{
 						transform: Transform::from_xyz(-x, y, z),
-						text: Text::from_section(format!("Previous Best Time: \n{}\nPrevious Best Cost: \n{}", new_best_time, new_best_cost), get_win_text_style(&asset_server))
+						text: Text::from_section(locale.format("win.labels", &[new_best_time, new_best_cost]), get_win_text_style(&asset_server, &settings))
 							.with_alignment(TextAlignment::Right),
 						text_anchor: bevy::sprite::Anchor::CenterRight,
 						..Default::default()
@@ -651,7 +698,7 @@ fn spawn_popup_buttons(
 
 					commands.spawn((Text2dBundle{
 						transform: Transform::from_xyz(x, y, z),
-						text: Text::from_section(format!("{}\n{}\n{}\n{}", win_text[0], win_text[1], win_text[2], win_text[3]), get_win_values_text_style(&asset_server))
+						text: Text::from_section(format!("{}\n{}\n{}\n{}", win_text[0], win_text[1], win_text[2], win_text[3]), get_win_values_text_style(&asset_server, &settings))
 							.with_alignment(TextAlignment::Left),
 						text_anchor: bevy::sprite::Anchor::CenterLeft,
 						..Default::default()
@@ -661,8 +708,8 @@ fn spawn_popup_buttons(
 					));
 					commands
 						.spawn((Text2dBundle {
-							transform: Transform::from_xyz(-300.0, -300.0, 840.0),
-							text: Text::from_section(format!("Replay"), get_button_text_style(&asset_server))
+							transform: Transform::from_xyz(-450.0, -300.0, 840.0),
+							text: Text::from_section(locale.get("button.replay"), get_button_text_style(&asset_server, &settings))
 								.with_alignment(TextAlignment::Center),
 							..Default::default()
 						},
@@ -671,8 +718,18 @@ fn spawn_popup_buttons(
 					));
 					commands
 						.spawn((Text2dBundle {
-							transform: Transform::from_xyz(300.0, -300.0, 840.0),
-							text: Text::from_section(format!("Continue"), get_button_text_style(&asset_server))
+							transform: Transform::from_xyz(0.0, -300.0, 840.0),
+							text: Text::from_section(locale.get("button.watch_solution"), get_button_text_style(&asset_server, &settings))
+								.with_alignment(TextAlignment::Center),
+							..Default::default()
+						},
+						DespawnOnExitPauseState,
+						Name::new("Watch Win Screen Text")
+					));
+					commands
+						.spawn((Text2dBundle {
+							transform: Transform::from_xyz(450.0, -300.0, 840.0),
+							text: Text::from_section(locale.get("button.continue"), get_button_text_style(&asset_server, &settings))
 								.with_alignment(TextAlignment::Center),
 							..Default::default()
 						},
@@ -681,15 +738,20 @@ fn spawn_popup_buttons(
 					));
 					let mut buttons = Vec::new();
 					let effects = [
-						ButtonEffect::PopupButton(PopupButton::ReplayLevel),
-						ButtonEffect::PopupButton(PopupButton::CompleteLevel),
+						ButtonEffect::Popup(PopupButton::ReplayLevel),
+						ButtonEffect::Popup(PopupButton::WatchReplay),
+						ButtonEffect::Popup(PopupButton::CompleteLevel),
 					];
-					if let Ok(save_data) = pkv.get::<SaveData>("save_data") {
-						let enabled = [save_data.cutscenes_unlocked[selected_level.0 + 1], true];
-						for i in 0..2 {
+					if let Ok(save_data) = pkv.get::<SaveData>(get_profile_key(selected_profile.0)) {
+						let enabled = [
+							save_data.cutscenes_unlocked[selected_level.0 + 1],
+							load_replay(&pkv, selected_profile.0, selected_level.0).is_some(),
+							true,
+						];
+						for i in 0..3 {
 							buttons.push((
 								StandardButton {
-									location: Vec3::new(-300.0+600.0*i as f32, -300.0, 830.0),
+									location: Vec3::new(-450.0+450.0*i as f32, -300.0, 830.0),
 									dimensions: Dimensions {
 										width: 400.0,
 										height: 40.0,
@@ -720,7 +782,353 @@ fn spawn_popup_buttons(
 						));
 					}
 				}
+				PopupType::FailScreen(reason) => {
+					commands.spawn((Text2dBundle{
+						transform: Transform::from_xyz(0.0, 300.0, 810.0),
+						text: Text::from_section(locale.get("fail.title"), get_win_title_text_style(&asset_server, &settings))
+							.with_alignment(TextAlignment::Center),
+						text_anchor: bevy::sprite::Anchor::Center,
+						..Default::default()
+						},
+						DespawnOnExitPauseState,
+						Name::new("Fail Text")
+					));
+					let level = levels.get(selected_level.0);
+					let fail_text = match reason {
+						FailReason::OverBudget => locale.format("fail.over_budget", &[level.cost_limit.unwrap_or_default().to_string()]),
+						FailReason::OutOfTime => locale.format("fail.out_of_time", &[format!("{:.0}", level.time_limit.unwrap_or_default())]),
+					};
+					commands.spawn((Text2dBundle{
+						transform: Transform::from_xyz(0.0, 0.0, 810.0),
+						text: Text::from_section(fail_text, get_win_values_text_style(&asset_server, &settings))
+							.with_alignment(TextAlignment::Center),
+						text_anchor: bevy::sprite::Anchor::Center,
+						..Default::default()
+						},
+						DespawnOnExitPauseState,
+						Name::new("Fail Text")
+					));
+					let buttons = [
+						(Vec3::new(-300.0, -300.0, 830.0), "button.retry", ButtonEffect::Popup(PopupButton::ReplayLevel)),
+						(Vec3::new(300.0, -300.0, 830.0), "button.exit", ButtonEffect::Popup(PopupButton::ReturnToLab)),
+					];
+					for (location, label, effect) in buttons {
+						let button = StandardButton {
+							location,
+							dimensions: Dimensions {
+								width: 400.0,
+								height: 40.0,
+							},
+							enabled: true,
+							idle_color: Color::hex("EDD6AD").unwrap(),
+							hovered_color: Color::hex("CDB68D").unwrap(),
+							disabled_color: Color::hex("9D865D").unwrap(),
+						};
+						commands
+							.spawn((SpriteBundle {
+								transform: Transform::from_translation(button.location),
+								sprite: Sprite {
+									color: Color::hex("EDD6AD").unwrap(),
+									custom_size: Some(Vec2::new(button.dimensions.width, button.dimensions.height)),
+									..Default::default()
+								},
+								..Default::default()
+							},
+							effect,
+							button,
+							DespawnOnExitPauseState,
+							Name::new("Fail Screen Button")
+						));
+						commands
+							.spawn((Text2dBundle {
+								transform: Transform::from_xyz(location.x, location.y, 840.0),
+								text: Text::from_section(locale.get(label), get_button_text_style(&asset_server, &settings))
+									.with_alignment(TextAlignment::Center),
+								..Default::default()
+							},
+							DespawnOnExitPauseState,
+							Name::new("Fail Screen Button Text")
+						));
+					}
+				}
+				PopupType::Profiles => {
+					commands.spawn((Text2dBundle{
+						transform: Transform::from_xyz(0.0, 300.0, 810.0),
+						text: Text::from_section(locale.get("profiles.title"), get_title_text_style(&asset_server, &settings)),
+						..Default::default()
+						},
+						DespawnOnExitPauseState,
+						Name::new("Profiles Text")
+					));
+					// Each slot has its name, which selects it or starts a new
+					// profile there, followed by buttons to manage it. Their
+					// text and which are enabled are kept up to date by the menu
+					for slot in 0..NUMBER_OF_PROFILES {
+						let y = 200.0 - 90.0 * slot as f32;
+						let buttons = [
+							(Vec3::new(-200.0, y, 810.0), 500.0, ButtonEffect::Popup(PopupButton::SelectProfile(slot))),
+							(Vec3::new(165.0, y, 810.0), 180.0, ButtonEffect::Popup(PopupButton::RenameProfile(slot))),
+							(Vec3::new(360.0, y, 810.0), 160.0, ButtonEffect::Popup(PopupButton::CopyProfile(slot))),
+							(Vec3::new(545.0, y, 810.0), 160.0, ButtonEffect::Popup(PopupButton::DeleteProfile(slot))),
+						];
+						for (location, width, effect) in buttons {
+							let button = StandardButton {
+								location,
+								dimensions: Dimensions {
+									width,
+									height: 60.0,
+								},
+								enabled: true,
+								idle_color: Color::hex("EDD6AD").unwrap(),
+								hovered_color: Color::hex("CDB68D").unwrap(),
+								disabled_color: Color::hex("9D865D").unwrap(),
+							};
+							commands
+								.spawn((SpriteBundle {
+									transform: Transform::from_translation(button.location),
+									sprite: Sprite {
+										color: Color::hex("EDD6AD").unwrap(),
+										custom_size: Some(Vec2::new(button.dimensions.width, button.dimensions.height)),
+										..Default::default()
+									},
+									..Default::default()
+								},
+								effect,
+								button,
+								DespawnOnExitPauseState,
+								Name::new("Profile Button")
+							)).with_children(|parent| {
+								let text = Text2dBundle {
+									transform: Transform::from_xyz(0.0, -2.5, 10.0),
+									text: Text::from_section("", get_button_text_style(&asset_server, &settings))
+										.with_alignment(TextAlignment::Center),
+									..Default::default()
+								};
+								match effect {
+									ButtonEffect::Popup(PopupButton::SelectProfile(_)) => {
+										parent.spawn((text, ProfileSlotText(slot), Name::new("Profile Name Text")));
+									},
+									ButtonEffect::Popup(PopupButton::DeleteProfile(_)) => {
+										parent.spawn((text, ProfileDeleteText(slot), Name::new("Profile Delete Text")));
+									},
+									ButtonEffect::Popup(PopupButton::RenameProfile(_)) => {
+										parent.spawn((Text2dBundle {
+											text: Text::from_section(locale.get("profiles.rename"), get_button_text_style(&asset_server, &settings))
+												.with_alignment(TextAlignment::Center),
+											..text
+										}, Name::new("Profile Rename Text")));
+									},
+									_ => {
+										parent.spawn((Text2dBundle {
+											text: Text::from_section(locale.get("profiles.copy"), get_button_text_style(&asset_server, &settings))
+												.with_alignment(TextAlignment::Center),
+											..text
+										}, Name::new("Profile Copy Text")));
+									},
+								}
+							});
+						}
+					}
+					let button = StandardButton {
+						location: Vec3::new(0.0, -310.0, 810.0),
+						dimensions: Dimensions {
+							width: 400.0,
+							height: 40.0,
+						},
+						enabled: true,
+						idle_color: Color::hex("EDD6AD").unwrap(),
+						hovered_color: Color::hex("CDB68D").unwrap(),
+						disabled_color: Color::hex("9D865D").unwrap(),
+					};
+					commands
+						.spawn((SpriteBundle {
+							transform: Transform::from_translation(button.location),
+							sprite: Sprite {
+								color: Color::hex("EDD6AD").unwrap(),
+								custom_size: Some(Vec2::new(button.dimensions.width, button.dimensions.height)),
+								..Default::default()
+							},
+							..Default::default()
+						},
+						ButtonEffect::Popup(PopupButton::ExitPopup),
+						button,
+						DespawnOnExitPauseState,
+						Name::new("Profiles Back Button")
+					));
+					commands.spawn((Text2dBundle {
+							transform: Transform::from_xyz(0.0, -312.5, 820.0),
+							text: Text::from_section(locale.get("button.back"), get_button_text_style(&asset_server, &settings))
+								.with_alignment(TextAlignment::Center),
+							..Default::default()
+						},
+						DespawnOnExitPauseState,
+						Name::new("Profiles Back Button Text")
+					));
+				}
+
+				PopupType::Controls => {
+					commands.spawn((Text2dBundle{
+						transform: Transform::from_xyz(0.0, 330.0, 810.0),
+						text: Text::from_section(locale.get("controls.title"), get_title_text_style(&asset_server, &settings)),
+						..Default::default()
+						},
+						DespawnOnExitPauseState,
+						Name::new("Controls Text")
+					));
+					// Actions are listed in two columns, each with buttons for
+					// its two bindings. Their text is kept up to date by the
+					// controls module while a binding is being changed
+					for (i, action) in InputAction::ALL.iter().enumerate() {
+						let x = if i < 8 {-300.0} else {380.0};
+						let y = 230.0 - 60.0 * (i % 8) as f32;
+						commands.spawn((Text2dBundle{
+							transform: Transform::from_xyz(x - 120.0, y - 2.5, 810.0),
+							text: Text::from_section(locale.get(action.key()), get_settings_text_style(&asset_server, &settings))
+								.with_alignment(TextAlignment::Right),
+							text_anchor: bevy::sprite::Anchor::CenterRight,
+							..Default::default()
+							},
+							DespawnOnExitPauseState,
+							Name::new("Action Text")
+						));
+						for slot in 0..2 {
+							let button = StandardButton {
+								location: Vec3::new(x - 30.0 + 160.0 * slot as f32, y, 810.0),
+								dimensions: Dimensions {
+									width: 150.0,
+									height: 50.0,
+								},
+								enabled: true,
+								idle_color: Color::hex("EDD6AD").unwrap(),
+								hovered_color: Color::hex("CDB68D").unwrap(),
+								disabled_color: Color::hex("9D865D").unwrap(),
+							};
+							commands
+								.spawn((SpriteBundle {
+									transform: Transform::from_translation(button.location),
+									sprite: Sprite {
+										color: Color::hex("EDD6AD").unwrap(),
+										custom_size: Some(Vec2::new(button.dimensions.width, button.dimensions.height)),
+										..Default::default()
+									},
+									..Default::default()
+								},
+								ButtonEffect::Popup(PopupButton::Rebind(*action, slot)),
+								button,
+								DespawnOnExitPauseState,
+								Name::new("Binding Button")
+							)).with_children(|parent| {
+								parent.spawn((Text2dBundle {
+										transform: Transform::from_xyz(0.0, -2.5, 10.0),
+										text: Text::from_section("", get_button_text_style(&asset_server, &settings))
+											.with_alignment(TextAlignment::Center),
+										..Default::default()
+									},
+									BindingText(*action, slot),
+									Name::new("Binding Text")
+								));
+							});
+						}
+					}
+					commands.spawn((Text2dBundle{
+						transform: Transform::from_xyz(0.0, -250.0, 810.0),
+						text: Text::from_section(locale.get("controls.hint"), get_settings_text_style(&asset_server, &settings))
+							.with_alignment(TextAlignment::Center),
+						..Default::default()
+						},
+						DespawnOnExitPauseState,
+						Name::new("Controls Hint Text")
+					));
+					for (x, label, effect) in [
+						(-250.0, "controls.reset", PopupButton::ResetControls),
+						(250.0, "button.back", PopupButton::OpenSettings),
+					] {
+						let button = StandardButton {
+							location: Vec3::new(x, -310.0, 810.0),
+							dimensions: Dimensions {
+								width: 400.0,
+								height: 40.0,
+							},
+							enabled: true,
+							idle_color: Color::hex("EDD6AD").unwrap(),
+							hovered_color: Color::hex("CDB68D").unwrap(),
+							disabled_color: Color::hex("9D865D").unwrap(),
+						};
+						commands
+							.spawn((SpriteBundle {
+								transform: Transform::from_translation(button.location),
+								sprite: Sprite {
+									color: Color::hex("EDD6AD").unwrap(),
+									custom_size: Some(Vec2::new(button.dimensions.width, button.dimensions.height)),
+									..Default::default()
+								},
+								..Default::default()
+							},
+							ButtonEffect::Popup(effect),
+							button,
+							DespawnOnExitPauseState,
+							Name::new("Controls Button")
+						)).with_children(|parent| {
+							parent.spawn((Text2dBundle {
+									transform: Transform::from_xyz(0.0, -2.5, 10.0),
+									text: Text::from_section(locale.get(label), get_button_text_style(&asset_server, &settings))
+										.with_alignment(TextAlignment::Center),
+									..Default::default()
+								},
+								Name::new("Controls Button Text")
+							));
+						});
+					}
+				}
 			}
 		}
 	}
-}
\ No newline at end of file
+}
+// Shows how the last export or import of a save went
+fn update_save_transfer_text(
+	save_transfer_message: Res<SaveTransferMessage>,
+	mut save_transfer_text_query: Query<&mut Text, With<SaveTransferText>>,
+) {
+	for mut text in save_transfer_text_query.iter_mut() {
+		text.sections[0].value = save_transfer_message.0.clone();
+	}
+}
+
+// Recolours the palette preview and renames it
+// whenever the palette is toggled
+fn update_palette_preview(
+	registry: Res<MoleculeRegistry>,
+	locale: Res<Locale>,
+	selected_palette: Res<SelectedPalette>,
+	mut palette_query: Query<(&mut TextureAtlasSprite, &Palette)>,
+	mut palette_name_query: Query<&mut Text, With<PaletteNameText>>,
+) {
+	if !selected_palette.is_changed() {
+		return;
+	}
+	for (mut sprite, palette) in palette_query.iter_mut() {
+		sprite.color = registry.color(palette.0, selected_palette.0);
+	}
+	for mut text in palette_name_query.iter_mut() {
+		text.sections[0].value = locale.get(PALETTE_KEYS[selected_palette.0 % NUMBER_OF_PALETTES]);
+	}
+}
+
+// Stars are drawn as diamonds in a row, filled in
+// for each one earned
+fn get_star_sprites(
+	stars: usize,
+	center: Vec3,
+	size: f32,
+) -> Vec<SpriteBundle> {
+	(0..MAX_STARS).map(|i| SpriteBundle {
+		transform: Transform::from_translation(center + Vec3::new((i as f32 - (MAX_STARS - 1) as f32 / 2.0) * size * 1.75, 0.0, 0.0))
+			.with_rotation(Quat::from_rotation_z(45.0_f32.to_radians())),
+		sprite: Sprite {
+			color: if i < stars {Color::hex("F2C46D").unwrap()} else {Color::hex("9D865D").unwrap()},
+			custom_size: Some(Vec2::new(size, size)),
+			..Default::default()
+		},
+		..Default::default()
+	}).collect()
+}
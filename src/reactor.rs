@@ -1,10 +1,12 @@
 // Import Bevy game engine essentials
-use bevy::{prelude::*, render::view::RenderLayers, math::Vec3Swizzles, time::Stopwatch};
+use bevy::{prelude::*, render::view::RenderLayers};
 use bevy_pkv::PkvStore;
+// Import the simulation's level spawning
+use mole_sim::reactor::spawn_level;
 // Import components, resources, and events
-use crate::{components::*, molecules::reset_choices};
+use crate::{components::*, controls::ActionInput, levels::Levels, molecules::reset_choices, save::get_profile_key};
 
-// Plugin for handling reactor sprites and logic
+// Plugin for handling reactor sprites and controls
 pub struct ReactorPlugin;
 
 impl Plugin for ReactorPlugin {
@@ -22,12 +24,16 @@ impl Plugin for ReactorPlugin {
 				particle_highlight,
 				update_cost,
 				update_stopwatch,
-				handle_levers,
-				intake_connections,
-				outlet_connections.after(intake_connections),
+				update_goal_progress,
+				update_limit_boxes,
+				handle_levers.run_if(not(resource_exists::<ReplayPlayback>())),
 				check_product_reactor,
 			).run_if(in_state(GameState::Reactor))
 			.run_if(not(in_state(PauseState::Paused))))
+			.add_systems(PostUpdate, (
+				attach_reactor_visuals,
+				attach_connection_visuals,
+			))
 		;
 	}
 }
@@ -92,7 +98,10 @@ fn spawn_reactor_intro(
 fn spawn_reactor_visuals(
 	mut commands: Commands,
 	selected_level: Res<SelectedLevel>,
+	levels: Levels,
 	asset_server: Res<AssetServer>,
+	settings: Res<Settings>,
+	locale: Res<Locale>,
 	ortho_size: Res<OrthoSize>,
 	selected_molecule_type: ResMut<SelectedMoleculeType>,
 ) {
@@ -178,6 +187,7 @@ fn spawn_reactor_visuals(
 			..Default::default()
 		},
 		Tooltip,
+		ScaledBox::Tooltip,
 		DespawnOnExitGameState,
 		Name::new("Tooltip"),
 	)).with_children(|parent| {
@@ -189,7 +199,7 @@ fn spawn_reactor_visuals(
 				)},
 				transform: Transform::from_xyz(-TOOLTIP_WIDTH/2.0 + TOOLTIP_MARGINS, TOOLTIP_HEIGHT/2.0 - TOOLTIP_MARGINS, 0.1),
 				text_anchor: bevy::sprite::Anchor::TopLeft,
-				text: Text::from_section(format!(""), get_tooltip_text_style(&asset_server))
+				text: Text::from_section("", get_tooltip_text_style(&asset_server, &settings))
 				.with_alignment(TextAlignment::Left),
 				..Default::default()
 			},
@@ -202,7 +212,7 @@ fn spawn_reactor_visuals(
 		.spawn((Text2dBundle {
 			transform: Transform::from_xyz(REACTOR_VIEWPORT_CENTER.x, REACTOR_VIEWPORT_CENTER.y, 710.0),
 			text_anchor: bevy::sprite::Anchor::Center,
-			text: Text::from_section(format!("3.00"), get_win_countdown_text_style(&asset_server))
+			text: Text::from_section("3.00", get_win_countdown_text_style(&asset_server, &settings))
 				.with_alignment(TextAlignment::Center),
 				visibility: Visibility::Hidden,
 			..Default::default()
@@ -223,6 +233,8 @@ fn spawn_reactor_visuals(
 			..Default::default()
 		},
 		DespawnOnExitGameState,
+		StopwatchBox,
+		ScaledBox::Stopwatch,
 		Name::new("Stopwatch Box Sprite")
 	)).with_children(|parent| {
 		parent
@@ -233,11 +245,11 @@ fn spawn_reactor_visuals(
 				)},
 				transform: Transform::from_xyz(-STOPWATCH_BOX_WIDTH / 2.0 + STOPWATCH_BOX_MARGINS, 0.0, 10.0),
 				text_anchor: bevy::sprite::Anchor::CenterLeft,
-				text: Text::from_section(format!("0.00 s"), get_stopwatch_text_style(&asset_server))
+				text: Text::from_section("", get_stopwatch_text_style(&asset_server, &settings))
 				.with_alignment(TextAlignment::Right),
 				..Default::default()
 			},
-			StopwatchText(Stopwatch::default()),
+			StopwatchText,
 			Name::new("Stopwatch Text")
 		));
 	});
@@ -253,6 +265,8 @@ fn spawn_reactor_visuals(
 			..Default::default()
 		},
 		DespawnOnExitGameState,
+		CostBox,
+		ScaledBox::Cost,
 		Name::new("Cost Box Sprite")
 	)).with_children(|parent| {
 		parent
@@ -263,7 +277,7 @@ fn spawn_reactor_visuals(
 				)},
 				transform: Transform::from_xyz(-COST_BOX_WIDTH / 2.0 + COST_BOX_MARGINS, 0.0, 10.0),
 				text_anchor: bevy::sprite::Anchor::CenterLeft,
-				text: Text::from_section(format!("0 c"), get_cost_text_style(&asset_server))
+				text: Text::from_section("", get_cost_text_style(&asset_server, &settings))
 				.with_alignment(TextAlignment::Right),
 				..Default::default()
 			},
@@ -283,6 +297,7 @@ fn spawn_reactor_visuals(
 			..Default::default()
 		},
 		DespawnOnExitGameState,
+		ScaledBox::Goal,
 		Name::new("Goal Box Sprite")
 	)).with_children(|parent| {
 		parent
@@ -293,45 +308,96 @@ fn spawn_reactor_visuals(
 				)},
 				transform: Transform::from_xyz(0.0, 0.0, 10.0),
 				text_anchor: bevy::sprite::Anchor::Center,
-				text: Text::from_section(get_level_goal_text(selected_level.0), get_goal_text_style(&asset_server))
+				text: Text::from_section(locale.get_or(&get_level_goal_key(selected_level.0), &levels.get(selected_level.0).goal_text), get_goal_text_style(&asset_server, &settings))
 				.with_alignment(TextAlignment::Center),
 				..Default::default()
 			},
+			GoalText,
 			Name::new("Goal Text")
 		));
 	});
 }
 
-// Update the stopwatch to track time spent on a level
+// Update the stopwatch to show the simulated time spent on a level
 fn update_stopwatch(
-	mut stopwatch_text_query: Query<(&mut Text, &mut StopwatchText)>,
-	time: Res<Time>,
+	mut stopwatch_text_query: Query<&mut Text, With<StopwatchText>>,
+	locale: Res<Locale>,
+	attempt: Option<Res<LevelAttempt>>,
 ) {
-	for (mut text, mut stopwatch) in stopwatch_text_query.iter_mut() {
-		stopwatch.0.tick(time.delta());
+	let elapsed = attempt.map_or(0.0, |attempt| attempt.tick as f32 * PHYSICS_TIMESTEP);
+	for mut text in stopwatch_text_query.iter_mut() {
 		text.sections[0].value =
-			if stopwatch.0.elapsed_secs() < 60.0 {format!("{:.2} s", stopwatch.0.elapsed_secs())}
-			else if stopwatch.0.elapsed_secs() < 6000.0 {format!("{:.0} m {:.0} s", (stopwatch.0.elapsed_secs() / 60.0).floor(), stopwatch.0.elapsed_secs() % 60.0)}
-			else if stopwatch.0.elapsed_secs() < 600000.0 {format!("{:.0} m", (stopwatch.0.elapsed_secs() / 60.0).floor())}
-			else {format!("You win!")};
+			if elapsed < 60.0 {locale.format("unit.seconds", &[format!("{:.2}", elapsed)])}
+			else if elapsed < 6000.0 {locale.format("unit.minutes_seconds", &[format!("{:.0}", (elapsed / 60.0).floor()), format!("{:.0}", elapsed % 60.0)])}
+			else if elapsed < 600000.0 {locale.format("unit.minutes", &[format!("{:.0}", (elapsed / 60.0).floor())])}
+			else {locale.get("reactor.stopwatch_overflow")};
+	}
+}
+
+// Lists each of the goal's sub-goals under the goal text
+// with the number of molecules currently counted for it
+fn update_goal_progress(
+	asset_server: Res<AssetServer>,
+	settings: Res<Settings>,
+	registry: Res<MoleculeRegistry>,
+	locale: Res<Locale>,
+	attempt: Option<Res<LevelAttempt>>,
+	mut goal_text_query: Query<&mut Text, With<GoalText>>,
+) {
+	let Some(attempt) = attempt else {
+		return;
+	};
+	let sub_goals = attempt.goal.sub_goals();
+	for mut text in goal_text_query.iter_mut() {
+		text.sections.truncate(1);
+		for ((chamber, goal), progress) in sub_goals.iter().zip(attempt.sub_goals.iter()) {
+			text.sections.push(TextSection::new(
+				format!("\n{}", goal.describe(*chamber, *progress, &registry, &locale)),
+				get_goal_progress_text_style(&asset_server, &settings, progress.met),
+			));
+		}
+	}
+}
+
+// Colours the cost and stopwatch boxes as the level's cost
+// and time limits get closer, using the simulation's counts
+fn update_limit_boxes(
+	attempt: Option<Res<LevelAttempt>>,
+	mut cost_box_query: Query<&mut Sprite, (With<CostBox>, Without<StopwatchBox>)>,
+	mut stopwatch_box_query: Query<&mut Sprite, (With<StopwatchBox>, Without<CostBox>)>,
+) {
+	let Some(attempt) = attempt else {
+		return;
+	};
+	if let Some(cost_limit) = attempt.cost_limit {
+		for mut sprite in cost_box_query.iter_mut() {
+			// Reaching the limit exactly is allowed, only going over fails
+			sprite.color = get_limit_color(if attempt.cost > cost_limit {1.0} else {(attempt.cost as f32 / cost_limit as f32).min(0.99)});
+		}
+	}
+	if let Some(time_limit) = attempt.time_limit {
+		for mut sprite in stopwatch_box_query.iter_mut() {
+			sprite.color = get_limit_color(attempt.tick as f32 * PHYSICS_TIMESTEP / time_limit);
+		}
 	}
 }
 
 // Update the cost to track cost spent on a level
 fn update_cost(
 	current_cost: Res<CurrentCost>,
+	locale: Res<Locale>,
 	mut cost_text_query: Query<(&mut Text, With<CostText>)>,
 ) {
 	for (mut text, _) in cost_text_query.iter_mut() {
 		text.sections[0].value = 
-		if current_cost.0 < 1000 {format!("{} c", current_cost.0)}
-		else if current_cost.0 < 10000 {format!("{:.3} kc", current_cost.0 as f32/1000.0)}
-		else if current_cost.0 < 100000 {format!("{:.2} kc", current_cost.0 as f32/1000.0)}
-		else if current_cost.0 < 1000000 {format!("{:.1} kc", current_cost.0 as f32/1000.0)}
-		else if current_cost.0 < 10000000 {format!("{:.3} Mc", current_cost.0 as f32/1000000.0)}
-		else if current_cost.0 < 100000000 {format!("{:.2} Mc", current_cost.0 as f32/1000000.0)}
-		else if current_cost.0 < 1000000000 {format!("{:.1} Mc", current_cost.0 as f32/1000000.0)}
-		else {format!("Expensive")};
+		if current_cost.0 < 1000 {locale.format("unit.cost", &[current_cost.0.to_string()])}
+		else if current_cost.0 < 10000 {locale.format("unit.kilo_cost", &[format!("{:.3}", current_cost.0 as f32/1000.0)])}
+		else if current_cost.0 < 100000 {locale.format("unit.kilo_cost", &[format!("{:.2}", current_cost.0 as f32/1000.0)])}
+		else if current_cost.0 < 1000000 {locale.format("unit.kilo_cost", &[format!("{:.1}", current_cost.0 as f32/1000.0)])}
+		else if current_cost.0 < 10000000 {locale.format("unit.mega_cost", &[format!("{:.3}", current_cost.0 as f32/1000000.0)])}
+		else if current_cost.0 < 100000000 {locale.format("unit.mega_cost", &[format!("{:.2}", current_cost.0 as f32/1000000.0)])}
+		else if current_cost.0 < 1000000000 {locale.format("unit.mega_cost", &[format!("{:.1}", current_cost.0 as f32/1000000.0)])}
+		else {locale.get("reactor.expensive")};
 	}
 }
 
@@ -341,8 +407,12 @@ fn spawn_reactor_buttons(
 	mut commands: Commands,
 	mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 	level: Res<SelectedLevel>,
+	levels: Levels,
 	asset_server: Res<AssetServer>,
+	registry: Res<MoleculeRegistry>,
 	selected_palette: Res<SelectedPalette>,
+	settings: Res<Settings>,
+	locale: Res<Locale>,
 ) {
 	// Spawn molecule select buttons
 	for j in 0..6 {
@@ -353,7 +423,7 @@ fn spawn_reactor_buttons(
 					width: 75.0,
 					height: 75.0,
 				},
-				enabled: get_available_molecules(level.0)[i + j*3],
+				enabled: levels.get(level.0).available_molecules.contains(&(i + j*3)),
 				idle_color: Color::hex("EDD6AD").unwrap(),
 				hovered_color: Color::hex("CDB68D").unwrap(),
 				disabled_color: Color::hex("9D865D").unwrap(),
@@ -363,7 +433,7 @@ fn spawn_reactor_buttons(
 			let dim = button.dimensions;
 			let en = button.enabled;
 
-			let texture_handle = asset_server.load(get_molecule_path(i + 3*j));
+			let texture_handle = asset_server.load(registry.path(i + 3*j));
 			let texture_atlas = TextureAtlas::from_grid(texture_handle, Vec2::new(32.0, 32.0), 4, 2, None, None);
 			let texture_atlas_handle = texture_atlases.add(texture_atlas);
 
@@ -376,7 +446,7 @@ fn spawn_reactor_buttons(
 				},
 				..Default::default()
 				},
-				ButtonEffect::ReactorButton(ReactorButton::SelectMolecule(i + j*3)),
+				ButtonEffect::Reactor(ReactorButton::SelectMolecule(i + j*3)),
 				button,
 				DespawnOnExitGameState,
 				Name::new(format!("Molecule Select Button {}", i + j*3))
@@ -386,7 +456,7 @@ fn spawn_reactor_buttons(
 					texture_atlas: texture_atlas_handle.clone(),
 					transform: Transform::from_xyz(loc.x, loc.y, loc.z + 1.0),
 					sprite: TextureAtlasSprite {
-						color: get_molecule_color(i + j*3, selected_palette.0),
+						color: registry.color(i + j*3, selected_palette.0),
 						index: 1,
 						custom_size: Some(Vec2::new(dim.width, dim.height)), 
 						..Default::default()
@@ -402,6 +472,17 @@ fn spawn_reactor_buttons(
 				DespawnOnExitGameState,
 				Name::new(format!("Molecule Select Button Sprite {}", i + j*3))
 			));
+			commands.spawn((Text2dBundle {
+					transform: Transform::from_xyz(loc.x, loc.y, loc.z + 1.5),
+					text: Text::from_section(registry.glyph(i + j*3), get_glyph_text_style(&asset_server, 30.0, registry.color(i + j*3, selected_palette.0)))
+						.with_alignment(TextAlignment::Center),
+					visibility: if settings.glyphs_enabled {Visibility::Inherited} else {Visibility::Hidden},
+					..Default::default()
+				},
+				MoleculeGlyph(i + j*3),
+				DespawnOnExitGameState,
+				Name::new(format!("Molecule Select Button Glyph {}", i + j*3))
+			));
 			if !en {
 				let texture_handle = asset_server.load("moles/lock.png".to_string());
 				let texture_atlas = TextureAtlas::from_grid(texture_handle, Vec2::new(32.0, 32.0), 4, 2, None, None);
@@ -452,14 +533,14 @@ fn spawn_reactor_buttons(
 			},
 			..Default::default()
 		},
-		ButtonEffect::ReactorButton(ReactorButton::ExitReactor),
+		ButtonEffect::Reactor(ReactorButton::ExitReactor),
 		button,
 		DespawnOnExitGameState,
 	)).with_children(|parent| {
 		parent
 			.spawn((Text2dBundle {
 				transform: Transform::from_xyz(0.0, -2.5, 10.0,),
-				text: Text::from_section(format!("Exit"), get_button_text_style(&asset_server))
+				text: Text::from_section(locale.get("button.exit"), get_button_text_style(&asset_server, &settings))
 					.with_alignment(TextAlignment::Center),
 				..Default::default()
 			},
@@ -487,14 +568,14 @@ fn spawn_reactor_buttons(
 			},
 			..Default::default()
 		},
-		ButtonEffect::ReactorButton(ReactorButton::PauseLevel),
+		ButtonEffect::Reactor(ReactorButton::PauseLevel),
 		button,
 		DespawnOnExitGameState,
 	)).with_children(|parent| {
 		parent
 			.spawn((Text2dBundle {
 				transform: Transform::from_xyz(0.0, -2.5, 10.0,),
-				text: Text::from_section(format!("Pause"), get_button_text_style(&asset_server))
+				text: Text::from_section(locale.get("button.pause"), get_button_text_style(&asset_server, &settings))
 					.with_alignment(TextAlignment::Center),
 				..Default::default()
 			},
@@ -522,14 +603,14 @@ fn spawn_reactor_buttons(
 			},
 			..Default::default()
 		},
-		ButtonEffect::ReactorButton(ReactorButton::RestartLevel),
+		ButtonEffect::Reactor(ReactorButton::RestartLevel),
 		button,
 		DespawnOnExitGameState,
 	)).with_children(|parent| {
 		parent
 			.spawn((Text2dBundle {
 				transform: Transform::from_xyz(0.0, -2.5, 10.0,),
-				text: Text::from_section(format!("Replay"), get_button_text_style(&asset_server))
+				text: Text::from_section(locale.get("button.replay"), get_button_text_style(&asset_server, &settings))
 					.with_alignment(TextAlignment::Center),
 				..Default::default()
 			},
@@ -587,10 +668,11 @@ fn spawn_reactor_levers(
 fn handle_levers(
 	window_query: Query<&Window>,
 	ortho_size: Res<OrthoSize>,
-	mouse: Res<Input<MouseButton>>,
+	actions: ActionInput,
 	lever_query: Query<(Entity, &Transform, (With<LeverInfo>, Without<SelectedLever>))>,
 	mut commands: Commands,
-	mut reactor_condition_query: Query<(&mut ReactorCondition, With<SelectedReactor>)>,
+	mut input_queue: ResMut<InputQueue>,
+	reactor_condition_query: Query<(&ReactorInfo, &ReactorCondition, With<SelectedReactor>)>,
 	mut selected_lever_query: Query<(Entity, &mut Transform, &LeverInfo, With<SelectedLever>)>,
 ) {
 	// Get the current window, and the cursor position scaled 
@@ -601,7 +683,7 @@ fn handle_levers(
 			ortho_size.width * (p.x / w.width() - 0.5), 
 			-ortho_size.height * (p.y / w.height() - 0.5)
 		);
-		if mouse.just_pressed(MouseButton::Left) {
+		if actions.just_pressed(InputAction::DragLever) {
 			for (entity, transform, _) in lever_query.iter() {
 				if (p.x - transform.translation.x).abs() < LEVER_WIDTH / 2.0 
 				&& (p.y - transform.translation.y).abs() < LEVER_HEIGHT / 2.0 {
@@ -609,7 +691,7 @@ fn handle_levers(
 				}
 			}
 		}
-		if !mouse.pressed(MouseButton::Left) {
+		if !actions.pressed(InputAction::DragLever) {
 			for (entity, _, _, _) in selected_lever_query.iter() {
 				commands.entity(entity).remove::<SelectedLever>();
 			}
@@ -617,549 +699,217 @@ fn handle_levers(
 		for (_, mut transform, info, _) in selected_lever_query.iter_mut() {
 			transform.translation.y = p.y.clamp(info.min_height, info.max_height);
 			let percent = (transform.translation.y - info.min_height)/(info.max_height - info.min_height);
-			for (mut condition, _) in reactor_condition_query.iter_mut() {
-				if info.lever_type == 0 {
-					condition.temperature = percent;
+			// The new conditions take effect on the next simulation step
+			for (r_info, condition, _) in reactor_condition_query.iter() {
+				let (temperature, pressure) = if info.lever_type == 0 {
+					(percent, condition.pressure)
 				} else {
-					condition.pressure = percent;
+					(condition.temperature, percent)
+				};
+				if temperature != condition.temperature || pressure != condition.pressure {
+					input_queue.0.push(ReactorInput::SetConditions{
+						reactor_id: r_info.reactor_id,
+						temperature,
+						pressure,
+					});
 				}
 			}
 		}
 	}
 }
 
-// Check for collisions between intake connections and molecules
-// and if so emit a connection event
-fn intake_connections(
+// Spawns the reactors, connections and starting molecules for the
+// given level through the simulation, as well as a launch tube for
+// each input chamber
+fn spawn_reactors(
 	mut commands: Commands,
-	mut ev_w_connection: EventWriter<ConnectionEvent>,
-	selected_molecule_query: Query<(Entity, &Transform, &MoleculeInfo, &Velocity, &ReactorInfo, With<SelectedMolecule>)>,
-	molecule_query: Query<(Entity, &Transform, &MoleculeInfo, &Velocity, &ReactorInfo, (With<Molecule>, Without<SelectedMolecule>))>,
-	connection_query: Query<(&Transform, &Connection)>,
+	asset_server: Res<AssetServer>,
+	registry: Res<MoleculeRegistry>,
+	mut sim_rng: ResMut<SimRng>,
+	level: Res<SelectedLevel>,
+	levels: Levels,
 ) {
-	for (c_transform, connection) in connection_query.iter() {
-		for (entity, m_transform, m_info, velocity, r_info, _) in molecule_query.iter() {
-			if connection.reactor_id == r_info.reactor_id {
-				if (c_transform.translation.xy() - m_transform.translation.xy()).length() < CONNECTION_IN_WIDTH 
-				&& connection.intake
-				&& connection.filter[m_info.index] {
-					ev_w_connection.send(ConnectionEvent{
-						connection_id: connection.connection_id,
-						m_info: *m_info,
-						r_info: *r_info,
-						velocity: velocity.0,
-						selected: false,
-					});
-					commands.entity(entity).despawn_recursive();
-				}
-			}
-		}
-		for (entity, m_transform, m_info, velocity, r_info, _) in selected_molecule_query.iter() {
-			if connection.reactor_id == r_info.reactor_id {
-				if (c_transform.translation.xy() - m_transform.translation.xy()).length() < CONNECTION_IN_WIDTH 
-				&& connection.intake 
-				&& connection.filter[m_info.index] {
-					ev_w_connection.send(ConnectionEvent{
-						connection_id: connection.connection_id,
-						m_info: *m_info,
-						r_info: *r_info,
-						velocity: velocity.0,
-						selected: true,
-					});
-					commands.entity(entity).despawn_recursive();
-				}
-			}
+	let level_definition = levels.get(level.0);
+	spawn_level(&mut commands, level.0, level_definition, &registry, &mut sim_rng);
+	for (i, reactor_definition) in level_definition.reactors.iter().enumerate() {
+		if reactor_definition.input_chamber {
+			commands
+				.spawn((SpriteBundle {
+					transform: Transform::from_translation(reactor_definition.reactor_type.wall_point(Vec2::Y).extend(910.0)),
+					texture: asset_server.load("sprites/ui/launcher.png"),
+					sprite: Sprite {
+						//color: Color::DARK_GRAY,
+						custom_size: Some(Vec2::new(LAUNCH_TUBE_WIDTH, LAUNCH_TUBE_HEIGHT)),
+						..Default::default()
+					},
+					..Default::default()
+				},
+				LaunchTube{
+					id: i,
+					current_rotation: 0.0,
+					limits: reactor_definition.launch_tube_limits,
+				},
+				RenderLayers::layer(1),
+				DespawnOnExitGameState,
+				Name::new("Launch Tube"),
+			));
 		}
 	}
 }
 
-// Handles connection events by spawning a new molecule
-// at every outlet connection with a matching ID
-fn outlet_connections(
+// Gives reactors spawned by the simulation
+// a sprite matching their shape and size
+fn attach_reactor_visuals(
 	mut commands: Commands,
-	mut ev_r_connection: EventReader<ConnectionEvent>,
-	mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 	asset_server: Res<AssetServer>,
-	selected_palette: Res<SelectedPalette>,
-	connection_query: Query<(&Transform, &Connection)>,
-	reactor_query: Query<&ReactorInfo>,
+	reactor_query: Query<(Entity, &ReactorInfo), Added<ReactorCondition>>,
 ) {
-	for ev in ev_r_connection.iter() {
-		for (transform, connection) in connection_query.iter() {
-			if connection.connection_id == ev.connection_id && !connection.intake {
-				let texture_handle = asset_server.load(get_molecule_path(ev.m_info.index));
-				let texture_atlas = TextureAtlas::from_grid(texture_handle, Vec2::new(32.0, 32.0), 4, 2, None, None);
-				let texture_atlas_handle = texture_atlases.add(texture_atlas);
-
-				let mut r_info = ev.r_info;
-				for r_new_info in reactor_query.iter() {
-					if connection.reactor_id == r_new_info.reactor_id {
-						r_info = *r_new_info;
-					}
-				}
-
-				let mut prev_transform = *transform;
-				prev_transform.rotate_local_z((rand::random::<f32>() - 0.5) * 0.75_f32);
-				let direction = -prev_transform.local_y().xy().normalize();
-
-				let mut mole = commands
-					.spawn((SpriteSheetBundle {
-						transform: Transform::from_translation((transform.translation.xy() + -prev_transform.local_y().xy().normalize() * ev.m_info.radius * 1.01).extend(500.0)),
-						texture_atlas: texture_atlas_handle.clone(),
-						sprite: TextureAtlasSprite{
-							color: get_molecule_color(ev.m_info.index, selected_palette.0),
-							index: 0,
-							custom_size: Some(Vec2::new(ev.m_info.radius * 2.0, ev.m_info.radius * 2.0)),
-							..Default::default()
-						},
-						..Default::default()
-					},
-					r_info,
-					Molecule(get_molecule_lifetime(ev.m_info.index)),
-					ev.m_info,
-					ParticleTrail{
-						spawn_timer: Timer::from_seconds(PARTICLE_SPAWN_DELAY, TimerMode::Repeating),
-						duration: PARTICLE_DURATION,
-					},
-					Velocity(ev.velocity.length() * direction),
-					AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-					AnimationIndices{ 
-						first: 0, 
-						total: 8,
-					},
-					RenderLayers::layer(1),
-					DespawnOnExitGameState,
-					Name::new("Molecule")
-				));
-				if ev.selected {
-					mole.insert(SelectedMolecule);
-				}
-			}
-		}
+	for (entity, reactor) in reactor_query.iter() {
+		let (path, size) = match reactor.reactor_type {
+			ReactorType::Rectangle{dimensions, ..} => ("sprites/ui/rectangle.png", Vec2::new(dimensions.width, dimensions.height)),
+			ReactorType::Circle{radius, ..} => ("sprites/ui/circle.png", Vec2::new(radius*2.0, radius*2.0)),
+		};
+		let texture: Handle<Image> = asset_server.load(path);
+		commands.entity(entity).insert((
+			texture,
+			Sprite{
+				color: if reactor.product_chamber {Color::BISQUE} else {Color::GREEN}, 
+				custom_size: Some(size), 
+				..Default::default()
+			},
+			VisibilityBundle::default(),
+			RenderLayers::layer(1),
+			DespawnOnExitGameState,
+		));
 	}
 }
 
-// Spawns all the reactors for the given level and assigns them
-// a unique ID, as well as spawning launch tubes and connections
-fn spawn_reactors(
+// Gives connections spawned by the simulation their sprite, and
+// places an identifier beyond each intake showing the molecule
+// it belongs to
+fn attach_connection_visuals(
 	mut commands: Commands,
 	mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 	asset_server: Res<AssetServer>,
-	level: Res<SelectedLevel>,
+	registry: Res<MoleculeRegistry>,
 	selected_palette: Res<SelectedPalette>,
+	connection_query: Query<(Entity, &Connection, &Transform), Added<Connection>>,
+	reactor_query: Query<&ReactorInfo, With<ReactorCondition>>,
 ) {
-	let reactors = get_reactors(level.0);
-	for (i, reactor) in reactors.iter().enumerate() {
-		// Spawn entity with features common to both reactors
-		let mut r = commands
-			.spawn((SpriteBundle::default(),
-			*reactor,
-			ReactorCondition{
-				temperature: 0.0,
-				pressure: 0.0,
+	for (entity, connection, transform) in connection_query.iter() {
+		let color = registry.color(connection.connection_id, selected_palette.0);
+		let (path, width) = if connection.intake {("sprites/ui/connection_in.png", CONNECTION_IN_WIDTH)} else {("sprites/ui/connection_out.png", CONNECTION_OUT_WIDTH)};
+		let texture: Handle<Image> = asset_server.load(path);
+		commands.entity(entity).insert((
+			texture,
+			Sprite {
+				color,
+				custom_size: Some(Vec2::new(width, CONNECTION_HEIGHT)),
+				..Default::default()
 			},
+			VisibilityBundle::default(),
 			RenderLayers::layer(1),
 			DespawnOnExitGameState,
-			Name::new(format!("Reactor {}", i))
 		));
-		// Match reactor type and add additional features to specific reactor types
-		let z = 910.0;
-		match reactor.reactor_type {
-			ReactorType::Rectangle{origin, dimensions} => {
-				let texture: Handle<Image> = asset_server.load("sprites/ui/rectangle.png");
-				r.insert(texture);
-				r.insert(Transform::from_xyz(origin.x, origin.y, 10.0));
-				r.insert(Sprite{
-					color: if reactor.product_chamber {Color::BISQUE} else {Color::GREEN}, 
-					custom_size: Some(Vec2::new(dimensions.width, dimensions.height)), 
-					..Default::default()
-				});
-				if reactor.input_chamber {
-					commands
-						.spawn((SpriteBundle {
-							transform: Transform::from_xyz(origin.x, origin.y + dimensions.height / 2.0, z),
-							texture: asset_server.load("sprites/ui/launcher.png"),
-							sprite: Sprite {
-								//color: Color::DARK_GRAY,
-								custom_size: Some(Vec2::new(LAUNCH_TUBE_WIDTH, LAUNCH_TUBE_HEIGHT)),
-								..Default::default()
-							},
-							..Default::default()
-						},
-						LaunchTube{
-							id: i,
-							current_rotation: 0.0,
-							limits: get_launch_tube_limits(level.0, reactor.reactor_id),
-						},
-						RenderLayers::layer(1),
-						DespawnOnExitGameState,
-						Name::new("Launch Tube"),
-					));
-				}
-				for (index, location, velocity) in get_reactor_initialization(level.0, reactor.reactor_id) {
-					commands
-						.spawn((SpriteSheetBundle {
-							transform: Transform::from_xyz(
-								origin.x + location.x + rand::random::<f32>(),
-								origin.y + location.y + rand::random::<f32>(),
-								500.0,
-							),
-							texture_atlas: texture_atlases.add(TextureAtlas::from_grid(asset_server.load(get_molecule_path(index)), Vec2::new(32.0, 32.0), 4, 2, None, None)).clone(),
-							sprite: TextureAtlasSprite{
-								color: get_molecule_color(index, selected_palette.0),
-								index: 0,
-								custom_size: Some(Vec2::new(get_molecule_radius(index) * 2.0, get_molecule_radius(index) * 2.0)),
-								..Default::default()
-							},
-							..Default::default()
-						},
-						*reactor,
-						Molecule(get_molecule_lifetime(index)),
-						MoleculeInfo {
-							index: index,
-							reacted: false,
-							radius: get_molecule_radius(index),
-							mass: get_molecule_mass(index),
-						},
-						ParticleTrail{
-							spawn_timer: Timer::from_seconds(PARTICLE_SPAWN_DELAY, TimerMode::Repeating),
-							duration: PARTICLE_DURATION,
-						},
-						Velocity(velocity),
-						AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-						AnimationIndices{ 
-							first: 0, 
-							total: 8,
-						},
-						RenderLayers::layer(1),
-						DespawnOnExitGameState,
-						Name::new("Molecule")
-					));
-				}
-				for (direction, connection) in get_reactor_connections(level.0, reactor.reactor_id).0 {
-					let translation = Vec3::new(
-						origin.x + direction.x * dimensions.width / 2.0, 
-						origin.y + direction.y * dimensions.height / 2.0, 
-						z + connection.connection_id as f32
-					);
-					if connection.intake {
-						commands.spawn((SpriteBundle {
-							texture: asset_server.load("sprites/ui/connection_in.png"),
-							transform: Transform::from_translation(translation)
-							.with_rotation(Quat::from_rotation_z(if direction.y == 1.0 {0.0} else if direction.y == -1.0 {180.0_f32.to_radians()} else {if direction.x == 1.0 {-90.0_f32.to_radians()} else {90.0_f32.to_radians()}})),
-							sprite: Sprite {
-								color: {get_molecule_color(connection.connection_id, selected_palette.0)},
-								custom_size: Some(Vec2::new(CONNECTION_IN_WIDTH, CONNECTION_HEIGHT)),
-								..Default::default()
-							},
-							..Default::default()
-						},
-						connection,
-						RenderLayers::layer(1),
-						DespawnOnExitGameState,
-						Name::new("Connection In"),
-						));
-					}
-				}
-				for (direction, connection) in get_reactor_connections(level.0, reactor.reactor_id).0 {
-					let translation = Vec3::new(
-						origin.x + direction.x * dimensions.width / 2.0, 
-						origin.y + direction.y * dimensions.height / 2.0,
-						z + connection.connection_id as f32
-					);
-					if !connection.intake {
-						commands.spawn((SpriteBundle {
-							texture: asset_server.load("sprites/ui/connection_out.png"),
-							transform: Transform::from_translation(translation)
-							.with_rotation(Quat::from_rotation_arc(Vec3::Y, (translation.xy() - origin).normalize().extend(0.0))),
-							sprite: Sprite {
-								color: {get_molecule_color(connection.connection_id, selected_palette.0)},
-								custom_size: Some(Vec2::new(CONNECTION_OUT_WIDTH, CONNECTION_HEIGHT)),
-								..Default::default()
-							},
-							..Default::default()
-						},
-						connection,
-						RenderLayers::layer(1),
-						DespawnOnExitGameState,
-						Name::new("Connection Out"),
-						));
-					}
-				}
-				for (direction, connection) in get_reactor_connections(level.0, reactor.reactor_id).0 {
-					let translation = Vec3::new(
-						origin.x + direction.x * dimensions.width / 2.0, 
-						origin.y + direction.y * dimensions.height / 2.0, 
-						z + connection.connection_id as f32
-					);
-					if connection.intake {
-						commands.spawn((SpriteSheetBundle {
-							texture_atlas: texture_atlases.add(TextureAtlas::from_grid(asset_server.load(get_molecule_path(connection.connection_id)), Vec2::new(32.0, 32.0), 4, 2, None, None)).clone(), 
-							transform: Transform::from_rotation(Quat::from_rotation_z(if direction.y == 1.0 {0.0} else if direction.y == -1.0 {180.0_f32.to_radians()} else {if direction.x == 1.0 {-90.0_f32.to_radians()} else {90.0_f32.to_radians()}}))
-							.with_translation(if direction.y == 1.0 {translation + Vec3:: new(0.0,250.0,0.0)} else if direction.y == -1.0 {translation - Vec3:: new(0.0,250.0,0.0)} else {if direction.x == 1.0 {translation + Vec3:: new(250.0,0.0,0.0)} else {translation - Vec3:: new(250.0,0.0,0.0)}}),
-							sprite: TextureAtlasSprite{
-								color: {get_molecule_color(connection.connection_id, selected_palette.0)},
-								index: 0,
-								custom_size: Some(Vec2::new(144.0, 144.0)),
-								..Default::default()
-							},
-							..Default::default()
-						},
-						RenderLayers::layer(1),
-						DespawnOnExitGameState,
-						Name::new("Molecule Identifier"),
-						));
-					}
-				}
-			},
-			ReactorType::Circle{origin, radius} => {
-				let texture: Handle<Image> = asset_server.load("sprites/ui/circle.png");
-				r.insert(texture);
-				r.insert(Transform::from_xyz(origin.x, origin.y, 10.0));
-				r.insert(Sprite{
-					color: if reactor.product_chamber {Color::BISQUE} else {Color::GREEN}, 
-					custom_size: Some(Vec2::new(radius*2.0, radius*2.0)), 
+		if connection.intake {
+			let size = match reactor_query.iter().find(|r_info| r_info.reactor_id == connection.reactor_id) {
+				Some(ReactorInfo{reactor_type: ReactorType::Circle{..}, ..}) => CONNECTION_HEIGHT,
+				_ => 144.0,
+			};
+			commands.spawn((SpriteSheetBundle {
+				texture_atlas: texture_atlases.add(TextureAtlas::from_grid(asset_server.load(registry.path(connection.connection_id)), Vec2::new(32.0, 32.0), 4, 2, None, None)),
+				transform: Transform::from_translation(transform.translation + transform.rotation * Vec3::new(0.0, 250.0, 0.0))
+					.with_rotation(transform.rotation),
+				sprite: TextureAtlasSprite{
+					color,
+					index: 0,
+					custom_size: Some(Vec2::new(size, size)),
 					..Default::default()
-				});
-				if reactor.input_chamber {
-					commands
-						.spawn((SpriteBundle {
-							transform: Transform::from_xyz(origin.x, origin.y + radius, z),
-							texture: asset_server.load("sprites/ui/launcher.png"),
-							sprite: Sprite {
-								//color: Color::DARK_GRAY,
-								custom_size: Some(Vec2::new(LAUNCH_TUBE_WIDTH, LAUNCH_TUBE_HEIGHT)),
-								..Default::default()
-							},
-							..Default::default()
-						},
-						LaunchTube{
-							id: i,
-							current_rotation: 0.0,
-							limits: get_launch_tube_limits(level.0, reactor.reactor_id),
-						},
-						RenderLayers::layer(1),
-						DespawnOnExitGameState,
-						Name::new("Launch Tube"),
-					));
-				}
-				for (index, location, velocity) in get_reactor_initialization(level.0, reactor.reactor_id) {
-					commands
-						.spawn((SpriteSheetBundle {
-							transform: Transform::from_xyz(
-								origin.x + location.x + rand::random::<f32>(),
-								origin.y + location.y + rand::random::<f32>(),
-								500.0,
-							),
-							texture_atlas: texture_atlases.add(TextureAtlas::from_grid(asset_server.load(get_molecule_path(index)), Vec2::new(32.0, 32.0), 4, 2, None, None)).clone(),
-							sprite: TextureAtlasSprite{
-								color: get_molecule_color(index, selected_palette.0),
-								index: 0,
-								custom_size: Some(Vec2::new(get_molecule_radius(index) * 4.0, get_molecule_radius(index) * 4.0)),
-								..Default::default()
-							},
-							..Default::default()
-						},
-						*reactor,
-						Molecule(get_molecule_lifetime(index)),
-						MoleculeInfo {
-							index: index,
-							reacted: false,
-							radius: get_molecule_radius(index),
-							mass: get_molecule_mass(index),
-						},
-						ParticleTrail{
-							spawn_timer: Timer::from_seconds(PARTICLE_SPAWN_DELAY, TimerMode::Repeating),
-							duration: PARTICLE_DURATION,
-						},
-						Velocity(velocity),
-						AnimationTimer(Timer::from_seconds(0.1, TimerMode::Repeating)),
-						AnimationIndices{ 
-							first: 0, 
-							total: 8,
-						},
-						RenderLayers::layer(1),
-						DespawnOnExitGameState,
-						Name::new("Molecule")
-					));
-				}
-				for (mut direction, connection) in get_reactor_connections(level.0, reactor.reactor_id).0 {
-					direction = direction.normalize();
-					let translation = Vec3::new(
-						origin.x + direction.x * radius, 
-						origin.y + direction.y * radius, 
-						z + connection.connection_id as f32
-					);
-					if connection.intake {
-						println!("INPUT CIRC");
-						commands.spawn((SpriteBundle {
-							texture: asset_server.load("sprites/ui/connection_in.png"),
-							transform: Transform::from_translation(translation)
-							.with_rotation(Quat::from_rotation_z(if direction.y == 1.0 {0.0} else if direction.y == -1.0 {180.0_f32.to_radians()} else {if direction.x == 1.0 {-90.0_f32.to_radians()} else {90.0_f32.to_radians()}})),
-							sprite: Sprite {
-								color: {get_molecule_color(connection.connection_id, selected_palette.0)},
-								custom_size: Some(Vec2::new(CONNECTION_IN_WIDTH, CONNECTION_HEIGHT)),
-								..Default::default()
-							},
-							..Default::default()
-						},
-						connection,
-						RenderLayers::layer(1),
-						DespawnOnExitGameState,
-						Name::new("Connection In"),
-						));
-					}
-				}
-				for (mut direction, connection) in get_reactor_connections(level.0, reactor.reactor_id).0 {
-					direction = direction.normalize();
-					let translation = Vec3::new(
-						origin.x + direction.x * radius, 
-						origin.y + direction.y * radius, 
-						z + connection.connection_id as f32
-					);
-					if !connection.intake {
-						println!("OUTPUT CIRC");
-						commands.spawn((SpriteBundle {
-							texture: asset_server.load("sprites/ui/connection_out.png"),
-							transform: Transform::from_translation(translation)
-							.with_rotation(Quat::from_rotation_arc(Vec3::Y, (translation.xy() - origin).normalize().extend(0.0))),
-							sprite: Sprite {
-								color: {get_molecule_color(connection.connection_id, selected_palette.0)},
-								custom_size: Some(Vec2::new(CONNECTION_OUT_WIDTH, CONNECTION_HEIGHT)),
-								..Default::default()
-							},
-							..Default::default()
-						},
-						connection,
-						RenderLayers::layer(1),
-						DespawnOnExitGameState,
-						Name::new("Connection Out"),
-						));
-					}
-				}
-				for (mut direction, connection) in get_reactor_connections(level.0, reactor.reactor_id).0 {
-					direction = direction.normalize();
-					let translation = Vec3::new(
-						origin.x + direction.x * radius, 
-						origin.y + direction.y * radius, 
-						z + connection.connection_id as f32
-					);
-					if connection.intake {
-						commands.spawn((SpriteSheetBundle {
-							texture_atlas: texture_atlases.add(TextureAtlas::from_grid(asset_server.load(get_molecule_path(connection.connection_id)), Vec2::new(32.0, 32.0), 4, 2, None, None)).clone(), 
-							transform: Transform::from_rotation(Quat::from_rotation_z(if direction.y == 1.0 {0.0} else if direction.y == -1.0 {180.0_f32.to_radians()} else {if direction.x == 1.0 {-90.0_f32.to_radians()} else {90.0_f32.to_radians()}}))
-							.with_translation(if direction.y == 1.0 {translation + Vec3:: new(0.0,250.0,0.0)} else if direction.y == -1.0 {translation - Vec3:: new(0.0,250.0,0.0)} else {if direction.x == 1.0 {translation + Vec3:: new(250.0,0.0,0.0)} else {translation - Vec3:: new(250.0,0.0,0.0)}}),
-							sprite: TextureAtlasSprite{
-								color: {get_molecule_color(connection.connection_id, selected_palette.0)},
-								index: 0,
-								custom_size: Some(Vec2::new(CONNECTION_HEIGHT, CONNECTION_HEIGHT)),
-								..Default::default()
-							},
-							..Default::default()
-						},
-						RenderLayers::layer(1),
-						DespawnOnExitGameState,
-						Name::new("Molecule Identifier"),
-						));
-					}
-				}
+				},
+				..Default::default()
 			},
+			RenderLayers::layer(1),
+			DespawnOnExitGameState,
+			Name::new("Molecule Identifier"),
+			));
 		}
 	}
 }
 
-// Checks to see if the target amount of molecules of the
-// desired type are held in the product reactor and if so
-// triggers a win screen popup
+// Shows the win countdown while the simulation reports the goal
+// as held, and once the level is complete saves the result and
+// triggers a win screen popup. Failed levels get a fail screen
 fn check_product_reactor(
 	mut pkv: ResMut<PkvStore>,
 	mut ev_w_popup: EventWriter<PopupEvent>,
+	mut ev_r_level_complete: EventReader<LevelCompleteEvent>,
+	mut ev_r_level_failed: EventReader<LevelFailedEvent>,
 	mut next_state: ResMut<NextState<PauseState>>,
-	mut win_countdown: ResMut<WinCountdown>,
 	mut win_countdown_text_query: Query<(&mut Text, &mut Visibility, With<WinCountdownText>)>,
 	asset_server: Res<AssetServer>,
 	current_cost: Res<CurrentCost>,
-	reactor_query: Query<(&ReactorInfo, With<ReactorCondition>)>,
-	molecule_query: Query<(&MoleculeInfo, &ReactorInfo, With<Molecule>)>,
-	stopwatch_query: Query<&StopwatchText>,
+	attempt: Option<Res<LevelAttempt>>,
 	selected_level: Res<SelectedLevel>,
-	time: Res<Time>,
+	selected_profile: Res<SelectedProfile>,
+	levels: Levels,
+	locale: Res<Locale>,
+	playback: Option<Res<ReplayPlayback>>,
 ) {
-	for (r_info, _) in reactor_query.iter() {
-		if r_info.product_chamber {
-			let win_condition = get_level_goal(selected_level.0);
-			let mut desired_count = 0;
-			let mut condition_passed = false;
-			match win_condition {
-				WinCondition::GreaterThan(desired_quantity, desired_molecule) => {
-					for (m_info, m_r_info, _) in molecule_query.iter() {
-						if r_info.reactor_id == m_r_info.reactor_id && m_info.index == desired_molecule {
-							desired_count += 1;
-							if desired_count >= desired_quantity {
-								condition_passed = true;
-								break;
-							}
-						}
-					}
-				},
-				WinCondition::LessThan(desired_quantity, desired_molecule) => {
-					condition_passed = true;
-					for (m_info, m_r_info, _) in molecule_query.iter() {
-						if r_info.reactor_id == m_r_info.reactor_id && m_info.index == desired_molecule {
-							desired_count += 1;
-							if desired_count >= desired_quantity {
-								condition_passed = false;
-								break;
-							}
-						}
-					}
-				},
-			}
-
-			if condition_passed {
-				win_countdown.0.tick(time.delta());
-				for (mut text, mut visibility, _) in win_countdown_text_query.iter_mut() {
-					*visibility = Visibility::Visible;
-					let time_left = 3.0 - 3.0 * win_countdown.0.percent();
-					text.sections[0].value = format!("Reaction\nComplete in:\n{:.2}", time_left);
-				}
-				if win_countdown.0.just_finished() {
-					for (_, mut visibility, _) in win_countdown_text_query.iter_mut() {
-						*visibility = Visibility::Hidden;
-					}
-					let mut prev_best_cost = 999999;
-					let mut prev_best_time = 999999.0;
-					let mut current_time = 999999.0;
-					if let Ok(mut save_data) = pkv.get::<SaveData>("save_data") {
-						prev_best_cost = save_data.best_costs[selected_level.0];
-						if current_cost.0 < prev_best_cost {
-							save_data.best_costs[selected_level.0] = current_cost.0;
-						}
-						for stopwatch in stopwatch_query.iter() {
-							prev_best_time = save_data.best_times[selected_level.0];
-							current_time = stopwatch.0.elapsed_secs();
-							if current_time < prev_best_time {
-								save_data.best_times[selected_level.0] = current_time;
-							}
-						}
-						save_data.levels_unlocked[selected_level.0 + 1] = true;
-						pkv.set("save_data", &save_data)
-							.expect("Unable to save data");
-					}
-					next_state.set(PauseState::Paused);
-					ev_w_popup.send(PopupEvent{ 
-						origin: Vec2::new(0.0, 0.0), 
-						image: asset_server.load("sprites/popup/logbook_base.png"),
-						alpha: 1.0,
-						popup_type: PopupType::WinScreen(prev_best_time, current_time, prev_best_cost, current_cost.0),
-					});
-				}
+	if let Some(attempt) = attempt.as_ref() {
+		for (mut text, mut visibility, _) in win_countdown_text_query.iter_mut() {
+			if attempt.goal_met && !attempt.complete {
+				*visibility = Visibility::Visible;
+				let time_left = attempt.win_countdown.remaining_secs();
+				text.sections[0].value = locale.format("reactor.countdown", &[format!("{:.2}", time_left)]);
 			} else {
-				win_countdown.0.reset();
-				for (_, mut visibility, _) in win_countdown_text_query.iter_mut() {
-					*visibility = Visibility::Hidden;
+				*visibility = Visibility::Hidden;
+			}
+		}
+	}
+	for _ in ev_r_level_complete.iter() {
+		let mut prev_best_cost = 999999;
+		let mut prev_best_time = 999999.0;
+		let mut current_time = 999999.0;
+		let mut stars = 0;
+		if let Ok(mut save_data) = pkv.get::<SaveData>(get_profile_key(selected_profile.0)) {
+			prev_best_cost = save_data.best_costs[selected_level.0];
+			if current_cost.0 < prev_best_cost {
+				save_data.best_costs[selected_level.0] = current_cost.0;
+			}
+			// Timed by the simulation's steps rather than the stopwatch,
+			// so the time matches the one the replay verifier gives
+			if let Some(attempt) = attempt.as_ref() {
+				prev_best_time = save_data.best_times[selected_level.0];
+				current_time = attempt.tick as f32 * PHYSICS_TIMESTEP;
+				if current_time < prev_best_time {
+					save_data.best_times[selected_level.0] = current_time;
 				}
 			}
+			stars = levels.get(selected_level.0).stars(current_time, current_cost.0);
+			save_data.stars.resize(NUMBER_OF_LEVELS, 0);
+			save_data.stars[selected_level.0] = save_data.stars[selected_level.0].max(stars);
+			save_data.levels_unlocked[selected_level.0 + 1] = true;
+			// Watching a replay does not count towards progress
+			if playback.is_none() {
+				pkv.set(get_profile_key(selected_profile.0), &save_data)
+					.expect("Unable to save data");
+			}
 		}
+		next_state.set(PauseState::Paused);
+		ev_w_popup.send(PopupEvent{ 
+			origin: Vec2::new(0.0, 0.0), 
+			image: asset_server.load("sprites/popup/logbook_base.png"),
+			alpha: 1.0,
+			popup_type: PopupType::WinScreen(prev_best_time, current_time, prev_best_cost, current_cost.0, stars),
+		});
+	}
+	for ev in ev_r_level_failed.iter() {
+		next_state.set(PauseState::Paused);
+		ev_w_popup.send(PopupEvent{
+			origin: Vec2::new(0.0, 0.0),
+			image: asset_server.load("sprites/popup/logbook_base.png"),
+			alpha: 1.0,
+			popup_type: PopupType::FailScreen(ev.0),
+		});
 	}
 }
\ No newline at end of file
@@ -0,0 +1,142 @@
+// Import Bevy game engine essentials
+use bevy::{prelude::*, asset::{AssetLoader, LoadContext, LoadedAsset}, utils::BoxedFuture};
+// Import components, resources, and events
+use crate::components::*;
+
+// Plugin for loading the molecule catalogue and reaction
+// table, keeping their resources in sync with the files
+pub struct RegistryPlugin;
+
+impl Plugin for RegistryPlugin {
+    fn build(&self, app: &mut App) {
+        app
+			.add_asset::<MoleculeRegistry>()
+			.init_asset_loader::<MoleculeRegistryLoader>()
+			.add_asset::<ReactionTable>()
+			.init_asset_loader::<ReactionTableLoader>()
+			.add_systems(Startup, (
+				load_molecule_registry,
+				load_reaction_table,
+			))
+			.add_systems(Update, (
+				update_molecule_registry,
+				update_reaction_table,
+			))
+		;
+	}
+}
+
+// Reads the molecule catalogue written in RON, found at
+// assets/data/molecules.catalogue.ron
+#[derive(Default)]
+pub struct MoleculeRegistryLoader;
+
+impl AssetLoader for MoleculeRegistryLoader {
+	fn load<'a>(
+		&'a self,
+		bytes: &'a [u8],
+		load_context: &'a mut LoadContext,
+	) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+		Box::pin(async move {
+			let registry = MoleculeRegistry::from_ron(bytes)
+				.map_err(|error| bevy::asset::Error::msg(format!("{}: {}", load_context.path().display(), error)))?;
+			load_context.set_default_asset(LoadedAsset::new(registry));
+			Ok(())
+		})
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["catalogue.ron"]
+	}
+}
+
+// Reads the reaction table written in RON, found at
+// assets/data/reactions.table.ron
+#[derive(Default)]
+pub struct ReactionTableLoader;
+
+impl AssetLoader for ReactionTableLoader {
+	fn load<'a>(
+		&'a self,
+		bytes: &'a [u8],
+		load_context: &'a mut LoadContext,
+	) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+		Box::pin(async move {
+			let table = ReactionTable::from_ron(bytes)
+				.map_err(|error| bevy::asset::Error::msg(format!("{}: {}", load_context.path().display(), error)))?;
+			load_context.set_default_asset(LoadedAsset::new(table));
+			Ok(())
+		})
+	}
+
+	fn extensions(&self) -> &[&str] {
+		&["table.ron"]
+	}
+}
+
+fn load_molecule_registry(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+) {
+	commands.insert_resource(MoleculeRegistryHandle(asset_server.load(get_molecule_registry_path())));
+}
+
+// Copies the catalogue into the resource once loaded,
+// and again whenever the file is hot reloaded
+fn update_molecule_registry(
+	handle: Res<MoleculeRegistryHandle>,
+	catalogues: Res<Assets<MoleculeRegistry>>,
+	mut registry: ResMut<MoleculeRegistry>,
+	mut ev_r_asset: EventReader<AssetEvent<MoleculeRegistry>>,
+) {
+	for ev in ev_r_asset.iter() {
+		match ev {
+			AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed } => {
+				if *changed == handle.0 {
+					if let Some(catalogue) = catalogues.get(&handle.0) {
+						*registry = catalogue.clone();
+					}
+				}
+			}
+			AssetEvent::Removed { .. } => {}
+		}
+	}
+}
+
+fn load_reaction_table(
+	mut commands: Commands,
+	asset_server: Res<AssetServer>,
+) {
+	commands.insert_resource(ReactionTableHandle(asset_server.load(get_reaction_table_path())));
+}
+
+fn update_reaction_table(
+	handle: Res<ReactionTableHandle>,
+	tables: Res<Assets<ReactionTable>>,
+	mut reactions: ResMut<ReactionTable>,
+	mut ev_r_asset: EventReader<AssetEvent<ReactionTable>>,
+) {
+	for ev in ev_r_asset.iter() {
+		match ev {
+			AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed } => {
+				if *changed == handle.0 {
+					if let Some(table) = tables.get(&handle.0) {
+						*reactions = table.clone();
+					}
+				}
+			}
+			AssetEvent::Removed { .. } => {}
+		}
+	}
+}
+
+impl MoleculeColor for MoleculeRegistry {
+	fn color(
+		&self,
+		index: usize,
+		palette: usize,
+	) -> Color {
+		let CatalogueColor::Rgba{red, green, blue, alpha} = self.get(index).colors[palette % NUMBER_OF_PALETTES];
+		Color::rgba(red, green, blue, alpha)
+	}
+}
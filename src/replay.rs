@@ -0,0 +1,144 @@
+// Import Bevy game engine essentials
+use bevy::prelude::*;
+// Import Pkv Store for saving and loading game data
+use bevy_pkv::PkvStore;
+// Import components, resources, and events
+use crate::{components::*, save::get_replay_key};
+
+// Plugin for saving the inputs of completed levels as
+// replays, and showing replays as they are played back
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+			.add_systems(OnExit(GameState::Reactor), (
+				stop_playback,
+			))
+			.add_systems(Update, (
+				count_launch_costs,
+				show_playback_inputs.run_if(resource_exists::<ReplayPlayback>()),
+				save_replay,
+			).run_if(in_state(GameState::Reactor)))
+		;
+	}
+}
+
+// Replays are also written next to the game so they can be shared
+// and checked with the verify command. The game itself only plays
+// back the replays kept with each profile's save data
+#[cfg(not(target_arch = "wasm32"))]
+fn get_replay_path(
+	level: usize,
+) -> String {
+	format!("replays/level_{:02}.replay.ron", level)
+}
+
+// The replay of the profile's last completed run of a level
+pub fn load_replay(
+	pkv: &PkvStore,
+	slot: usize,
+	level: usize,
+) -> Option<Replay> {
+	pkv.get::<Option<Replay>>(&get_replay_key(slot, level)).ok().flatten()
+}
+
+// Launches are charged once the simulation has applied them,
+// so played back launches are counted the same way
+fn count_launch_costs(
+	mut current_cost: ResMut<CurrentCost>,
+	mut ev_r_input: EventReader<ReactorInputEvent>,
+	registry: Res<MoleculeRegistry>,
+) {
+	for ev in ev_r_input.iter() {
+		if let ReactorInput::Launch{index, ..} = ev.0 {
+			current_cost.0 += registry.cost(index);
+		}
+	}
+}
+
+// Moves the launch tubes and levers and changes the selected
+// reactor and molecule to follow the replay being played back
+fn show_playback_inputs(
+	mut commands: Commands,
+	mut ev_r_input: EventReader<ReactorInputEvent>,
+	mut selected_molecule_type: ResMut<SelectedMoleculeType>,
+	mut launch_tube_query: Query<(&mut Transform, &mut LaunchTube), Without<LeverInfo>>,
+	mut lever_query: Query<(&mut Transform, &LeverInfo), Without<LaunchTube>>,
+	reactor_query: Query<(Entity, &ReactorInfo, &ReactorCondition, Option<&SelectedReactor>)>,
+) {
+	for ev in ev_r_input.iter() {
+		match ev.0 {
+			ReactorInput::MoveLaunchTube{reactor_id, location, angle, tilt} => {
+				for (mut transform, mut launch_tube) in launch_tube_query.iter_mut() {
+					if launch_tube.id == reactor_id {
+						transform.translation = location.extend(transform.translation.z);
+						transform.rotation = Quat::from_rotation_z(angle);
+						launch_tube.current_rotation = tilt;
+					}
+				}
+			},
+			ReactorInput::SelectReactor(reactor_id) => {
+				for (entity, r_info, condition, selected) in reactor_query.iter() {
+					if r_info.reactor_id == reactor_id {
+						commands.entity(entity).insert(SelectedReactor);
+						for (mut transform, info) in lever_query.iter_mut() {
+							let percent = if info.lever_type == 0 {condition.temperature} else {condition.pressure};
+							transform.translation.y = info.min_height + percent * (info.max_height - info.min_height);
+						}
+					} else if selected.is_some() {
+						commands.entity(entity).remove::<SelectedReactor>();
+					}
+				}
+			},
+			ReactorInput::SetConditions{reactor_id, temperature, pressure} => {
+				let selected = reactor_query.iter().any(|(_, r_info, _, selected)| r_info.reactor_id == reactor_id && selected.is_some());
+				if selected {
+					for (mut transform, info) in lever_query.iter_mut() {
+						let percent = if info.lever_type == 0 {temperature} else {pressure};
+						transform.translation.y = info.min_height + percent * (info.max_height - info.min_height);
+					}
+				}
+			},
+			ReactorInput::SelectMolecule(index) => {
+				selected_molecule_type.0 = index;
+			},
+			ReactorInput::Launch{..} | ReactorInput::PlaceSpawner{..} => (),
+		}
+	}
+}
+
+// Keeps the inputs of every completed level, replacing the
+// profile's previous replay for that level
+fn save_replay(
+	mut pkv: ResMut<PkvStore>,
+	selected_profile: Res<SelectedProfile>,
+	mut ev_r_level_complete: EventReader<LevelCompleteEvent>,
+	recording: Res<ReplayRecording>,
+	playback: Option<Res<ReplayPlayback>>,
+) {
+	for _ in ev_r_level_complete.iter() {
+		if playback.is_some() {
+			continue;
+		}
+		let replay = &recording.0;
+		pkv.set(get_replay_key(selected_profile.0, replay.level), replay)
+			.expect("Unable to save replay");
+		#[cfg(not(target_arch = "wasm32"))]
+		match replay.to_ron() {
+			Ok(text) => {
+				if let Err(error) = std::fs::create_dir_all("replays")
+					.and_then(|_| std::fs::write(get_replay_path(replay.level), text)) {
+					warn!("Unable to write {}: {}", get_replay_path(replay.level), error);
+				}
+			},
+			Err(error) => warn!("Unable to write replay: {}", error),
+		}
+	}
+}
+
+fn stop_playback(
+	mut commands: Commands,
+) {
+	commands.remove_resource::<ReplayPlayback>();
+}
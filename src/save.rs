@@ -0,0 +1,938 @@
+// Import Bevy game engine essentials
+use bevy::prelude::*;
+// Import Pkv Store for saving and loading game data
+use bevy_pkv::{GetError, PkvStore};
+// Import components, resources, and events
+use crate::{components::*, replay::load_replay};
+
+// Each migration brings a save from the version it is listed
+// at up to the next one, so a save of any older version is
+// brought up to date by running the rest of the list in order
+const MIGRATIONS: [fn(&mut SaveData); SAVE_VERSION as usize] = [
+	// 0 to 1
+	fill_progress_and_award_stars,
+];
+
+// A blank save for a new profile, also used for any fields
+// missing from an older save while it is being read
+impl Default for SaveData {
+	fn default() -> Self {
+		SaveData{
+			levels_unlocked: vec![false; NUMBER_OF_LEVELS],
+			best_times: vec![999999.0; NUMBER_OF_LEVELS],
+			best_costs: vec![999999; NUMBER_OF_LEVELS],
+			cutscenes_unlocked: vec![false; NUMBER_OF_CUTSCENES],
+			stars: vec![0; NUMBER_OF_LEVELS],
+			version: SAVE_VERSION,
+		}
+	}
+}
+
+impl Default for Settings {
+	fn default() -> Self {
+		Settings{
+			sfx_volume: 0.4,
+			bgm_volume: 0.4,
+			selected_palette: 0,
+			fullscreen_enabled: true,
+			particles_enabled: true,
+			controls: Controls::default(),
+			glyphs_enabled: false,
+			ui_scale: 1.0,
+			text_scale: 1.0,
+			language: FALLBACK_LANGUAGE.to_string(),
+		}
+	}
+}
+
+// The controls the game had before they could be rebound
+impl Default for Controls {
+	fn default() -> Self {
+		Controls{
+			bindings: InputAction::ALL.iter()
+				.map(|action| (*action, get_default_bindings(*action)))
+				.collect(),
+		}
+	}
+}
+
+impl Controls {
+	// Actions missing from the save, such as ones added since
+	// it was written, keep their default bindings
+	pub fn get(
+		&self,
+		action: InputAction,
+	) -> [Option<InputBinding>; 2] {
+		self.bindings.iter()
+			.find(|(bound, _)| *bound == action)
+			.map(|(_, bindings)| *bindings)
+			.unwrap_or_else(|| get_default_bindings(action))
+	}
+
+	// Replaces one of the two bindings for an action, or clears
+	// it when given None
+	pub fn set(
+		&mut self,
+		action: InputAction,
+		slot: usize,
+		binding: Option<InputBinding>,
+	) {
+		match self.bindings.iter_mut().find(|(bound, _)| *bound == action) {
+			Some((_, bindings)) => bindings[slot] = binding,
+			None => {
+				let mut bindings = get_default_bindings(action);
+				bindings[slot] = binding;
+				self.bindings.push((action, bindings));
+			},
+		}
+	}
+}
+
+impl InputAction {
+	pub const ALL: [InputAction; 15] = [
+		InputAction::MoveLeft,
+		InputAction::MoveRight,
+		InputAction::RotateCCW,
+		InputAction::RotateCW,
+		InputAction::Sprint,
+		InputAction::LaunchOnce,
+		InputAction::LaunchHold,
+		InputAction::PlaceSpawner,
+		InputAction::SelectReactor,
+		InputAction::DragLever,
+		InputAction::TrackMolecule,
+		InputAction::Pan,
+		InputAction::ZoomIn,
+		InputAction::ZoomOut,
+		InputAction::AdvanceCutscene,
+	];
+}
+
+fn get_default_bindings(
+	action: InputAction,
+) -> [Option<InputBinding>; 2] {
+	use InputBinding::*;
+	match action {
+		InputAction::MoveLeft => [Some(Key(KeyCode::A)), None],
+		InputAction::MoveRight => [Some(Key(KeyCode::D)), None],
+		InputAction::RotateCCW => [Some(Key(KeyCode::Q)), None],
+		InputAction::RotateCW => [Some(Key(KeyCode::E)), None],
+		InputAction::Sprint => [Some(Key(KeyCode::ShiftLeft)), None],
+		InputAction::LaunchOnce => [Some(Key(KeyCode::Space)), None],
+		InputAction::LaunchHold => [Some(Key(KeyCode::W)), None],
+		InputAction::PlaceSpawner => [Some(Key(KeyCode::S)), None],
+		InputAction::SelectReactor => [Some(Mouse(MouseButton::Left)), None],
+		InputAction::DragLever => [Some(Mouse(MouseButton::Left)), None],
+		InputAction::TrackMolecule => [Some(Mouse(MouseButton::Middle)), None],
+		InputAction::Pan => [Some(Mouse(MouseButton::Right)), None],
+		InputAction::ZoomIn => [Some(Key(KeyCode::Equals)), None],
+		InputAction::ZoomOut => [Some(Key(KeyCode::Minus)), None],
+		InputAction::AdvanceCutscene => [Some(Mouse(MouseButton::Left)), Some(Key(KeyCode::Space))],
+	}
+}
+
+impl Profiles {
+	pub fn name(
+		&self,
+		slot: usize,
+	) -> Option<&str> {
+		self.names.get(slot).and_then(|name| name.as_deref())
+	}
+
+	pub fn count(&self) -> usize {
+		self.names.iter().filter(|name| name.is_some()).count()
+	}
+
+	pub fn first_empty(&self) -> Option<usize> {
+		self.names.iter().position(|name| name.is_none())
+	}
+
+	// The name shown for a profile, in the selected language
+	// for a profile still using the default name
+	pub fn display_name(
+		&self,
+		slot: usize,
+		locale: &Locale,
+	) -> Option<String> {
+		self.name(slot).map(|name| get_display_name(name, slot, locale))
+	}
+
+	// Names a new profile in an empty slot, blank until it is
+	// renamed so the default name follows the language
+	pub fn create(
+		&mut self,
+		slot: usize,
+	) -> bool {
+		match self.names.get_mut(slot) {
+			Some(name) if name.is_none() => {
+				*name = Some(String::new());
+				true
+			},
+			_ => false,
+		}
+	}
+
+	// Names a copy of a profile in the first empty slot, returning
+	// the slot so the progress can be copied into it
+	pub fn copy(
+		&mut self,
+		slot: usize,
+		name: &str,
+	) -> Option<usize> {
+		self.name(slot)?;
+		let copy = self.first_empty()?;
+		self.names[copy] = Some(name.chars().take(MAX_PROFILE_NAME_LENGTH).collect());
+		Some(copy)
+	}
+
+	// Empties a slot, unless it holds the only profile, moving the
+	// selection to another profile if it was the one removed
+	pub fn delete(
+		&mut self,
+		slot: usize,
+	) -> bool {
+		if self.name(slot).is_none() || self.count() == 1 {
+			return false;
+		}
+		self.names[slot] = None;
+		if self.selected == slot {
+			self.selected = self.names.iter().position(|name| name.is_some()).unwrap_or(0);
+		}
+		true
+	}
+
+	pub fn type_name(
+		&mut self,
+		slot: usize,
+		character: char,
+	) {
+		if let Some(Some(name)) = self.names.get_mut(slot) {
+			if !character.is_control() && name.chars().count() < MAX_PROFILE_NAME_LENGTH {
+				name.push(character);
+			}
+		}
+	}
+
+	pub fn erase_name(
+		&mut self,
+		slot: usize,
+	) {
+		if let Some(Some(name)) = self.names.get_mut(slot) {
+			name.pop();
+		}
+	}
+
+	// Tidies a name once typing is finished, a name left blank
+	// goes back to being shown as the default name
+	pub fn finish_name(
+		&mut self,
+		slot: usize,
+	) {
+		if let Some(Some(name)) = self.names.get_mut(slot) {
+			*name = name.trim().to_string();
+		}
+	}
+
+	// Makes sure there is a slot for every profile, at least one
+	// profile, and that the selected slot holds one of them
+	fn fill(&mut self) {
+		self.names.resize(NUMBER_OF_PROFILES, None);
+		if self.count() == 0 {
+			self.create(0);
+		}
+		if self.name(self.selected).is_none() {
+			self.selected = self.names.iter().position(|name| name.is_some()).unwrap_or(0);
+		}
+	}
+}
+
+pub fn get_display_name(
+	name: &str,
+	slot: usize,
+	locale: &Locale,
+) -> String {
+	if name.is_empty() {
+		locale.format("profiles.default_name", &[(slot + 1).to_string()])
+	} else {
+		name.to_string()
+	}
+}
+
+pub fn get_profile_key(
+	slot: usize,
+) -> String {
+	format!("profile_{}", slot)
+}
+
+pub fn get_replay_key(
+	slot: usize,
+	level: usize,
+) -> String {
+	format!("profile_{}_replay_{}", slot, level)
+}
+
+// Gives a copied profile the replays of the one it was copied from
+pub fn copy_replays(
+	pkv: &mut PkvStore,
+	from: usize,
+	to: usize,
+) {
+	for level in 0..NUMBER_OF_LEVELS {
+		let replay = load_replay(pkv, from, level);
+		pkv.set(get_replay_key(to, level), &replay)
+			.expect("Unable to save replay");
+	}
+}
+
+// The store cannot remove keys, so a deleted profile's replays
+// are emptied instead, leaving nothing for a new profile to watch
+pub fn clear_replays(
+	pkv: &mut PkvStore,
+	slot: usize,
+) {
+	for level in 0..NUMBER_OF_LEVELS {
+		pkv.set(get_replay_key(slot, level), &None::<Replay>)
+			.expect("Unable to save replay");
+	}
+}
+
+// Reads the list of profiles, making the first one on a new install
+// and bringing the selected profile up to date. A list which cannot
+// be read is rebuilt from the profiles still saved, and a selected
+// profile which cannot be read is swapped for one which can
+pub fn load_profiles(
+	pkv: &mut PkvStore,
+) -> Profiles {
+	let mut profiles = match pkv.get::<Profiles>("profiles") {
+		Ok(profiles) => profiles,
+		Err(GetError::NotFound) => {
+			split_single_save(pkv);
+			Profiles::default()
+		},
+		Err(error) => {
+			error!("Unable to read profiles, rebuilding the list from the saved profiles: {}", error);
+			keep_unreadable(pkv, "profiles");
+			recover_profiles(pkv)
+		},
+	};
+	profiles.fill();
+	if let Err(error) = load_profile(pkv, profiles.selected) {
+		error!("{}", error);
+		let readable = (0..NUMBER_OF_PROFILES)
+			.find(|slot| profiles.name(*slot).is_some() && load_profile(pkv, *slot).is_ok());
+		match (readable, profiles.first_empty()) {
+			(Some(slot), _) => profiles.selected = slot,
+			(None, Some(slot)) => {
+				profiles.create(slot);
+				profiles.selected = slot;
+				pkv.set(get_profile_key(slot), &SaveData::default())
+					.expect("Unable to save data");
+			},
+			(None, None) => error!("Every profile is unreadable, progress will not be saved until one is started over"),
+		}
+	}
+	pkv.set("profiles", &profiles)
+		.expect("Unable to save profiles");
+	profiles
+}
+
+// Every slot with a save keeps its progress under the default name
+fn recover_profiles(
+	pkv: &PkvStore,
+) -> Profiles {
+	Profiles{
+		names: (0..NUMBER_OF_PROFILES)
+			.map(|slot| pkv.get::<ron::Value>(get_profile_key(slot)).ok().map(|_| String::new()))
+			.collect(),
+		..Default::default()
+	}
+}
+
+// Settings which cannot be read are kept aside and the defaults are
+// used, they are only written over once a setting is changed
+pub fn load_settings(
+	pkv: &mut PkvStore,
+) -> Settings {
+	match pkv.get::<Settings>("settings") {
+		Ok(settings) => return settings,
+		Err(GetError::NotFound) => (),
+		Err(error) => {
+			error!("Unable to read settings, starting with the defaults: {}", error);
+			keep_unreadable(pkv, "settings");
+			return Settings::default();
+		},
+	}
+	let settings = Settings::default();
+	pkv.set("settings", &settings)
+		.expect("Unable to save settings");
+	settings
+}
+
+// Brings the save for a profile up to the current version, or
+// starts a blank one if the slot has never been saved to. A save
+// which cannot be read, or was written by a newer version of the
+// game, is left as it is and a copy is kept aside, so nothing is
+// lost unless the player chooses to start over
+pub fn load_profile(
+	pkv: &mut PkvStore,
+	slot: usize,
+) -> Result<(), String> {
+	let key = get_profile_key(slot);
+	let save_data = match pkv.get::<SaveData>(&key) {
+		Ok(save_data) if save_data.version == SAVE_VERSION => return Ok(()),
+		// Writing it back would drop whatever this version does not know about
+		Ok(save_data) if save_data.version > SAVE_VERSION => {
+			keep_unreadable(pkv, &key);
+			return Err(format!("Unable to read {}: it is from a newer version of the game ({})", key, save_data.version));
+		},
+		Ok(mut save_data) => {
+			info!("Migrating {} from version {} to {}", key, save_data.version, SAVE_VERSION);
+			migrate_save_data(&mut save_data);
+			save_data
+		},
+		Err(GetError::NotFound) => SaveData::default(),
+		Err(error) => {
+			keep_unreadable(pkv, &key);
+			return Err(format!("Unable to read {}: {}", key, error));
+		},
+	};
+	pkv.set(&key, &save_data)
+		.expect("Unable to save data");
+	Ok(())
+}
+
+pub fn get_unreadable_key(
+	key: &str,
+) -> String {
+	format!("{}_unreadable", key)
+}
+
+// Copies a value the game can no longer read to a key of its own,
+// so it can still be recovered by hand once the original is replaced
+fn keep_unreadable(
+	pkv: &mut PkvStore,
+	key: &str,
+) {
+	match pkv.get::<ron::Value>(key) {
+		Ok(value) => {
+			pkv.set(get_unreadable_key(key), &value)
+				.expect("Unable to save data");
+			warn!("Kept a copy of {} at {}", key, get_unreadable_key(key));
+		},
+		Err(error) => error!("Unable to keep a copy of {}: {}", key, error),
+	}
+}
+
+// Before profiles, the settings and progress were saved together
+// under one key. They are split apart, with the progress and the
+// replays becoming the first profile's, and the old keys are left
+// untouched
+fn split_single_save(
+	pkv: &mut PkvStore,
+) {
+	if let Ok(settings) = pkv.get::<Settings>("save_data") {
+		pkv.set("settings", &settings)
+			.expect("Unable to save settings");
+	}
+	if let Ok(save_data) = pkv.get::<SaveData>("save_data") {
+		info!("Moving the existing save into the first profile");
+		pkv.set(get_profile_key(0), &save_data)
+			.expect("Unable to save data");
+		for level in 0..NUMBER_OF_LEVELS {
+			if let Ok(replay) = pkv.get::<Replay>(format!("replay_{}", level)) {
+				pkv.set(get_replay_key(0, level), &replay)
+					.expect("Unable to save replay");
+			}
+		}
+	}
+}
+
+// Gathers the selected profile with its replays, and the settings,
+// and writes them to a file that can be shared, returning the name
+// of the profile written
+pub fn export_save(
+	pkv: &PkvStore,
+	profiles: &Profiles,
+	slot: usize,
+	settings: &Settings,
+	locale: &Locale,
+) -> Result<String, String> {
+	let export = SaveExport{
+		name: profiles.name(slot).unwrap_or_default().to_string(),
+		settings: settings.clone(),
+		save_data: pkv.get::<SaveData>(get_profile_key(slot)).map_err(|error| error.to_string())?,
+		replays: (0..NUMBER_OF_LEVELS)
+			.filter_map(|level| load_replay(pkv, slot, level))
+			.collect(),
+	};
+	let text = ron::ser::to_string_pretty(&export, ron::ser::PrettyConfig::default())
+		.map_err(|error| error.to_string())?;
+	write_save_export(&text, locale)?;
+	Ok(get_display_name(&export.name, slot, locale))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_save_export(
+	text: &str,
+	_locale: &Locale,
+) -> Result<(), String> {
+	if let Some(folder) = std::path::Path::new(SAVE_EXPORT_PATH).parent() {
+		std::fs::create_dir_all(folder).map_err(|error| error.to_string())?;
+	}
+	std::fs::write(SAVE_EXPORT_PATH, text).map_err(|error| error.to_string())
+}
+
+// The browser has no folder next to the game, so the export is
+// handed to it as a download instead
+#[cfg(target_arch = "wasm32")]
+fn write_save_export(
+	text: &str,
+	locale: &Locale,
+) -> Result<(), String> {
+	use wasm_bindgen::JsCast;
+	let js_error = |error: wasm_bindgen::JsValue| format!("{:?}", error);
+	let document = web_sys::window()
+		.and_then(|window| window.document())
+		.ok_or_else(|| locale.get("save.error.no_page"))?;
+	let parts = js_sys::Array::of1(&wasm_bindgen::JsValue::from_str(text));
+	let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, web_sys::BlobPropertyBag::new().type_("text/plain"))
+		.map_err(js_error)?;
+	let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+	let link = document.create_element("a").map_err(js_error)?
+		.dyn_into::<web_sys::HtmlAnchorElement>().map_err(|_| locale.get("save.error.no_page"))?;
+	link.set_href(&url);
+	link.set_download(SAVE_EXPORT_PATH);
+	link.click();
+	web_sys::Url::revoke_object_url(&url).map_err(js_error)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_save_export<'a>(
+	get_level: impl Fn(usize) -> Option<&'a LevelDefinition>,
+	registry: &MoleculeRegistry,
+	locale: &Locale,
+) -> Result<SaveExport, String> {
+	let bytes = std::fs::read(SAVE_EXPORT_PATH)
+		.map_err(|error| format!("{}: {}", SAVE_EXPORT_PATH, error))?;
+	parse_save_export(&bytes, get_level, registry, locale)
+}
+
+// The browser cannot read files next to the game, so the text of
+// an export is pasted in instead
+#[cfg(target_arch = "wasm32")]
+pub fn read_save_export<'a>(
+	get_level: impl Fn(usize) -> Option<&'a LevelDefinition>,
+	registry: &MoleculeRegistry,
+	locale: &Locale,
+) -> Result<SaveExport, String> {
+	let window = web_sys::window().ok_or_else(|| locale.get("save.error.no_page"))?;
+	let text = window.prompt_with_message(&locale.get("save.paste"))
+		.map_err(|error| format!("{:?}", error))?
+		.ok_or_else(|| locale.get("save.error.not_pasted"))?;
+	parse_save_export(text.as_bytes(), get_level, registry, locale)
+}
+
+// Reads an exported save, bringing it up to the current version and
+// checking it could have been made by this version of the game, so a
+// bad file is turned away before anything is overwritten
+pub fn parse_save_export<'a>(
+	bytes: &[u8],
+	get_level: impl Fn(usize) -> Option<&'a LevelDefinition>,
+	registry: &MoleculeRegistry,
+	locale: &Locale,
+) -> Result<SaveExport, String> {
+	let mut export = ron::de::from_bytes::<SaveExport>(bytes)
+		.map_err(|error| locale.format("save.error.not_a_save", &[error.to_string()]))?;
+	if export.save_data.version > SAVE_VERSION {
+		return Err(locale.format("save.error.newer_version", &[export.save_data.version.to_string()]));
+	}
+	migrate_save_data(&mut export.save_data);
+	let save_data = &export.save_data;
+	let lengths = [
+		("save.error.unlocked_levels", save_data.levels_unlocked.len(), NUMBER_OF_LEVELS),
+		("save.error.best_times", save_data.best_times.len(), NUMBER_OF_LEVELS),
+		("save.error.best_costs", save_data.best_costs.len(), NUMBER_OF_LEVELS),
+		("save.error.stars", save_data.stars.len(), NUMBER_OF_LEVELS),
+		("save.error.unlocked_cutscenes", save_data.cutscenes_unlocked.len(), NUMBER_OF_CUTSCENES),
+	];
+	for (key, length, expected) in lengths {
+		if length != expected {
+			return Err(locale.format(key, &[length.to_string(), expected.to_string()]));
+		}
+	}
+	if save_data.stars.iter().any(|stars| *stars > MAX_STARS) {
+		return Err(locale.format("save.error.too_many_stars", &[MAX_STARS.to_string()]));
+	}
+	let settings = &export.settings;
+	if !(0.0..=1.0).contains(&settings.sfx_volume) || !(0.0..=1.0).contains(&settings.bgm_volume) {
+		return Err(locale.get("save.error.volume"));
+	}
+	if settings.selected_palette >= NUMBER_OF_PALETTES {
+		return Err(locale.format("save.error.palette", &[settings.selected_palette.to_string(), NUMBER_OF_PALETTES.to_string()]));
+	}
+	if !SCALE_STEPS.contains(&settings.ui_scale) || !SCALE_STEPS.contains(&settings.text_scale) {
+		return Err(locale.get("save.error.scale"));
+	}
+	if !LANGUAGES.iter().any(|(language, _)| *language == settings.language) {
+		return Err(locale.format("save.error.language", std::slice::from_ref(&settings.language)));
+	}
+	let mut levels = Vec::new();
+	for replay in export.replays.iter() {
+		if replay.level >= NUMBER_OF_LEVELS {
+			return Err(locale.format("save.error.replay_level", &[(replay.level + 1).to_string()]));
+		}
+		if levels.contains(&replay.level) {
+			return Err(locale.format("save.error.replay_twice", &[(replay.level + 1).to_string()]));
+		}
+		if replay.inputs.windows(2).any(|pair| pair[0].tick > pair[1].tick) {
+			return Err(locale.format("save.error.replay_order", &[(replay.level + 1).to_string()]));
+		}
+		// Replays of a level which failed to load are checked
+		// again if they are ever played back
+		if let Some(level) = get_level(replay.level) {
+			replay.validate(replay.level, level, registry)
+				.map_err(|error| locale.format("save.error.replay_input", &[(replay.level + 1).to_string(), error]))?;
+		}
+		levels.push(replay.level);
+	}
+	export.name = export.name.trim().chars().take(MAX_PROFILE_NAME_LENGTH).collect();
+	Ok(export)
+}
+
+// Replaces a profile and its replays with the ones in the export,
+// so levels without a replay in the export have none to watch.
+// The settings are applied by the caller
+pub fn import_save(
+	pkv: &mut PkvStore,
+	profiles: &mut Profiles,
+	slot: usize,
+	export: &SaveExport,
+) {
+	if let Some(Some(name)) = profiles.names.get_mut(slot) {
+		*name = export.name.clone();
+	}
+	profiles.finish_name(slot);
+	pkv.set(get_profile_key(slot), &export.save_data)
+		.expect("Unable to save data");
+	clear_replays(pkv, slot);
+	for replay in export.replays.iter() {
+		pkv.set(get_replay_key(slot, replay.level), replay)
+			.expect("Unable to save replay");
+	}
+}
+
+pub fn migrate_save_data(
+	save_data: &mut SaveData,
+) {
+	while (save_data.version as usize) < MIGRATIONS.len() {
+		MIGRATIONS[save_data.version as usize](save_data);
+		save_data.version += 1;
+	}
+}
+
+// Version 0 covers every save from before versions were stored,
+// which may have been made with fewer levels and cutscenes, and
+// may or may not have had stars. Levels already completed are
+// given the star for completing them
+fn fill_progress_and_award_stars(
+	save_data: &mut SaveData,
+) {
+	save_data.levels_unlocked.resize(NUMBER_OF_LEVELS, false);
+	save_data.best_times.resize(NUMBER_OF_LEVELS, 999999.0);
+	save_data.best_costs.resize(NUMBER_OF_LEVELS, 999999);
+	save_data.cutscenes_unlocked.resize(NUMBER_OF_CUTSCENES, false);
+	save_data.stars.resize(NUMBER_OF_LEVELS, 0);
+	for (stars, best_time) in save_data.stars.iter_mut().zip(save_data.best_times.iter()) {
+		if *best_time < 999999.0 {
+			*stars = (*stars).max(1);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Saves written by each earlier version of the game, kept
+	// exactly as they were written so they are never updated
+	// along with the current format
+	const FIXTURES: [(&str, &str); 2] = [
+		("save_v0.ron", include_str!("../tests/saves/save_v0.ron")),
+		("save_v0_stars.ron", include_str!("../tests/saves/save_v0_stars.ron")),
+	];
+
+	fn load_fixture(
+		name: &str,
+		text: &str,
+	) -> SaveData {
+		let mut save_data: SaveData = ron::from_str(text)
+			.unwrap_or_else(|error| panic!("{}: {}", name, error));
+		migrate_save_data(&mut save_data);
+		save_data
+	}
+
+	// The fallback language, as the game shows it before any
+	// other language is picked
+	fn load_locale() -> Locale {
+		let table = StringTable::from_ron(include_bytes!("../assets/locale/en.strings.ron")).unwrap();
+		Locale{
+			language: FALLBACK_LANGUAGE.to_string(),
+			strings: table.strings.into_iter().collect(),
+		}
+	}
+
+	fn load_levels() -> Vec<LevelDefinition> {
+		(0..NUMBER_OF_LEVELS)
+			.map(|level| {
+				let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets").join(get_level_path(level));
+				LevelDefinition::from_ron(&std::fs::read(&path).unwrap())
+					.unwrap_or_else(|error| panic!("{}: {}", path.display(), error))
+			})
+			.collect()
+	}
+
+	fn load_registry() -> MoleculeRegistry {
+		MoleculeRegistry::from_ron(include_bytes!("../assets/data/molecules.catalogue.ron")).unwrap()
+	}
+
+	fn parse_export(
+		bytes: &[u8],
+	) -> Result<SaveExport, String> {
+		let levels = load_levels();
+		parse_save_export(bytes, |level| levels.get(level), &load_registry(), &load_locale())
+	}
+
+	#[test]
+	fn fixtures_migrate_to_the_current_version() {
+		for (name, text) in FIXTURES {
+			let save_data = load_fixture(name, text);
+			assert_eq!(save_data.version, SAVE_VERSION, "{} was not migrated", name);
+			assert_eq!(save_data.levels_unlocked.len(), NUMBER_OF_LEVELS, "{} has the wrong number of levels", name);
+			assert_eq!(save_data.best_times.len(), NUMBER_OF_LEVELS, "{} has the wrong number of times", name);
+			assert_eq!(save_data.best_costs.len(), NUMBER_OF_LEVELS, "{} has the wrong number of costs", name);
+			assert_eq!(save_data.stars.len(), NUMBER_OF_LEVELS, "{} has the wrong number of stars", name);
+			assert_eq!(save_data.cutscenes_unlocked.len(), NUMBER_OF_CUTSCENES, "{} has the wrong number of cutscenes", name);
+		}
+	}
+
+	#[test]
+	fn migrations_keep_progress() {
+		for (name, text) in FIXTURES {
+			let save_data = load_fixture(name, text);
+			assert!(save_data.levels_unlocked[..4].iter().all(|unlocked| *unlocked), "{} lost its unlocked levels", name);
+			assert!(!save_data.levels_unlocked[4], "{} unlocked a level", name);
+			assert_eq!(save_data.best_costs[..3], [50, 180, 32], "{} lost its best costs", name);
+			assert_eq!(save_data.best_times[3], 999999.0, "{} gained a best time", name);
+		}
+	}
+
+	#[test]
+	fn completed_levels_keep_at_least_one_star() {
+		let save_data = load_fixture(FIXTURES[0].0, FIXTURES[0].1);
+		assert_eq!(save_data.stars[..4], [1, 1, 1, 0]);
+		// Stars earned before saves were versioned are kept
+		let save_data = load_fixture(FIXTURES[1].0, FIXTURES[1].1);
+		assert_eq!(save_data.stars[..4], [3, 1, 2, 0]);
+	}
+
+	#[test]
+	fn settings_are_split_from_single_saves() {
+		for (name, text) in FIXTURES {
+			let settings: Settings = ron::from_str(text)
+				.unwrap_or_else(|error| panic!("{}: {}", name, error));
+			assert_eq!(settings.selected_palette, 1, "{} lost its palette", name);
+			assert_eq!(settings.bgm_volume, 0.2, "{} lost its volume", name);
+		}
+	}
+
+	#[test]
+	fn there_is_always_a_profile_selected() {
+		let mut profiles = Profiles::default();
+		profiles.fill();
+		assert_eq!(profiles.names.len(), NUMBER_OF_PROFILES);
+		assert_eq!(profiles.display_name(0, &load_locale()).as_deref(), Some("Player 1"));
+		// The only profile cannot be deleted
+		assert!(!profiles.delete(0));
+		assert!(profiles.create(2));
+		profiles.selected = 2;
+		assert!(profiles.delete(2));
+		assert_eq!(profiles.selected, 0);
+		// Slots that are empty or missing are reselected when loading
+		profiles.selected = 7;
+		profiles.fill();
+		assert_eq!(profiles.selected, 0);
+	}
+
+	#[test]
+	fn saves_without_controls_keep_the_defaults() {
+		for (name, text) in FIXTURES {
+			let settings: Settings = ron::from_str(text)
+				.unwrap_or_else(|error| panic!("{}: {}", name, error));
+			assert_eq!(settings.controls, Controls::default(), "{} changed the controls", name);
+		}
+		// Actions added after a save was written are still bound
+		let controls: Controls = ron::from_str("(bindings: [(MoveLeft, (Some(Key(Left)), None))])").unwrap();
+		assert_eq!(controls.get(InputAction::MoveLeft), [Some(InputBinding::Key(KeyCode::Left)), None]);
+		assert_eq!(controls.get(InputAction::MoveRight), Controls::default().get(InputAction::MoveRight));
+	}
+
+	#[test]
+	fn every_action_can_be_rebound() {
+		let mut controls = Controls::default();
+		for action in InputAction::ALL {
+			assert!(controls.get(action)[0].is_some(), "{:?} has no default binding", action);
+			controls.set(action, 1, Some(InputBinding::Key(KeyCode::F1)));
+			controls.set(action, 0, None);
+			assert_eq!(controls.get(action), [None, Some(InputBinding::Key(KeyCode::F1))]);
+		}
+		let text = ron::to_string(&controls).unwrap();
+		assert_eq!(ron::from_str::<Controls>(&text).unwrap(), controls);
+	}
+
+	#[test]
+	fn profiles_are_copied_into_the_first_empty_slot() {
+		let mut profiles = Profiles::default();
+		profiles.fill();
+		profiles.create(1);
+		let locale = load_locale();
+		let name = locale.format("profiles.copy_name", &[profiles.display_name(1, &locale).unwrap()]);
+		assert_eq!(profiles.copy(1, &name), Some(2));
+		assert_eq!(profiles.name(2), Some("Player 2 Copy"));
+		assert_eq!(profiles.copy(4, "Copy"), None);
+		for _ in 0..NUMBER_OF_PROFILES {
+			profiles.copy(0, "Copy");
+		}
+		assert_eq!(profiles.count(), NUMBER_OF_PROFILES);
+		assert_eq!(profiles.copy(0, "Copy"), None);
+	}
+
+	#[test]
+	fn profile_names_are_kept_short_and_default_when_blank() {
+		let mut profiles = Profiles::default();
+		profiles.fill();
+		for character in "A much longer name than fits\n".chars() {
+			profiles.type_name(0, character);
+		}
+		assert_eq!(profiles.name(0).unwrap().chars().count(), MAX_PROFILE_NAME_LENGTH);
+		for _ in 0..MAX_PROFILE_NAME_LENGTH {
+			profiles.erase_name(0);
+		}
+		profiles.type_name(0, ' ');
+		profiles.finish_name(0);
+		assert_eq!(profiles.name(0), Some(""));
+		assert_eq!(profiles.display_name(0, &load_locale()).as_deref(), Some("Player 1"));
+	}
+
+	#[test]
+	fn exports_are_read_back() {
+		let export = parse_export(include_bytes!("../tests/saves/export_v1.ron"))
+			.unwrap_or_else(|error| panic!("export_v1.ron: {}", error));
+		assert_eq!(export.name, "Tester");
+		assert_eq!(export.settings.selected_palette, 2);
+		assert_eq!(export.save_data.stars[..3], [3, 2, 0]);
+		assert_eq!(export.replays.len(), 1);
+		let text = ron::ser::to_string_pretty(&export, ron::ser::PrettyConfig::default()).unwrap();
+		assert!(parse_export(text.as_bytes()).is_ok());
+	}
+
+	#[test]
+	fn bad_exports_are_turned_away() {
+		let text = include_str!("../tests/saves/export_v1.ron");
+		let broken = [
+			("newer version", text.replace("version: 1", "version: 99")),
+			("missing level", text.replacen("false, ", "", 1)),
+			("too many stars", text.replace("stars: [3", "stars: [4")),
+			("loud volume", text.replace("sfx_volume: 0.5", "sfx_volume: 5.0")),
+			("missing palette", text.replace("selected_palette: 2", "selected_palette: 9")),
+			("huge text", text.replace("selected_palette: 2,", "selected_palette: 2,\n\t\ttext_scale: 9.0,")),
+			("missing language", text.replace("selected_palette: 2,", "selected_palette: 2,\n\t\tlanguage: \"xx\",")),
+			("missing level replay", text.replace("level: 0", "level: 99")),
+			// Level 0 only offers molecule 0, and has a single reactor
+			("replayed molecule not offered", text.replacen("index: 0", "index: 2", 1)),
+			("replayed molecule missing", text.replacen("index: 0", "index: 99", 1)),
+			("replayed reactor missing", text.replacen("reactor_id: 0", "reactor_id: 3", 1)),
+			("replay launched off the tube", text.replacen("952.0", "0.0", 1)),
+			("not a save", "(name: \"Tester\")".to_string()),
+		];
+		for (name, broken) in broken {
+			assert!(parse_export(broken.as_bytes()).is_err(), "{} was accepted", name);
+		}
+	}
+
+	// A profile written with its best costs as text, which no
+	// version of the game has been able to read
+	#[test]
+	fn unreadable_saves_are_kept() {
+		let path = std::env::temp_dir().join("mole_rancher_unreadable_saves");
+		let _ = std::fs::remove_dir_all(&path);
+		let mut pkv = PkvStore::new_in_dir(&path);
+		let unreadable: ron::Value = ron::from_str(include_str!("../tests/saves/save_unreadable.ron")).unwrap();
+		let mut profiles = Profiles::default();
+		profiles.fill();
+		profiles.create(1);
+		profiles.selected = 1;
+		pkv.set("profiles", &profiles).unwrap();
+		pkv.set(get_profile_key(0), &SaveData::default()).unwrap();
+		pkv.set(get_profile_key(1), &unreadable).unwrap();
+
+		// The other profile is played instead, and the unreadable
+		// save is left where it was with a copy kept aside
+		assert_eq!(load_profiles(&mut pkv).selected, 0);
+		assert!(load_profile(&mut pkv, 1).is_err());
+		assert_eq!(pkv.get::<ron::Value>(get_profile_key(1)).unwrap(), unreadable);
+		assert_eq!(pkv.get::<ron::Value>(get_unreadable_key(&get_profile_key(1))).unwrap(), unreadable);
+
+		// A list of profiles which cannot be read is rebuilt
+		// from the saves, without starting any of them over
+		pkv.set("profiles", &"not a list of profiles").unwrap();
+		let profiles = load_profiles(&mut pkv);
+		assert_eq!(profiles.count(), 2);
+		assert_eq!(pkv.get::<String>(get_unreadable_key("profiles")).unwrap(), "not a list of profiles");
+		assert_eq!(pkv.get::<ron::Value>(get_profile_key(1)).unwrap(), unreadable);
+		let _ = std::fs::remove_dir_all(&path);
+	}
+
+	// A profile saved by a later version, with a field this one does not know
+	#[test]
+	fn newer_saves_are_kept() {
+		let path = std::env::temp_dir().join("mole_rancher_newer_saves");
+		let _ = std::fs::remove_dir_all(&path);
+		let mut pkv = PkvStore::new_in_dir(&path);
+		let newer: ron::Value = ron::from_str(include_str!("../tests/saves/save_v2.ron")).unwrap();
+		pkv.set(get_profile_key(1), &newer).unwrap();
+
+		assert!(load_profile(&mut pkv, 1).is_err());
+		assert_eq!(pkv.get::<ron::Value>(get_profile_key(1)).unwrap(), newer);
+		assert_eq!(pkv.get::<ron::Value>(get_unreadable_key(&get_profile_key(1))).unwrap(), newer);
+		let _ = std::fs::remove_dir_all(&path);
+	}
+
+	#[test]
+	fn replays_belong_to_their_profile() {
+		let path = std::env::temp_dir().join("mole_rancher_profile_replays");
+		let _ = std::fs::remove_dir_all(&path);
+		let mut pkv = PkvStore::new_in_dir(&path);
+		let replay = Replay{level: 2, seed: 7, inputs: Vec::new()};
+		pkv.set(get_replay_key(0, 2), &replay).unwrap();
+		assert!(load_replay(&pkv, 1, 2).is_none());
+
+		// A copy can watch the replays of the profile it was copied
+		// from, and a deleted profile leaves none behind in its slot
+		copy_replays(&mut pkv, 0, 1);
+		assert_eq!(load_replay(&pkv, 1, 2).map(|replay| replay.seed), Some(7));
+		assert!(load_replay(&pkv, 1, 3).is_none());
+		clear_replays(&mut pkv, 0);
+		assert!(load_replay(&pkv, 0, 2).is_none());
+		assert!(load_replay(&pkv, 1, 2).is_some());
+		let _ = std::fs::remove_dir_all(&path);
+	}
+
+	#[test]
+	fn current_saves_are_left_alone() {
+		let mut save_data = SaveData::default();
+		save_data.stars[0] = 2;
+		let saved = save_data.clone();
+		migrate_save_data(&mut save_data);
+		assert_eq!(save_data, saved);
+	}
+}
@@ -1,9 +1,9 @@
 // Import Bevy game engine essentials
-use bevy::{prelude::*, render::{camera::ScalingMode, view::RenderLayers}, core_pipeline::clear_color::ClearColorConfig};
+use bevy::{prelude::*, app::AppExit, render::{camera::ScalingMode, view::RenderLayers}, core_pipeline::clear_color::ClearColorConfig};
 // Import Pkv Store for saving and loading game data
 use bevy_pkv::PkvStore;
 // Import components, resources, and events
-use crate::components::*;
+use crate::{components::*, levels::Levels, save::{load_profiles, load_settings}};
 
 // Plugin for handling all initial one time setup 
 // such as camera spawning, loading save data and 
@@ -12,6 +12,15 @@ pub struct SetupPlugin;
 
 impl Plugin for SetupPlugin {
     fn build(&self, app: &mut App) {
+		// Settings and profiles are needed by systems from the first
+		// frame, so they are loaded before any of them run. Older saves
+		// are brought up to the current version, and a new install is
+		// given a blank profile
+		let mut pkv = PkvStore::new(".SoysCodingCafe", "Mole Rancher Remastered");
+		let settings = load_settings(&mut pkv);
+		let profiles = load_profiles(&mut pkv);
+		let mut settings_save_timer = Timer::from_seconds(SETTINGS_SAVE_DELAY, TimerMode::Once);
+		settings_save_timer.pause();
         app
 			// States
 			.add_state::<GameState>()
@@ -22,11 +31,13 @@ impl Plugin for SetupPlugin {
 			.add_event::<ReplayLevelEvent>()
 			.add_event::<PopupEvent>()
 			.add_event::<PopupCompleteEvent>()
-			.add_event::<ConnectionEvent>()
-			.add_event::<SoundEffectEvent>()
+			.add_event::<FocusEvent>()
 			// Resources
 			.insert_resource(OrthoSize{width: ORTHO_WIDTH, height: ORTHO_HEIGHT})
-			.insert_resource(PkvStore::new(".SoysCodingCafe", "Mole Rancher Remastered"))
+			.insert_resource(SelectedPalette(settings.selected_palette))
+			.insert_resource(settings)
+			.insert_resource(pkv)
+			.insert_resource(SettingsSaveTimer(settings_save_timer))
 			.insert_resource(CutsceneTracker{
 				current_scene: 0,
 				current_line: 0,
@@ -35,19 +46,21 @@ impl Plugin for SetupPlugin {
 				actor_info: ActorInfo { actor: Actor::Nobody },
 				cutscene_state: CutsceneState::Initialize,
 			})
-			.insert_resource(SelectedPalette(0))
 			.insert_resource(SelectedLevel(0))
+			.insert_resource(SelectedProfile(profiles.selected))
+			.insert_resource(profiles)
+			.insert_resource(PendingProfileAction::None)
+			.insert_resource(SaveTransferMessage::default())
+			.insert_resource(PendingSaveImport::default())
 			.insert_resource(SelectedMoleculeType(0))
 			.insert_resource(CurrentCost(0))
-			.insert_resource(MoleculeCount{total: 0, cap: MOLECULE_CAP})
+			.insert_resource(SimRng::new(std::env::var("MOLE_RANCHER_SEED").ok().and_then(|seed| seed.parse().ok())))
 			.insert_resource(BootTimer(Timer::from_seconds(BOOT_DURATION, TimerMode::Once)))
 			.insert_resource(LaunchTimer(Timer::from_seconds(LAUNCH_COOLDOWN, TimerMode::Once)))
-			.insert_resource(WinCountdown(Timer::from_seconds(WIN_COUNTDOWN_LENGTH, TimerMode::Once)))
 			.insert_resource(FadeTransitionTimer(Timer::from_seconds(FADE_TRANSITION_DURATION, TimerMode::Once)))
 			.insert_resource(TextSpeedTimer(Timer::from_seconds(TEXT_SPEED, TimerMode::Repeating)))
 			// Systems
 			.add_systems( Startup,(
-				load_game,
 				spawn_cameras,
 				spawn_splash_screen,
 			))
@@ -55,6 +68,11 @@ impl Plugin for SetupPlugin {
 				animate_sprites.run_if(not(in_state(PauseState::Paused))),
 				advance_splash_screen.run_if(in_state(GameState::Boot)),
 			))
+			// Runs after everything else so a quit this frame is seen
+			.add_systems(Last, (
+				save_settings,
+				save_profiles,
+			))
 			.add_systems(OnExit(PauseState::Paused), (
 				despawn_entities_with::<DespawnOnExitPauseState>,
 			))
@@ -73,44 +91,46 @@ impl Plugin for SetupPlugin {
 			.add_systems(OnExit(GameState::Reactor), (
 				despawn_entities_with::<DespawnOnExitGameState>,
 			))
+			.add_systems(OnExit(GameState::Editor), (
+				despawn_entities_with::<DespawnOnExitGameState>,
+			))
 		;
 	}
 }
 
-// On startup loads data from save file if it exists, otherwise
-// creates a new blank save file for the player
-fn load_game(
+// Profiles change rarely, so they are written back as soon
+// as a profile button or a typed name changes them
+fn save_profiles(
+	profiles: Res<Profiles>,
 	mut pkv: ResMut<PkvStore>,
-	mut selected_palette: ResMut<SelectedPalette>,
 ) {
-	if let Ok(save_data) = pkv.get::<SaveData>("save_data") {
-		selected_palette.0 = save_data.selected_palette;
-	} else {
-		let mut levels_unlocked = Vec::new();
-		let mut best_times = Vec::new();
-		let mut best_costs = Vec::new();
-		let mut cutscenes_unlocked = Vec::new();
-		for _ in 0..NUMBER_OF_LEVELS {
-			levels_unlocked.push(false);
-			best_times.push(999999.0);
-			best_costs.push(999999);
-		}
-		for _ in 0..NUMBER_OF_CUTSCENES {
-			cutscenes_unlocked.push(false);
-		}
-		let save_data = SaveData{
-			sfx_volume: 0.4,
-			bgm_volume: 0.4,
-			selected_palette: 0,
-			fullscreen_enabled: true,
-			particles_enabled: true,
-			levels_unlocked: levels_unlocked,
-			best_times: best_times,
-			best_costs: best_costs,
-			cutscenes_unlocked: cutscenes_unlocked,
-		};
-		pkv.set("save_data", &save_data)
-			.expect("Unable to save data");
+	if profiles.is_changed() && !profiles.is_added() {
+		pkv.set("profiles", &*profiles)
+			.expect("Unable to save profiles");
+	}
+}
+
+// Settings are written back a short time after they last changed,
+// so clicking through the volume buttons writes them once. Any
+// change still waiting is written straight away when quitting
+fn save_settings(
+	settings: Res<Settings>,
+	time: Res<Time>,
+	mut pkv: ResMut<PkvStore>,
+	mut settings_save_timer: ResMut<SettingsSaveTimer>,
+	mut ev_r_exit: EventReader<AppExit>,
+) {
+	let timer = &mut settings_save_timer.0;
+	if settings.is_changed() && !settings.is_added() {
+		timer.reset();
+		timer.unpause();
+	}
+	timer.tick(time.delta());
+	let exiting = ev_r_exit.iter().count() > 0;
+	if timer.just_finished() || (exiting && !timer.paused()) {
+		pkv.set("settings", &*settings)
+			.expect("Unable to save settings");
+		timer.pause();
 	}
 }
 
@@ -130,7 +150,7 @@ fn spawn_cameras(
 					order: 1,
 					..Default::default()
 				},
-				camera_2d: Camera2d {clear_color: ClearColorConfig::None, ..Default::default()},
+				camera_2d: Camera2d {clear_color: ClearColorConfig::None},
 				transform: Transform::from_translation(Vec3::new(0.0, 0.0, 1000.0)),
 				projection: OrthographicProjection {
 					scale: 1.0,
@@ -168,6 +188,7 @@ fn spawn_splash_screen(
 	mut commands: Commands,
 	ortho_size: Res<OrthoSize>,
 	asset_server: Res<AssetServer>,
+	settings: Res<Settings>,
 ) {
 	commands
 		.spawn((SpriteBundle {
@@ -185,7 +206,7 @@ fn spawn_splash_screen(
 		.spawn((Text2dBundle {
 			transform: Transform::from_xyz(-300.0, -250.0, 10.0,),
 			text_anchor: bevy::sprite::Anchor::CenterRight,
-			text: Text::from_section(format!("SoysCodingCafe \nOgelnac \nIQuick143 "), get_splash_text_style(&asset_server))
+			text: Text::from_section("SoysCodingCafe \nOgelnac \nIQuick143 ", get_splash_text_style(&asset_server, &settings))
 				.with_alignment(TextAlignment::Right),
 			..Default::default()
 		},
@@ -196,10 +217,12 @@ fn spawn_splash_screen(
 		.spawn((Text2dBundle {
 			transform: Transform::from_xyz(-300.0, -250.0, 10.0,),
 			text_anchor: bevy::sprite::Anchor::CenterLeft,
-			text: Text::from_section(format!(" Lead Programmer, Designer, Manager, and Producer.\n Lead Artist and Audio Engineer.\n Programmer, Quality Assurance, and Playtester."), get_splash_text_style(&asset_server))
+			text: Text::from_section("", get_splash_text_style(&asset_server, &settings))
 				.with_alignment(TextAlignment::Left),
 			..Default::default()
 		},
+		// Filled in once the string tables have loaded
+		LocalizedText("splash.roles"),
 		DespawnOnExitGameState,
 		Name::new("Splash Text")
 	));
@@ -218,13 +241,21 @@ fn spawn_splash_screen(
 }
 
 // Fade transitions into menu after a certain amount 
-// of time or when the user clicks
+// of time or when the user clicks, once all game data is loaded
 fn advance_splash_screen(
 	mouse: Res<Input<MouseButton>>,
 	time: Res<Time>,
+	levels: Levels,
+	registry: Res<MoleculeRegistry>,
+	reactions: Res<ReactionTable>,
+	locale: Res<Locale>,
 	mut boot_timer: ResMut<BootTimer>,
 	mut ev_w_fade_transition: EventWriter<FadeTransitionEvent>,
 ) {
+	// Hold on the splash screen until the level, molecule, reaction and string files are ready
+	if !levels.finished_loading() || !registry.is_loaded() || !reactions.is_loaded() || !locale.is_loaded() {
+		return;
+	}
 	boot_timer.0.tick(time.delta());
 	if mouse.just_pressed(MouseButton::Left) || boot_timer.0.just_finished() {
 		ev_w_fade_transition.send(FadeTransitionEvent(GameState::Menu));